@@ -41,7 +41,8 @@ macro_rules! new_full_start {
 				Ok(sc_client::LongestChain::new(backend.clone()))
 			})?
 			.with_transaction_pool(|config, client, _fetcher| {
-				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone())
+					.with_log_target(config.log_target);
 				let pool = sc_transaction_pool::BasicPool::new(config, pool_api);
 				Ok(pool)
 			})?
@@ -206,7 +207,8 @@ pub fn new_light<C: Send + Default + 'static>(config: Configuration<C, GenesisCo
 			let fetcher = fetcher
 				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
 
-			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
+			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone())
+				.with_log_target(config.log_target);
 			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
 				config, pool_api, sc_transaction_pool::RevalidationType::Light,
 			);
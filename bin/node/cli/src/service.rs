@@ -62,8 +62,10 @@ macro_rules! new_full_start {
 				Ok(sc_client::LongestChain::new(backend.clone()))
 			})?
 			.with_transaction_pool(|config, client, _fetcher| {
-				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone());
+				let pool_api = sc_transaction_pool::FullChainApi::new(client.clone())
+					.with_log_target(config.log_target);
 				let pool = sc_transaction_pool::BasicPool::new(config, pool_api);
+				pool.set_metrics_sink(Arc::new(sc_service::GrafanaMetricsSink::default()));
 				Ok(pool)
 			})?
 			.with_import_queue(|_config, client, mut select_chain, _transaction_pool| {
@@ -310,7 +312,8 @@ pub fn new_light<C: Send + Default + 'static>(config: NodeConfiguration<C>)
 		.with_transaction_pool(|config, client, fetcher| {
 			let fetcher = fetcher
 				.ok_or_else(|| "Trying to start light transaction pool without active fetcher")?;
-			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone());
+			let pool_api = sc_transaction_pool::LightChainApi::new(client.clone(), fetcher.clone())
+				.with_log_target(config.log_target);
 			let pool = sc_transaction_pool::BasicPool::with_revalidation_type(
 				config, pool_api, sc_transaction_pool::RevalidationType::Light,
 			);
@@ -139,10 +139,22 @@ impl From<Error> for rpc::Error {
 				message: "Cycle Detected".into(),
 				data: None,
 			},
-			Error::Pool(PoolError::ImmediatelyDropped) => rpc::Error {
+			Error::Pool(PoolError::ImmediatelyDropped { exceeded, current, limit, current_bytes, limit_bytes }) => rpc::Error {
 				code: rpc::ErrorCode::ServerError(POOL_IMMEDIATELY_DROPPED),
 				message: "Immediately Dropped".into(),
-				data: Some("The transaction couldn't enter the pool because of the limit".into()),
+				data: Some(format!(
+					"The transaction couldn't enter the pool because of the {} limit \
+					({} of {} transactions, {} of {} bytes)",
+					exceeded, current, limit, current_bytes, limit_bytes,
+				).into()),
+			},
+			Error::Pool(PoolError::WeightLimitExceeded { current, limit }) => rpc::Error {
+				code: rpc::ErrorCode::ServerError(POOL_IMMEDIATELY_DROPPED),
+				message: "Immediately Dropped".into(),
+				data: Some(format!(
+					"The transaction couldn't enter the pool because of the weight limit ({} of {})",
+					current, limit,
+				).into()),
 			},
 			Error::UnsupportedKeyType => rpc::Error {
 				code: rpc::ErrorCode::ServerError(UNSUPPORTED_KEY_TYPE),
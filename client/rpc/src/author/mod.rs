@@ -171,7 +171,7 @@ where
 	}
 
 	fn watch_extrinsic(&self,
-		_metadata: Self::Metadata,
+		metadata: Self::Metadata,
 		subscriber: Subscriber<TransactionStatus<TxHash<P>, BlockHash<P>>>,
 		xt: Bytes,
 	) {
@@ -179,9 +179,13 @@ where
 			let best_block_hash = self.client.chain_info().best_hash;
 			let dxt = TransactionFor::<P>::decode(&mut &xt[..])
 				.map_err(error::Error::from)?;
+			let at = generic::BlockId::hash(best_block_hash);
+			let submission = match metadata.source_id() {
+				Some(source_id) => self.pool.submit_and_watch_from_source(&at, source_id, dxt),
+				None => self.pool.submit_and_watch(&at, dxt),
+			};
 			Ok(
-				self.pool
-					.submit_and_watch(&generic::BlockId::hash(best_block_hash), dxt)
+				submission
 					.map_err(|e| e.into_pool_error()
 						.map(error::Error::from)
 						.unwrap_or_else(|e| error::Error::Verification(Box::new(e)).into())
@@ -15,11 +15,14 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 //! RPC Metadata
-use std::sync::Arc;
+use std::sync::{Arc, atomic::{AtomicU64, Ordering}};
 
 use jsonrpc_pubsub::{Session, PubSubMetadata};
 use rpc::futures::sync::mpsc;
 
+/// Source of unique session identifiers.
+static NEXT_SESSION_ID: AtomicU64 = AtomicU64::new(0);
+
 /// RPC Metadata.
 ///
 /// Manages persistent session for transports that support it
@@ -28,6 +31,7 @@ use rpc::futures::sync::mpsc;
 #[derive(Default, Clone)]
 pub struct Metadata {
 	session: Option<Arc<Session>>,
+	session_id: Option<u64>,
 }
 
 impl rpc::Metadata for Metadata {}
@@ -42,9 +46,18 @@ impl Metadata {
 	pub fn new(transport: mpsc::Sender<String>) -> Self {
 		Metadata {
 			session: Some(Arc::new(Session::new(transport))),
+			session_id: Some(NEXT_SESSION_ID.fetch_add(1, Ordering::Relaxed)),
 		}
 	}
 
+	/// Returns an identifier of the connection (session) the request came from.
+	///
+	/// The identifier is assigned when the session is created and is never reused
+	/// by other sessions. Returns `None` for transports without sessions.
+	pub fn source_id(&self) -> Option<String> {
+		self.session_id.map(|id| format!("session-{}", id))
+	}
+
 	/// Create new `Metadata` for tests.
 	#[cfg(test)]
 	pub fn new_test() -> (mpsc::Receiver<String>, Self) {
@@ -909,6 +909,23 @@ ServiceBuilder<
 			let _ = to_spawn_tx.unbounded_send(Box::pin(select(events, exit.clone()).map(drop)));
 		}
 
+		{
+			// finality notifications
+			let txpool = Arc::downgrade(&transaction_pool);
+			let to_spawn_tx_ = to_spawn_tx.clone();
+
+			let events = client.finality_notification_stream()
+				.for_each(move |notification| {
+					if let Some(txpool) = txpool.upgrade() {
+						let future = txpool.on_block_finalized(notification.hash);
+						let _ = to_spawn_tx_.unbounded_send(Box::pin(future));
+					}
+
+					ready(())
+				});
+			let _ = to_spawn_tx.unbounded_send(Box::pin(select(events, exit.clone()).map(drop)));
+		}
+
 		{
 			// extrinsic notifications
 			let network = Arc::downgrade(&network);
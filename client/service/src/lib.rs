@@ -25,6 +25,7 @@ pub mod chain_ops;
 pub mod error;
 
 mod builder;
+mod metrics;
 mod status_sinks;
 
 use std::{io, pin::Pin};
@@ -60,6 +61,7 @@ pub use self::builder::{
 	TFullCallExecutor, TLightCallExecutor,
 };
 pub use config::{Configuration, Roles, PruningMode};
+pub use self::metrics::GrafanaMetricsSink;
 pub use sc_chain_spec::{ChainSpec, Properties, RuntimeGenesis, Extension as ChainSpecExtension};
 pub use sp_transaction_pool::{TransactionPool, InPoolTransaction, error::IntoPoolError};
 pub use sc_transaction_pool::txpool::Options as TransactionPoolOptions;
@@ -589,8 +591,7 @@ where
 	H: std::hash::Hash + Eq + sp_runtime::traits::Member + sp_runtime::traits::MaybeSerialize,
 	E: IntoPoolError + From<sp_transaction_pool::error::Error>,
 {
-	pool.ready()
-		.filter(|t| t.is_propagateable())
+	pool.ready_propagable()
 		.map(|t| {
 			let hash = t.hash().clone();
 			let ex: B::Extrinsic = t.data().clone();
@@ -0,0 +1,51 @@
+// Copyright 2019-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Transaction pool metrics recorded to the Grafana data source.
+
+use std::collections::HashMap;
+use parking_lot::Mutex;
+use grafana_data_source::record_metrics;
+use sc_transaction_pool::MetricsSink;
+
+/// Metrics sink recording the transaction pool metrics to the Grafana data source.
+///
+/// The data source keeps plain time series, so counters are recorded as running totals
+/// and every histogram observation is recorded as is.
+#[derive(Default)]
+pub struct GrafanaMetricsSink {
+	counters: Mutex<HashMap<&'static str, u64>>,
+}
+
+impl MetricsSink for GrafanaMetricsSink {
+	fn inc_counter(&self, name: &'static str, value: u64) {
+		let total = {
+			let mut counters = self.counters.lock();
+			let counter = counters.entry(name).or_insert(0);
+			*counter = counter.saturating_add(value);
+			*counter
+		};
+		let _ = record_metrics!(name => total,);
+	}
+
+	fn set_gauge(&self, name: &'static str, value: u64) {
+		let _ = record_metrics!(name => value,);
+	}
+
+	fn observe_histogram(&self, name: &'static str, value: f64) {
+		let _ = record_metrics!(name => value,);
+	}
+}
@@ -19,10 +19,12 @@
 //! For a more full-featured pool, have a look at the `pool` module.
 
 use std::{
-	collections::HashSet,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
+	mem,
 	sync::Arc,
+	time,
 };
 
 use log::{trace, debug, warn};
@@ -34,7 +36,9 @@ use sp_runtime::transaction_validity::{
 	TransactionLongevity as Longevity,
 	TransactionPriority as Priority,
 };
-use sp_transaction_pool::{error, PoolStatus, InPoolTransaction};
+use sp_transaction_pool::{
+	error, FutureStatus, PoolStatus, InPoolTransaction, SourceCounts, TransactionSource, TransactionWeight as Weight,
+};
 
 use crate::future::{FutureTransactions, WaitingTransaction};
 use crate::ready::ReadyTransactions;
@@ -94,6 +98,8 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub hash: Hash,
 	/// Transaction priority (higher = better)
 	pub priority: Priority,
+	/// Priority reported by the runtime, before normalization.
+	pub runtime_priority: Priority,
 	/// At which block the transaction becomes invalid?
 	pub valid_till: Longevity,
 	/// Tags required by the transaction.
@@ -102,6 +108,16 @@ pub struct Transaction<Hash, Extrinsic> {
 	pub provides: Vec<Tag>,
 	/// Should that transaction be propagated.
 	pub propagate: bool,
+	/// Time when the transaction was submitted to the pool.
+	pub submitted_at: time::Instant,
+	/// Source of the transaction.
+	pub source: TransactionSource,
+	/// Estimated weight of the transaction.
+	pub weight: Weight,
+	/// Time the most recent validation of the transaction took.
+	pub validation_time: time::Duration,
+	/// Index of the type limit the transaction counts against (see `BasePool::with_type_limits`).
+	pub type_index: Option<usize>,
 }
 
 impl<Hash, Extrinsic> AsRef<Extrinsic> for Transaction<Hash, Extrinsic> {
@@ -141,6 +157,32 @@ impl<Hash, Extrinsic> InPoolTransaction for Transaction<Hash, Extrinsic> {
 	fn is_propagateable(&self) -> bool {
 		self.propagate
 	}
+
+	fn validation_time(&self) -> time::Duration {
+		self.validation_time
+	}
+}
+
+impl<Hash, Extrinsic> Transaction<Hash, Extrinsic> {
+	/// Returns approximate amount of memory the transaction occupies in the pool.
+	///
+	/// The estimate consists of:
+	/// - the encoded length of the extrinsic,
+	/// - the size of the transaction structure itself (metadata),
+	/// - the contents of all `requires` and `provides` tags,
+	/// - an index entry (tag and hash) per each `provides` tag and per each `requires` tag,
+	///   since queues keep reverse lookups for both.
+	///
+	/// Allocator overhead and hash map load factor are not taken into account.
+	pub fn memory_footprint(&self) -> usize {
+		let tag_size = |tag: &Tag| mem::size_of::<Tag>() + tag.len();
+		let index_entry = |tag: &Tag| tag_size(tag) + mem::size_of::<Hash>();
+
+		mem::size_of::<Self>()
+			+ self.bytes
+			+ self.requires.iter().map(|tag| tag_size(tag) + index_entry(tag)).sum::<usize>()
+			+ self.provides.iter().map(|tag| tag_size(tag) + index_entry(tag)).sum::<usize>()
+	}
 }
 
 impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
@@ -155,10 +197,16 @@ impl<Hash: Clone, Extrinsic: Clone> Transaction<Hash, Extrinsic> {
 			bytes: self.bytes.clone(),
 			hash: self.hash.clone(),
 			priority: self.priority.clone(),
+			runtime_priority: self.runtime_priority,
 			valid_till: self.valid_till.clone(),
 			requires: self.requires.clone(),
 			provides: self.provides.clone(),
 			propagate: self.propagate,
+			submitted_at: self.submitted_at,
+			source: self.source,
+			weight: self.weight,
+			validation_time: self.validation_time,
+			type_index: self.type_index,
 		}
 	}
 }
@@ -185,6 +233,10 @@ impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic> where
 		write!(fmt, "valid_till: {:?}, ", &self.valid_till)?;
 		write!(fmt, "bytes: {:?}, ", &self.bytes)?;
 		write!(fmt, "propagate: {:?}, ", &self.propagate)?;
+		write!(fmt, "submitted_at: {:?}, ", &self.submitted_at)?;
+		write!(fmt, "source: {:?}, ", &self.source)?;
+		write!(fmt, "weight: {:?}, ", &self.weight)?;
+		write!(fmt, "validation_time: {:?}, ", &self.validation_time)?;
 		write!(fmt, "requires: [")?;
 		print_tags(fmt, &self.requires)?;
 		write!(fmt, "], provides: [")?;
@@ -199,6 +251,106 @@ impl<Hash, Extrinsic> fmt::Debug for Transaction<Hash, Extrinsic> where
 /// Store last pruned tags for given number of invocations.
 const RECENTLY_PRUNED_TAGS: usize = 2;
 
+/// Length of the `provides` tag prefix identifying the sender.
+///
+/// Standard runtimes provide `(AccountId, Index)` as the first tag, so the first 32 bytes
+/// are the account id. Shorter tags are used as a whole.
+const SENDER_TAG_LEN: usize = 32;
+
+/// Number of transactions per sender.
+///
+/// The sender is derived from the first `provides` tag (see `SENDER_TAG_LEN`),
+/// transactions that don't provide any tags are not accounted.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SenderIndex {
+	senders: HashMap<Tag, usize>,
+}
+
+impl SenderIndex {
+	fn sender_tag<Hash, Ex>(tx: &Transaction<Hash, Ex>) -> Option<Tag> {
+		tx.provides.first().map(|tag| tag[..tag.len().min(SENDER_TAG_LEN)].to_vec())
+	}
+
+	/// Accounts for given transaction entering the pool.
+	pub fn add<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(sender) = Self::sender_tag(tx) {
+			*self.senders.entry(sender).or_default() += 1;
+		}
+	}
+
+	/// Accounts for given transaction leaving the pool.
+	pub fn remove<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(sender) = Self::sender_tag(tx) {
+			let remove = match self.senders.get_mut(&sender) {
+				Some(count) => {
+					*count -= 1;
+					*count == 0
+				},
+				None => false,
+			};
+			if remove {
+				self.senders.remove(&sender);
+			}
+		}
+	}
+
+	/// Returns number of distinct senders.
+	pub fn len(&self) -> usize {
+		self.senders.len()
+	}
+
+	/// Returns `true` if there are transactions of given sender.
+	pub fn contains(&self, sender: &Tag) -> bool {
+		self.senders.contains_key(sender)
+	}
+
+	/// Returns tags of all senders.
+	pub fn tags(&self) -> impl Iterator<Item=&Tag> {
+		self.senders.keys()
+	}
+}
+
+/// Number of transactions per type (see `BasePool::with_type_limits`).
+///
+/// Transactions without a type are not accounted.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TypeCounts {
+	counts: HashMap<usize, usize>,
+}
+
+impl TypeCounts {
+	/// Accounts for given transaction entering the queue.
+	pub fn add<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(index) = tx.type_index {
+			*self.counts.entry(index).or_default() += 1;
+		}
+	}
+
+	/// Accounts for given transaction leaving the queue.
+	pub fn remove<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		if let Some(index) = tx.type_index {
+			let remove = match self.counts.get_mut(&index) {
+				Some(count) => {
+					*count -= 1;
+					*count == 0
+				},
+				None => false,
+			};
+			if remove {
+				self.counts.remove(&index);
+			}
+		}
+	}
+
+	/// Returns number of transactions of given type.
+	pub fn get(&self, index: usize) -> usize {
+		self.counts.get(&index).cloned().unwrap_or(0)
+	}
+}
+
+/// Identifier of a bundle of transactions (see `BasePool::set_bundle`).
+pub type BundleId = [u8; 32];
+
 /// Transaction pool.
 ///
 /// Builds a dependency graph for all transactions in the pool and returns
@@ -212,6 +364,12 @@ const RECENTLY_PRUNED_TAGS: usize = 2;
 #[derive(Debug)]
 pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	reject_future_transactions: bool,
+	/// Maximal length of a dependency chain in the future queue.
+	max_future_chain_depth: Option<usize>,
+	/// Reject future transactions forming a dependency cycle.
+	reject_dependency_cycles: bool,
+	/// Prefixes and maximal numbers of transactions of each type.
+	type_limits: Vec<(Vec<u8>, usize)>,
 	future: FutureTransactions<Hash, Ex>,
 	ready: ReadyTransactions<Hash, Ex>,
 	/// Store recently pruned tags (for last two invocations).
@@ -220,6 +378,17 @@ pub struct BasePool<Hash: hash::Hash + Eq, Ex> {
 	/// transactions to future in case they were just stuck in verification.
 	recently_pruned: [HashSet<Tag>; RECENTLY_PRUNED_TAGS],
 	recently_pruned_index: usize,
+	/// Transactions that are never evicted because of the pool limits.
+	pinned: HashSet<Hash>,
+	/// Ready transactions taken out for external block building.
+	///
+	/// Shared with the iterators returned by `ready`, modified copy-on-write.
+	reserved: Arc<HashSet<Hash>>,
+	/// Bundle of each bundled transaction (see `set_bundle`).
+	bundles: HashMap<Hash, BundleId>,
+	/// Number of transactions in each bundle.
+	bundle_sizes: HashMap<BundleId, usize>,
+	log_target: &'static str,
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for BasePool<Hash, Ex> {
@@ -231,15 +400,62 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> Default for Bas
 impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash, Ex> {
 	/// Create new pool given reject_future_transactions flag.
 	pub fn new(reject_future_transactions: bool) -> Self {
+		Self::with_log_target(reject_future_transactions, crate::DEFAULT_LOG_TARGET)
+	}
+
+	/// Create new pool given reject_future_transactions flag and a log target.
+	pub fn with_log_target(reject_future_transactions: bool, log_target: &'static str) -> Self {
 		BasePool {
 			reject_future_transactions,
+			max_future_chain_depth: None,
+			reject_dependency_cycles: false,
+			type_limits: Vec::new(),
 			future: Default::default(),
-			ready: Default::default(),
+			ready: ReadyTransactions::with_log_target(log_target),
 			recently_pruned: Default::default(),
 			recently_pruned_index: 0,
+			pinned: Default::default(),
+			reserved: Default::default(),
+			bundles: Default::default(),
+			bundle_sizes: Default::default(),
+			log_target,
 		}
 	}
 
+	/// Limits the length of dependency chains formed by transactions in the future queue.
+	///
+	/// Future transactions that would form a longer chain are rejected with
+	/// `Error::ChainTooDeep`.
+	pub fn with_max_future_chain_depth(mut self, max_future_chain_depth: Option<usize>) -> Self {
+		self.max_future_chain_depth = max_future_chain_depth;
+		self
+	}
+
+	/// Makes transactions providing the same tags as ready transactions with equal priority
+	/// replace them, instead of being rejected with `Error::TooLowPriority`.
+	///
+	/// Resubmissions of transactions that are already in the pool are rejected with
+	/// `Error::AlreadyImported` regardless.
+	pub fn with_replace_equal_priority(mut self, replace_equal_priority: bool) -> Self {
+		self.ready.set_replace_equal_priority(replace_equal_priority);
+		self
+	}
+
+	/// Limits the number of transactions of each type in the pool.
+	///
+	/// The type of a transaction is given by `Transaction::type_index`, indexing `type_limits`.
+	/// Transactions exceeding the limit of their type are rejected with `Error::TypeLimitExceeded`.
+	pub fn with_type_limits(mut self, type_limits: Vec<(Vec<u8>, usize)>) -> Self {
+		self.type_limits = type_limits;
+		self
+	}
+
+	/// Rejects future transactions forming a dependency cycle with `Error::DependencyCycle`.
+	pub fn with_reject_dependency_cycles(mut self, reject_dependency_cycles: bool) -> Self {
+		self.reject_dependency_cycles = reject_dependency_cycles;
+		self
+	}
+
 	/// Temporary enables future transactions, runs closure and then restores
 	/// `reject_future_transactions` flag back to previous value.
 	///
@@ -264,18 +480,33 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		&mut self,
 		tx: Transaction<Hash, Ex>,
 	) -> error::Result<Imported<Hash, Ex>> {
+		self.import_keeping_failed(tx).map(|(imported, _)| imported)
+	}
+
+	/// Same as `import`, but also returns the transactions that failed to be promoted.
+	///
+	/// See `Imported::Ready::failed`, such transactions are otherwise discarded.
+	fn import_keeping_failed(
+		&mut self,
+		tx: Transaction<Hash, Ex>,
+	) -> error::Result<(Imported<Hash, Ex>, Vec<Arc<Transaction<Hash, Ex>>>)> {
 		if self.future.contains(&tx.hash) || self.ready.contains(&tx.hash) {
 			return Err(error::Error::AlreadyImported(Box::new(tx.hash.clone())))
 		}
+		self.check_type_limit(&tx)?;
+		// a reservation doesn't survive the transaction leaving the pool
+		if self.reserved.contains(&tx.hash) {
+			Arc::make_mut(&mut self.reserved).remove(&tx.hash);
+		}
 
 		let tx = WaitingTransaction::new(
 			tx,
 			self.ready.provided_tags(),
 			&self.recently_pruned,
 		);
-		trace!(target: "txpool", "[{:?}] {:?}", tx.transaction.hash, tx);
+		trace!(target: self.log_target, "[{:?}] {:?}", tx.transaction.hash, tx);
 		debug!(
-			target: "txpool",
+			target: self.log_target,
 			"[{:?}] Importing to {}",
 			tx.transaction.hash,
 			if tx.is_ready() { "ready" } else { "future" }
@@ -287,21 +518,40 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				return Err(error::Error::RejectedFutureTransaction);
 			}
 
+			if self.reject_dependency_cycles && self.future.creates_cycle(&tx) {
+				return Err(error::Error::DependencyCycle);
+			}
+
+			if let Some(max) = self.max_future_chain_depth {
+				let depth = self.future.chain_depth(&tx);
+				if depth > max {
+					return Err(error::Error::ChainTooDeep { depth, max });
+				}
+			}
+
 			let hash = tx.transaction.hash.clone();
 			self.future.import(tx);
-			return Ok(Imported::Future { hash });
+			return Ok((Imported::Future { hash }, Vec::new()));
 		}
 
-		self.import_to_ready(tx)
+		self.import_to_ready_keeping_failed(tx)
 	}
 
 	/// Imports transaction to ready queue.
 	///
 	/// NOTE the transaction has to have all requirements satisfied.
 	fn import_to_ready(&mut self, tx: WaitingTransaction<Hash, Ex>) -> error::Result<Imported<Hash, Ex>> {
+		self.import_to_ready_keeping_failed(tx).map(|(imported, _)| imported)
+	}
+
+	fn import_to_ready_keeping_failed(
+		&mut self,
+		tx: WaitingTransaction<Hash, Ex>,
+	) -> error::Result<(Imported<Hash, Ex>, Vec<Arc<Transaction<Hash, Ex>>>)> {
 		let hash = tx.transaction.hash.clone();
 		let mut promoted = vec![];
 		let mut failed = vec![];
+		let mut failed_transactions = vec![];
 		let mut removed = vec![];
 
 		let mut first = true;
@@ -318,7 +568,8 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			to_import.append(&mut self.future.satisfy_tags(&tx.transaction.provides));
 
 			// import this transaction
-			let current_hash = tx.transaction.hash.clone();
+			let current = tx.transaction.clone();
+			let current_hash = current.hash.clone();
 			match self.ready.import(tx) {
 				Ok(mut replaced) => {
 					if !first {
@@ -329,10 +580,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 				},
 				// transaction failed to be imported.
 				Err(e) => if first {
-					debug!(target: "txpool", "[{:?}] Error importing: {:?}", current_hash, e);
+					debug!(target: self.log_target, "[{:?}] Error importing: {:?}", current_hash, e);
 					return Err(e)
 				} else {
 					failed.push(current_hash);
+					failed_transactions.push(current);
 				},
 			}
 			first = false;
@@ -348,21 +600,147 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			// since they depend on each other and will never get to the best iterator.
 			self.ready.remove_subtree(&promoted);
 
-			debug!(target: "txpool", "[{:?}] Cycle detected, bailing.", hash);
+			debug!(target: self.log_target, "[{:?}] Cycle detected, bailing.", hash);
 			return Err(error::Error::CycleDetected)
 		}
 
-		Ok(Imported::Ready {
+		Ok((Imported::Ready {
 			hash,
 			promoted,
 			failed,
 			removed,
-		})
+		}, failed_transactions))
 	}
 
 	/// Returns an iterator over ready transactions in the pool.
+	///
+	/// Reserved transactions (and the ones depending on them) are skipped.
 	pub fn ready(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
-		self.ready.get()
+		self.ready.get_excluding(self.reserved.clone())
+	}
+
+	/// Returns an iterator over ready transactions ordered by priority per encoded byte.
+	///
+	/// Dependencies are respected the same way as in `ready`, reserved transactions are skipped.
+	pub fn ready_by_fee_density(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
+		self.ready.get_by_fee_density(self.reserved.clone())
+	}
+
+	/// Returns ready transactions shuffled deterministically by given seed.
+	///
+	/// Dependencies are respected the same way as in `ready`, reserved transactions are skipped.
+	pub fn ready_shuffled(&self, seed: [u8; 32]) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		self.ready.get_shuffled(self.reserved.clone(), seed)
+	}
+
+	/// Returns an iterator over ready transactions that should be propagated to other peers.
+	///
+	/// Non-propagable transactions still have to be visited, since they may unlock
+	/// propagable ones, but they are never yielded.
+	pub fn ready_propagable(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
+		self.ready.get().filter(|tx| tx.propagate)
+	}
+
+	/// Returns an iterator over the best ready transactions with total weight up to `max`.
+	///
+	/// The iteration stops at the first transaction that doesn't fit, since skipping it
+	/// could yield transactions that depend on it.
+	pub fn ready_within_weight(&self, max: Weight) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
+		let mut total: Weight = 0;
+		self.ready().take_while(move |tx| {
+			total = total.saturating_add(tx.weight);
+			total <= max
+		})
+	}
+
+	/// Returns the best ready transactions with total size up to `max_bytes` and total
+	/// weight up to `max_weight`.
+	///
+	/// Just like `ready_within_weight` the list ends at the first transaction that
+	/// exceeds any of the budgets.
+	///
+	/// Bundles (see `set_bundle`) are all-or-nothing: all their members are placed together
+	/// once the last of them is reached. The list ends at a transaction requiring a tag
+	/// provided by a bundle member that is not placed yet, or at a bundle that doesn't fit
+	/// as a whole. Bundles some members of which are not ready are never placed.
+	pub fn next_block_candidates(&self, max_bytes: usize, max_weight: Weight) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let provided_tags = self.ready.provided_tags();
+		let mut pending = HashMap::<BundleId, Vec<Arc<Transaction<Hash, Ex>>>>::new();
+		let mut included = HashSet::new();
+		let mut total_bytes = 0usize;
+		let mut total_weight: Weight = 0;
+		let mut candidates = Vec::new();
+		for tx in self.ready() {
+			let unit = match self.bundles.get(&tx.hash) {
+				None => vec![tx],
+				Some(id) => {
+					let members = pending.entry(*id).or_insert_with(Vec::new);
+					members.push(tx);
+					if members.len() < self.bundle_sizes.get(id).cloned().unwrap_or(0) {
+						continue;
+					}
+					pending.remove(id).unwrap_or_default()
+				},
+			};
+
+			// all requirements have to be provided by already placed transactions
+			let mut satisfied = true;
+			for (index, tx) in unit.iter().enumerate() {
+				let is_provided = |tag| match provided_tags.get(tag) {
+					None => true,
+					Some(provider) => included.contains(provider)
+						|| unit[..index].iter().any(|member| member.hash == *provider),
+				};
+				if !tx.requires.iter().all(is_provided) {
+					satisfied = false;
+					break;
+				}
+				total_bytes = total_bytes.saturating_add(tx.bytes);
+				total_weight = total_weight.saturating_add(tx.weight);
+			}
+			if !satisfied || total_bytes > max_bytes || total_weight > max_weight {
+				break;
+			}
+			included.extend(unit.iter().map(|tx| tx.hash.clone()));
+			candidates.extend(unit);
+		}
+		candidates
+	}
+
+	/// Makes given transactions a bundle, which is only picked by `next_block_candidates`
+	/// as a whole.
+	///
+	/// Bundles of transactions that left the pool are forgotten when tags are pruned,
+	/// a bundle some members of which left the pool is never picked.
+	pub fn set_bundle(&mut self, hashes: &[Hash], id: BundleId) {
+		self.forget_dead_bundles();
+		self.bundles.extend(hashes.iter().map(|hash| (hash.clone(), id)));
+		self.bundle_sizes.insert(id, hashes.len());
+	}
+
+	/// Forgets bundle membership of transactions that left the pool.
+	///
+	/// Sizes of bundles are kept as long as any of their members is in the pool.
+	fn forget_dead_bundles(&mut self) {
+		if self.bundles.is_empty() {
+			return;
+		}
+		let (ready, future) = (&self.ready, &self.future);
+		self.bundles.retain(|hash, _| ready.contains(hash) || future.contains(hash));
+		let alive = self.bundles.values().cloned().collect::<HashSet<_>>();
+		self.bundle_sizes.retain(|id, _| alive.contains(id));
+	}
+
+	/// Returns the bundle given transaction belongs to, if any.
+	pub fn bundle_of(&self, hash: &Hash) -> Option<BundleId> {
+		self.bundles.get(hash).cloned()
+	}
+
+	/// Returns an iterator over all transactions in the pool.
+	///
+	/// Ready transactions (including the reserved ones) go first, followed by future ones.
+	pub fn all(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> + '_ {
+		self.ready.get().chain(self.future.all_shared().cloned())
 	}
 
 	/// Returns an iterator over future transactions in the pool.
@@ -370,6 +748,35 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.future.all()
 	}
 
+	/// Same as `futures`, but returns the shared transactions.
+	pub fn futures_shared(&self) -> impl Iterator<Item=&Arc<Transaction<Hash, Ex>>> {
+		self.future.all_shared()
+	}
+
+	/// Returns hashes of all transactions in the pool together with their required
+	/// and provided tags.
+	///
+	/// Meant for inspection of dependencies between transactions.
+	pub fn tag_graph(&self) -> Vec<(Hash, Vec<Tag>, Vec<Tag>)> {
+		self.ready.get()
+			.map(|tx| (tx.hash.clone(), tx.requires.clone(), tx.provides.clone()))
+			.chain(self.future.all().map(|tx| (tx.hash.clone(), tx.requires.clone(), tx.provides.clone())))
+			.collect()
+	}
+
+	/// Returns up to `limit` transactions that spent the longest time in the pool.
+	///
+	/// Includes both ready and future pool. Transactions are sorted by ascending `submitted_at`.
+	/// There is no index by submission time, so all transactions are visited.
+	pub fn oldest_pending(&self, limit: usize) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let mut all = self.ready()
+			.chain(self.future.all_shared().cloned())
+			.collect::<Vec<_>>();
+		all.sort_by_key(|tx| tx.submitted_at);
+		all.truncate(limit);
+		all
+	}
+
 	/// Returns pool transactions given list of hashes.
 	///
 	/// Includes both ready and future pool. For every hash in the `hashes`
@@ -390,58 +797,270 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.ready.by_hash(hash)
 	}
 
+	/// Checks that the pool doesn't hold too many transactions of the same type as `tx` already.
+	///
+	/// Ready transactions providing any of the tags of `tx` are not counted, since `tx` would
+	/// replace them.
+	fn check_type_limit(&self, tx: &Transaction<Hash, Ex>) -> error::Result<()> {
+		let index = match tx.type_index {
+			Some(index) => index,
+			None => return Ok(()),
+		};
+		let (prefix, limit) = match self.type_limits.get(index) {
+			Some((prefix, limit)) => (prefix, *limit),
+			None => return Ok(()),
+		};
+
+		let provided_tags = self.ready.provided_tags();
+		let replaced = tx.provides.iter()
+			.filter_map(|tag| provided_tags.get(tag))
+			.collect::<HashSet<_>>()
+			.into_iter()
+			.filter_map(|hash| self.ready.by_hash(hash))
+			.filter(|existing| existing.type_index == Some(index))
+			.count();
+		let current = (self.ready.type_count(index) + self.future.type_count(index)).saturating_sub(replaced);
+		if current >= limit {
+			return Err(error::Error::TypeLimitExceeded { prefix: prefix.clone(), current, limit });
+		}
+		Ok(())
+	}
+
 	/// Makes sure that the transactions in the queues stay within provided limits.
 	///
 	/// Removes and returns worst transactions from the queues and all transactions that depend on them.
 	/// Technically the worst transaction should be evaluated by computing the entire pending set.
 	/// We use a simplified approach to remove the transaction that occupies the pool for the longest time.
-	pub fn enforce_limits(&mut self, ready: &Limit, future: &Limit) -> Vec<Arc<Transaction<Hash, Ex>>> {
+	///
+	/// The ready queue limit is additionally partitioned between local and external transactions
+	/// according to `quotas`, every partition is enforced separately before the global limit.
+	/// Transactions in `protect` (and the ones they depend on) are not evicted, same as pinned ones.
+	pub fn enforce_limits(
+		&mut self,
+		ready: &Limit,
+		future: &Limit,
+		quotas: &SourceQuotas,
+		max_weight: Option<Weight>,
+		protect: &[Hash],
+	) -> Vec<(Arc<Transaction<Hash, Ex>>, error::Error)> {
 		let mut removed = vec![];
+		let protected = self.protected(protect);
+		let mut totals = self.totals(quotas.is_partitioned());
+
+		if let Some(max_weight) = max_weight {
+			while totals.weight > max_weight {
+				let weight = totals.weight;
+				match self.worst_ready(&protected, |_| true) {
+					Some(hash) => {
+						let error = || error::Error::WeightLimitExceeded { current: weight, limit: max_weight };
+						self.evict(&hash, error, &mut totals, &mut removed);
+					},
+					None => break,
+				}
+			}
+		}
 
-		while ready.is_exceeded(self.ready.len(), self.ready.bytes()) {
-			// find the worst transaction
-			let minimal = self.ready
-				.fold(|minimal, current| {
-					let transaction = &current.transaction;
-					match minimal {
-						None => Some(transaction.clone()),
-						Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
-							Some(transaction.clone())
+		if quotas.is_partitioned() {
+			for &(is_local, fraction) in &[(true, quotas.local), (false, quotas.external)] {
+				let limit = ready.share(fraction);
+				let exceeded = if is_local { error::ExceededLimit::LocalQuota } else { error::ExceededLimit::ExternalQuota };
+				loop {
+					let (count, bytes) = totals.partition(is_local);
+					if !limit.is_exceeded(count, bytes) {
+						break;
+					}
+					match self.worst_ready(&protected, |tx| SourceQuotas::is_local(tx.source) == is_local) {
+						Some(hash) => {
+							let error = || limit.exceeded(exceeded, count, bytes);
+							self.evict(&hash, error, &mut totals, &mut removed);
 						},
-						other => other,
+						None => break,
 					}
-				});
+				}
+			}
+		}
 
-			if let Some(minimal) = minimal {
-				removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
-			} else {
+		loop {
+			let (count, bytes) = totals.ready;
+			if !ready.is_exceeded(count, bytes) {
 				break;
 			}
+			match self.worst_ready(&protected, |_| true) {
+				Some(hash) => {
+					let error = || ready.exceeded(error::ExceededLimit::Ready, count, bytes);
+					self.evict(&hash, error, &mut totals, &mut removed);
+				},
+				None => break,
+			}
 		}
 
-		while future.is_exceeded(self.future.len(), self.future.bytes()) {
-			// find the worst transaction
-			let minimal = self.future
-				.fold(|minimal, current| {
-					match minimal {
-						None => Some(current.clone()),
-						Some(ref tx) if tx.imported_at > current.imported_at => {
-							Some(current.clone())
-						},
-						other => other,
-					}
-				});
-
-			if let Some(minimal) = minimal {
-				removed.append(&mut self.remove_subtree(&[minimal.transaction.hash.clone()]))
-			} else {
+		loop {
+			let (count, bytes) = totals.future;
+			if !future.is_exceeded(count, bytes) {
 				break;
 			}
+			match self.worst_future(&protected) {
+				Some(hash) => {
+					let error = || future.exceeded(error::ExceededLimit::Future, count, bytes);
+					self.evict(&hash, error, &mut totals, &mut removed);
+				},
+				None => break,
+			}
 		}
 
 		removed
 	}
 
+	/// Returns the totals the limits are enforced against.
+	///
+	/// Totals of local ready transactions are only computed if `partitioned`.
+	fn totals(&mut self, partitioned: bool) -> Totals {
+		let local = if partitioned {
+			self.ready
+				.fold(|acc, current| {
+					let (count, bytes) = acc.unwrap_or((0, 0));
+					let transaction: &Transaction<Hash, Ex> = &current.transaction.transaction;
+					if SourceQuotas::is_local(transaction.source) {
+						Some((count + 1, bytes + transaction.memory_footprint()))
+					} else {
+						Some((count, bytes))
+					}
+				})
+				.unwrap_or((0, 0))
+		} else {
+			(0, 0)
+		};
+		Totals {
+			ready: (self.ready.len(), self.ready.bytes()),
+			future: (self.future.len(), self.future.bytes()),
+			local,
+			weight: self.ready.weight(),
+		}
+	}
+
+	/// Returns the worst ready transaction that is not protected and matches `filter`.
+	fn worst_ready(
+		&mut self,
+		protected: &HashSet<Hash>,
+		filter: impl Fn(&Transaction<Hash, Ex>) -> bool,
+	) -> Option<Hash> {
+		self.ready
+			.fold(|minimal, current| {
+				let transaction = &current.transaction;
+				match minimal {
+					_ if !filter(&*transaction.transaction) => minimal,
+					_ if protected.contains(&transaction.transaction.hash) => minimal,
+					None => Some(transaction.clone()),
+					Some(ref tx) if tx.insertion_id > transaction.insertion_id => {
+						Some(transaction.clone())
+					},
+					other => other,
+				}
+			})
+			.map(|minimal| minimal.transaction.hash.clone())
+	}
+
+	/// Returns the worst future transaction that is not protected.
+	fn worst_future(&mut self, protected: &HashSet<Hash>) -> Option<Hash> {
+		self.future
+			.fold(|minimal, current| {
+				match minimal {
+					_ if protected.contains(&current.transaction.hash) => minimal,
+					None => Some(current.clone()),
+					Some(ref tx) if tx.imported_at > current.imported_at => {
+						Some(current.clone())
+					},
+					other => other,
+				}
+			})
+			.map(|minimal| minimal.transaction.hash.clone())
+	}
+
+	/// Removes given transaction and all transactions depending on it, reporting `error` for each of them.
+	fn evict(
+		&mut self,
+		hash: &Hash,
+		error: impl Fn() -> error::Error,
+		totals: &mut Totals,
+		removed: &mut Vec<(Arc<Transaction<Hash, Ex>>, error::Error)>,
+	) {
+		let ready = self.ready.remove_subtree(&[hash.clone()]);
+		ready.iter().for_each(|tx| totals.remove(tx));
+		let future = self.future.remove(&[hash.clone()]);
+		totals.future.0 -= future.len();
+		totals.future.1 -= future.iter().map(|tx| tx.memory_footprint()).sum::<usize>();
+		removed.extend(ready.into_iter().chain(future).map(|tx| (tx, error())));
+	}
+
+	/// Pins a transaction, so that it's never evicted because of the pool limits.
+	///
+	/// Pinned transactions are still pruned or removed as usual.
+	/// Returns `false` if the transaction is not in the pool.
+	pub fn pin(&mut self, hash: &Hash) -> bool {
+		if !self.ready.contains(hash) && !self.future.contains(hash) {
+			return false;
+		}
+		self.pinned.insert(hash.clone());
+		true
+	}
+
+	/// Unpins a transaction.
+	///
+	/// Returns `false` if the transaction was not pinned.
+	pub fn unpin(&mut self, hash: &Hash) -> bool {
+		self.pinned.remove(hash)
+	}
+
+	/// Reserves up to `limit` best ready transactions.
+	///
+	/// Reserved transactions stay in the pool, but are no longer returned by `ready`
+	/// until released. Reservations of transactions that left the pool are forgotten.
+	pub fn reserve_ready(&mut self, limit: usize) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let reserved = self.ready().take(limit).collect::<Vec<_>>();
+		let ready = &self.ready;
+		let set = Arc::make_mut(&mut self.reserved);
+		set.retain(|hash| ready.contains(hash));
+		set.extend(reserved.iter().map(|tx| tx.hash.clone()));
+		reserved
+	}
+
+	/// Releases reserved transactions, so that they are returned by `ready` again.
+	///
+	/// Returns number of transactions that were reserved.
+	pub fn release_reserved(&mut self, hashes: &[Hash]) -> usize {
+		if !hashes.iter().any(|hash| self.reserved.contains(hash)) {
+			return 0
+		}
+		let reserved = Arc::make_mut(&mut self.reserved);
+		hashes.iter().filter(|hash| reserved.remove(*hash)).count()
+	}
+
+	/// Returns pinned and `extra` transactions together with all ready transactions they depend on.
+	///
+	/// Evicting a ready dependency would remove the pinned transaction as well.
+	/// Pins of transactions that left the pool are forgotten.
+	fn protected(&mut self, extra: &[Hash]) -> HashSet<Hash> {
+		let ready = &self.ready;
+		let future = &self.future;
+		self.pinned.retain(|hash| ready.contains(hash) || future.contains(hash));
+
+		let mut protected = HashSet::new();
+		let mut to_visit = self.pinned.iter().chain(extra).cloned().collect::<Vec<_>>();
+		while let Some(hash) = to_visit.pop() {
+			if !protected.insert(hash.clone()) {
+				continue;
+			}
+			let transaction = self.ready.by_hash(&hash)
+				.or_else(|| self.future.by_hashes(&[hash.clone()]).pop().and_then(|tx| tx));
+			if let Some(transaction) = transaction {
+				to_visit.extend(transaction.requires.iter()
+					.filter_map(|tag| self.ready.provided_tags().get(tag).cloned())
+				);
+			}
+		}
+		protected
+	}
+
 	/// Removes all transactions represented by the hashes and all other transactions
 	/// that depend on them.
 	///
@@ -456,6 +1075,32 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		removed
 	}
 
+	/// Moves transaction with given hash from the future queue to the ready queue,
+	/// as if all its requirements were satisfied.
+	///
+	/// NOTE this bypasses dependency checks and is meant to be used for testing only.
+	pub fn force_promote(&mut self, hash: &Hash) -> error::Result<Imported<Hash, Ex>> {
+		let transaction = self.future.remove(&[hash.clone()])
+			.pop()
+			.ok_or(error::Error::NotInFutureQueue)?;
+		let waiting = WaitingTransaction {
+			transaction: transaction.clone(),
+			missing_tags: Default::default(),
+			imported_at: time::Instant::now(),
+		};
+
+		self.import_to_ready(waiting).map_err(|e| {
+			// put the transaction back where it was
+			let waiting = WaitingTransaction::new(
+				transaction.duplicate(),
+				self.ready.provided_tags(),
+				&self.recently_pruned,
+			);
+			self.future.import(waiting);
+			e
+		})
+	}
+
 	/// Removes and returns all transactions from the future queue.
 	pub fn clear_future(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.future.clear()
@@ -490,12 +1135,14 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			match self.import_to_ready(tx) {
 				Ok(res) => promoted.push(res),
 				Err(e) => {
-					warn!(target: "txpool", "[{:?}] Failed to promote during pruning: {:?}", hash, e);
+					warn!(target: self.log_target, "[{:?}] Failed to promote during pruning: {:?}", hash, e);
 					failed.push(hash)
 				},
 			}
 		}
 
+		self.forget_dead_bundles();
+
 		PruneStatus {
 			pruned,
 			failed,
@@ -512,6 +1159,193 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 			future_bytes: self.future.bytes(),
 		}
 	}
+
+	/// Get aggregate statistics of the future queue.
+	pub fn future_status(&self) -> FutureStatus {
+		FutureStatus {
+			count: self.future.len(),
+			bytes: self.future.bytes(),
+			distinct_senders: self.future.distinct_senders(),
+			oldest_age: self.future.oldest_submitted_at().map(|at| at.elapsed()),
+		}
+	}
+
+	/// Returns number of ready and future transactions per source.
+	pub fn count_by_source(&self) -> SourceCounts {
+		self.ready.count_by_source().combine(self.future.count_by_source())
+	}
+
+	/// Updates priorities of ready transactions and reorders the ready queue accordingly.
+	pub fn update_ready_priorities(&mut self, priority: impl FnMut(&Transaction<Hash, Ex>) -> Priority)
+		where Ex: Clone
+	{
+		self.ready.update_priorities(priority)
+	}
+
+	/// Returns number of distinct senders of ready and future transactions.
+	///
+	/// Senders are derived from the first `provides` tag, assuming that it encodes
+	/// the sender, as it does in standard runtimes.
+	pub fn distinct_senders(&self) -> usize {
+		let ready = self.ready.senders();
+		ready.len() + self.future.senders().tags().filter(|tag| !ready.contains(tag)).count()
+	}
+
+	/// Returns sender tags of ready and future transactions (see `distinct_senders`).
+	pub fn sender_tags(&self) -> Vec<Tag> {
+		let ready = self.ready.senders();
+		ready.tags()
+			.chain(self.future.senders().tags().filter(|tag| !ready.contains(tag)))
+			.cloned()
+			.collect()
+	}
+}
+
+impl<Hash: hash::Hash + Member + Serialize, Ex: Clone + std::fmt::Debug> BasePool<Hash, Ex> {
+	/// Imports a batch of transactions to the pool atomically.
+	///
+	/// Either all transactions are imported or none of them. In case any import fails
+	/// the transactions imported so far are removed, transactions that they have replaced,
+	/// promoted or failed to promote are put back and the index of the failing transaction
+	/// is returned together with the error.
+	pub fn import_atomic(
+		&mut self,
+		txs: Vec<Transaction<Hash, Ex>>,
+	) -> Result<Vec<Imported<Hash, Ex>>, (usize, error::Error)> {
+		let mut imported = Vec::with_capacity(txs.len());
+		let mut discarded = Vec::new();
+		for (index, tx) in txs.into_iter().enumerate() {
+			match self.import_keeping_failed(tx) {
+				Ok((result, failed)) => {
+					imported.push(result);
+					discarded.extend(failed);
+				},
+				Err(e) => {
+					debug!(target: self.log_target, "Atomic import failed at {}: {:?}. Rolling back.", index, e);
+					self.rollback(imported, discarded);
+					return Err((index, e))
+				},
+			}
+		}
+
+		Ok(imported)
+	}
+
+	/// Changes source of a ready or future transaction.
+	///
+	/// Returns `false` if the transaction is not in the pool or already has given source.
+	pub fn set_source(&mut self, hash: &Hash, source: TransactionSource) -> bool {
+		self.ready.set_source(hash, source) || self.future.set_source(hash, source)
+	}
+
+	/// Reverts given imports.
+	///
+	/// Removes imported transactions and re-imports all transactions that have been
+	/// replaced by them or removed together with them, as well as the `discarded` ones
+	/// that failed to be promoted.
+	fn rollback(&mut self, imported: Vec<Imported<Hash, Ex>>, discarded: Vec<Arc<Transaction<Hash, Ex>>>) {
+		let batch = imported.iter().map(|result| result.hash().clone()).collect::<HashSet<_>>();
+		let hashes = batch.iter().cloned().collect::<Vec<_>>();
+		let removed = self.remove_subtree(&hashes);
+
+		// replaced transactions go first, since other removed transactions may depend on them
+		let mut to_restore = Vec::new();
+		for result in imported {
+			if let Imported::Ready { removed, .. } = result {
+				to_restore.extend(removed);
+			}
+		}
+		to_restore.extend(removed);
+		to_restore.extend(discarded);
+
+		self.with_futures_enabled(|pool, _| {
+			for tx in to_restore.into_iter().filter(|tx| !batch.contains(&tx.hash)) {
+				let hash = tx.hash.clone();
+				if let Err(e) = pool.reimport(tx) {
+					warn!(target: pool.log_target, "[{:?}] Failed to restore during rollback: {:?}", hash, e);
+				}
+			}
+		});
+	}
+
+	/// Replaces a transaction with another one.
+	///
+	/// `old` is removed together with ready transactions depending on it, then `tx` is imported
+	/// and the dependent transactions are put back. In case the import fails the pool is
+	/// restored and the error is returned. `old` doesn't have to be in the pool.
+	pub fn replace(&mut self, old: &Hash, tx: Transaction<Hash, Ex>) -> error::Result<Replaced<Hash, Ex>> {
+		let removed = self.remove_subtree(&[old.clone()]);
+		let imported = match self.import(tx) {
+			Ok(imported) => imported,
+			Err(e) => {
+				debug!(target: self.log_target, "[{:?}] Replacement failed: {:?}. Rolling back.", old, e);
+				self.with_futures_enabled(|pool, _| {
+					for tx in removed {
+						let hash = tx.hash.clone();
+						if let Err(e) = pool.reimport(tx) {
+							warn!(target: pool.log_target, "[{:?}] Failed to restore during rollback: {:?}", hash, e);
+						}
+					}
+				});
+				return Err(e);
+			},
+		};
+
+		let (replaced, dependents) = removed.into_iter().partition::<Vec<_>, _>(|tx| tx.hash == *old);
+		let (restored, mut removed) = self.restore(dependents);
+		removed.extend(replaced);
+
+		Ok(Replaced {
+			imported,
+			restored,
+			removed,
+		})
+	}
+
+	/// Imports transactions that have been removed from the pool back.
+	///
+	/// Transactions with unsatisfied requirements are put to the future queue, even if
+	/// future transactions are rejected otherwise. Returns results of the successful imports
+	/// and the transactions that couldn't be imported.
+	pub fn restore(
+		&mut self,
+		txs: Vec<Arc<Transaction<Hash, Ex>>>,
+	) -> (Vec<Imported<Hash, Ex>>, Vec<Arc<Transaction<Hash, Ex>>>) {
+		let mut restored = Vec::new();
+		let mut failed = Vec::new();
+		self.with_futures_enabled(|pool, _| {
+			for tx in txs {
+				match pool.reimport(tx.clone()) {
+					Ok(result) => restored.push(result),
+					Err(e) => {
+						debug!(target: pool.log_target, "[{:?}] Failed to restore: {:?}", tx.hash, e);
+						failed.push(tx);
+					},
+				}
+			}
+		});
+		(restored, failed)
+	}
+
+	/// Imports a transaction that has been removed from the pool back.
+	fn reimport(&mut self, tx: Arc<Transaction<Hash, Ex>>) -> error::Result<Imported<Hash, Ex>> {
+		let tx = match Arc::try_unwrap(tx) {
+			Ok(tx) => tx,
+			Err(tx) => tx.duplicate(),
+		};
+		self.import(tx)
+	}
+}
+
+/// Result of a transaction replacement.
+#[derive(Debug)]
+pub struct Replaced<Hash, Ex> {
+	/// Result of the import of the new transaction.
+	pub imported: Imported<Hash, Ex>,
+	/// Results of the imports of transactions that depended on the replaced one.
+	pub restored: Vec<Imported<Hash, Ex>>,
+	/// Replaced transaction and dependent transactions that couldn't be imported back.
+	pub removed: Vec<Arc<Transaction<Hash, Ex>>>,
 }
 
 /// Queue limits
@@ -519,7 +1353,9 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 pub struct Limit {
 	/// Maximal number of transactions in the queue.
 	pub count: usize,
-	/// Maximal size of encodings of all transactions in the queue.
+	/// Maximal approximate memory occupied by all transactions in the queue.
+	///
+	/// See `Transaction::memory_footprint` for the accounting model.
 	pub total_bytes: usize,
 }
 
@@ -528,11 +1364,134 @@ impl Limit {
 	pub fn is_exceeded(&self, count: usize, bytes: usize) -> bool {
 		self.count < count || self.total_bytes < bytes
 	}
+
+	/// Returns the error of a transaction dropped because `current` values exceed this limit.
+	pub fn exceeded(&self, exceeded: error::ExceededLimit, count: usize, bytes: usize) -> error::Error {
+		error::Error::ImmediatelyDropped {
+			exceeded,
+			current: count,
+			limit: self.count,
+			current_bytes: bytes,
+			limit_bytes: self.total_bytes,
+		}
+	}
+
+	/// Returns the limit extended by given fraction of it.
+	pub fn extended(&self, fraction: f32) -> Limit {
+		let fraction = f64::from(fraction.max(0.0));
+		Limit {
+			count: self.count + (self.count as f64 * fraction) as usize,
+			total_bytes: self.total_bytes + (self.total_bytes as f64 * fraction) as usize,
+		}
+	}
+
+	/// Returns given fraction of the limit.
+	pub fn share(&self, fraction: f32) -> Limit {
+		let fraction = f64::from(fraction.max(0.0).min(1.0));
+		Limit {
+			count: (self.count as f64 * fraction) as usize,
+			total_bytes: (self.total_bytes as f64 * fraction) as usize,
+		}
+	}
+}
+
+/// Partitioning of the ready queue limits between transaction sources.
+///
+/// Both values are fractions (`0.0..=1.0`) of the ready queue limits. Transactions coming from
+/// `TransactionSource::Local` count towards `local` share, all other transactions
+/// (including the ones resubmitted from retracted blocks) count towards `external` share.
+/// The global ready limit is enforced on top of the partitions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourceQuotas {
+	/// Fraction of the ready limit available for local transactions.
+	pub local: f32,
+	/// Fraction of the ready limit available for external transactions.
+	pub external: f32,
+}
+
+impl Default for SourceQuotas {
+	fn default() -> Self {
+		SourceQuotas {
+			local: 1.0,
+			external: 1.0,
+		}
+	}
+}
+
+impl SourceQuotas {
+	/// Returns true if any of the sources is restricted to a part of the ready limit.
+	pub fn is_partitioned(&self) -> bool {
+		self.local < 1.0 || self.external < 1.0
+	}
+
+	fn is_local(source: TransactionSource) -> bool {
+		source == TransactionSource::Local
+	}
+}
+
+/// Running totals of the queues, kept up to date while evicting transactions.
+#[derive(Debug, Default)]
+struct Totals {
+	/// Number and approximate memory of ready transactions.
+	ready: (usize, usize),
+	/// Number and approximate memory of future transactions.
+	future: (usize, usize),
+	/// Number and approximate memory of local ready transactions.
+	local: (usize, usize),
+	/// Total weight of ready transactions.
+	weight: Weight,
+}
+
+impl Totals {
+	/// Returns the totals of ready transactions in local or external partition.
+	fn partition(&self, is_local: bool) -> (usize, usize) {
+		if is_local {
+			self.local
+		} else {
+			(self.ready.0 - self.local.0, self.ready.1 - self.local.1)
+		}
+	}
+
+	/// Updates the totals after given ready transaction got evicted.
+	fn remove<Hash, Ex>(&mut self, tx: &Transaction<Hash, Ex>) {
+		let bytes = tx.memory_footprint();
+		self.ready.0 -= 1;
+		self.ready.1 -= bytes;
+		if SourceQuotas::is_local(tx.source) {
+			self.local.0 = self.local.0.saturating_sub(1);
+			self.local.1 = self.local.1.saturating_sub(bytes);
+		}
+		self.weight = self.weight.saturating_sub(tx.weight);
+	}
+}
+
+/// Creates a test transaction with given hash, default priority and no tags.
+///
+/// Tests override the fields they care about with struct update syntax.
+#[cfg(test)]
+pub(crate) fn tx(hash: u64) -> Transaction<u64, Vec<u8>> {
+	Transaction {
+		data: vec![hash as u8],
+		bytes: 1,
+		hash,
+		priority: 5u64,
+		runtime_priority: 5u64,
+		valid_till: 64u64,
+		requires: vec![],
+		provides: vec![],
+		propagate: true,
+		submitted_at: time::Instant::now(),
+		source: TransactionSource::External,
+		weight: 0,
+		validation_time: Default::default(),
+		type_index: None,
+	}
 }
 
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use assert_matches::assert_matches;
 
 	type Hash = u64;
 
@@ -540,6 +1499,55 @@ mod tests {
 		BasePool::default()
 	}
 
+	#[test]
+	fn should_forget_bundles_of_pruned_transactions() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			provides: vec![vec![1]],
+			..tx(1)
+		}).unwrap();
+		pool.set_bundle(&[1, 2], [1; 32]);
+		assert_eq!(pool.bundle_of(&1), Some([1; 32]));
+
+		// when
+		pool.prune_tags(vec![vec![1]]);
+
+		// then
+		assert_eq!(pool.bundle_of(&1), None);
+		assert!(pool.bundles.is_empty());
+		assert!(pool.bundle_sizes.is_empty());
+	}
+
+	#[test]
+	fn should_keep_track_of_transaction_types() {
+		// given
+		let mut pool = pool().with_type_limits(vec![(vec![1], 1)]);
+		let tx = |hash: u64, requires: Vec<Vec<u8>>| Transaction {
+			data: vec![1u8],
+			requires,
+			provides: vec![vec![hash as u8]],
+			type_index: Some(0),
+			..tx(hash)
+		};
+		pool.import(tx(1, vec![])).unwrap();
+
+		// when
+		let err = pool.import(tx(2, vec![vec![10]])).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::TypeLimitExceeded { current: 1, limit: 1, .. });
+
+		// when
+		pool.remove_subtree(&[1]);
+
+		// then
+		pool.import(tx(2, vec![vec![10]])).unwrap();
+		assert_eq!(pool.future.len(), 1);
+		let err = pool.import(tx(3, vec![])).unwrap_err();
+		assert_matches!(err, error::Error::TypeLimitExceeded { current: 1, limit: 1, .. });
+	}
+
 	#[test]
 	fn should_import_transaction_to_ready() {
 		// given
@@ -547,14 +1555,8 @@ mod tests {
 
 		// when
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1u64,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 
 		// then
@@ -562,6 +1564,195 @@ mod tests {
 		assert_eq!(pool.ready.len(), 1);
 	}
 
+	#[test]
+	fn should_only_return_propagable_ready_transactions() {
+		// given
+		let mut pool = pool();
+		pool.import(Transaction {
+			provides: vec![vec![1]],
+			propagate: false,
+			..tx(1)
+		}).unwrap();
+		pool.import(Transaction {
+			requires: vec![vec![1]],
+			provides: vec![vec![2]],
+			..tx(2)
+		}).unwrap();
+
+		// when
+		let propagable = pool.ready_propagable().map(|tx| tx.hash).collect::<Vec<_>>();
+
+		// then
+		assert_eq!(pool.ready().count(), 2);
+		assert_eq!(propagable, vec![2]);
+	}
+
+	#[test]
+	fn should_count_transactions_by_source() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, requires: Vec<Tag>, source| Transaction {
+			requires,
+			provides: vec![vec![hash as u8]],
+			source,
+			..tx(hash)
+		};
+
+		// when
+		pool.import(tx(1, vec![], TransactionSource::Local)).unwrap();
+		pool.import(tx(3, vec![vec![2]], TransactionSource::External)).unwrap();
+
+		// then
+		assert_eq!(pool.count_by_source(), SourceCounts { local: 1, external: 1, in_block: 0 });
+
+		// when
+		pool.import(tx(2, vec![vec![1]], TransactionSource::InBlock)).unwrap();
+
+		// then
+		assert_eq!(pool.future.len(), 0);
+		assert_eq!(pool.count_by_source(), SourceCounts { local: 1, external: 1, in_block: 1 });
+
+		// when
+		pool.prune_tags(vec![vec![1]]);
+
+		// then
+		assert_eq!(pool.count_by_source(), SourceCounts { local: 0, external: 1, in_block: 1 });
+
+		// when
+		pool.remove_subtree(&[2]);
+
+		// then
+		assert_eq!(pool.count_by_source(), SourceCounts::default());
+	}
+
+	#[test]
+	fn should_exclude_reserved_transactions_from_ready() {
+		// given
+		let mut pool = pool();
+		let tx = |hash: u64, priority: u64, requires: Vec<Tag>, provides: Vec<Tag>| Transaction {
+			priority,
+			runtime_priority: priority,
+			requires,
+			provides,
+			..tx(hash)
+		};
+		pool.import(tx(1, 10, vec![], vec![vec![1]])).unwrap();
+		pool.import(tx(2, 5, vec![vec![1]], vec![vec![2]])).unwrap();
+		pool.import(tx(3, 1, vec![], vec![vec![3]])).unwrap();
+
+		// when
+		let reserved = pool.reserve_ready(1);
+
+		// then
+		assert_eq!(reserved.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+		assert_eq!(pool.status().ready, 3);
+		assert_eq!(pool.reserve_ready(2).iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![3]);
+		assert_eq!(pool.ready().count(), 0);
+
+		// when
+		assert_eq!(pool.release_reserved(&[1, 3, 4]), 2);
+
+		// then
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1, 2, 3]);
+	}
+
+	#[test]
+	fn should_count_distinct_senders() {
+		// given
+		let mut pool = pool();
+		let tag = |sender: u8, nonce: u8| {
+			let mut tag = vec![sender; 32];
+			tag.push(nonce);
+			tag
+		};
+		let tx = |sender: u8, nonce: u8| Transaction {
+			data: vec![sender, nonce],
+			requires: if nonce > 0 { vec![tag(sender, nonce - 1)] } else { vec![] },
+			provides: vec![tag(sender, nonce)],
+			..tx(u64::from(sender) * 10 + u64::from(nonce))
+		};
+
+		// when
+		pool.import(tx(1, 0)).unwrap();
+		pool.import(tx(1, 2)).unwrap();
+		pool.import(tx(2, 0)).unwrap();
+
+		// then
+		assert_eq!(pool.future.len(), 1);
+		assert_eq!(pool.distinct_senders(), 2);
+
+		// when
+		pool.import(tx(1, 1)).unwrap();
+
+		// then
+		assert_eq!(pool.future.len(), 0);
+		assert_eq!(pool.distinct_senders(), 2);
+
+		// when
+		pool.remove_subtree(&[20]);
+
+		// then
+		assert_eq!(pool.distinct_senders(), 1);
+		assert_eq!(pool.sender_tags(), vec![vec![1u8; 32]]);
+
+		// when
+		pool.prune_tags(vec![tag(1, 2)]);
+
+		// then
+		assert_eq!(pool.distinct_senders(), 0);
+	}
+
+	#[test]
+	fn should_report_future_status() {
+		// given
+		let mut pool = pool();
+		let tag = |sender: u8, nonce: u8| {
+			let mut tag = vec![sender; 32];
+			tag.push(nonce);
+			tag
+		};
+		let tx = |sender: u8, nonce: u8| Transaction {
+			data: vec![sender, nonce],
+			requires: if nonce > 0 { vec![tag(sender, nonce - 1)] } else { vec![] },
+			provides: vec![tag(sender, nonce)],
+			..tx(u64::from(sender) * 10 + u64::from(nonce))
+		};
+		assert!(pool.future_status().oldest_age.is_none());
+
+		// when
+		pool.import(tx(1, 0)).unwrap();
+		pool.import(tx(1, 2)).unwrap();
+		pool.import(tx(1, 3)).unwrap();
+		pool.import(tx(2, 1)).unwrap();
+
+		// then
+		let status = pool.future_status();
+		assert_eq!(status.count, 3);
+		assert_eq!(status.bytes, pool.status().future_bytes);
+		assert_eq!(status.distinct_senders, 2);
+		assert!(status.oldest_age.is_some());
+
+		// when
+		pool.import(tx(1, 1)).unwrap();
+
+		// then
+		let status = pool.future_status();
+		assert_eq!(status.count, 1);
+		assert_eq!(status.bytes, tx(2, 1).memory_footprint());
+		assert_eq!(status.distinct_senders, 1);
+
+		// when
+		pool.clear_future();
+
+		// then
+		let status = pool.future_status();
+		assert_eq!(status.count, 0);
+		assert_eq!(status.bytes, 0);
+		assert_eq!(status.distinct_senders, 0);
+		assert!(status.oldest_age.is_none());
+	}
+
 	#[test]
 	fn should_not_import_same_transaction_twice() {
 		// given
@@ -569,24 +1760,12 @@ mod tests {
 
 		// when
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap_err();
 
 		// then
@@ -602,26 +1781,15 @@ mod tests {
 
 		// when
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![0]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 
 		// then
@@ -636,57 +1804,31 @@ mod tests {
 
 		// when
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![3u8],
-			bytes: 1,
-			hash: 3,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![2]],
-			provides: vec![],
-			propagate: true,
+			..tx(3)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![4u8],
-			bytes: 1,
-			hash: 4,
 			priority: 1_000u64,
-			valid_till: 64u64,
+			runtime_priority: 1_000u64,
 			requires: vec![vec![3], vec![4]],
-			provides: vec![],
-			propagate: true,
+			..tx(4)
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
 
 		let res = pool.import(Transaction {
-			data: vec![5u8],
-			bytes: 1,
-			hash: 5,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![0], vec![4]],
-			propagate: true,
+			..tx(5)
 		}).unwrap();
 
 		// then
@@ -711,38 +1853,23 @@ mod tests {
 		// given
 		let mut pool = pool();
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![3u8],
-			bytes: 1,
-			hash: 3,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
-			propagate: true,
+			..tx(3)
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
 
 		// when
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 
 		// then
@@ -755,14 +1882,10 @@ mod tests {
 
 		// let's close the cycle with one additional transaction
 		let res = pool.import(Transaction {
-			data: vec![4u8],
-			bytes: 1,
-			hash: 4,
 			priority: 50u64,
-			valid_till: 64u64,
-			requires: vec![],
+			runtime_priority: 50u64,
 			provides: vec![vec![0]],
-			propagate: true,
+			..tx(4)
 		}).unwrap();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), Some(4));
@@ -783,38 +1906,23 @@ mod tests {
 		// given
 		let mut pool = pool();
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![3u8],
-			bytes: 1,
-			hash: 3,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
-			propagate: true,
+			..tx(3)
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 0);
 		assert_eq!(pool.ready.len(), 0);
 
 		// when
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![0]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 
 		// then
@@ -827,14 +1935,10 @@ mod tests {
 
 		// let's close the cycle with one additional transaction
 		let err = pool.import(Transaction {
-			data: vec![4u8],
-			bytes: 1,
-			hash: 4,
 			priority: 1u64, // lower priority than Tx(2)
-			valid_till: 64u64,
-			requires: vec![],
+			runtime_priority: 1u64,
 			provides: vec![vec![0]],
-			propagate: true,
+			..tx(4)
 		}).unwrap_err();
 		let mut it = pool.ready().into_iter().map(|tx| tx.data[0]);
 		assert_eq!(it.next(), None);
@@ -851,65 +1955,35 @@ mod tests {
 		// given
 		let mut pool = pool();
 		pool.import(Transaction {
-			data: vec![5u8],
-			bytes: 1,
-			hash: 5,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![0], vec![4]],
-			propagate: true,
+			..tx(5)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![3u8],
-			bytes: 1,
-			hash: 3,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![2]],
-			provides: vec![],
-			propagate: true,
+			..tx(3)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![3], vec![2]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![4u8],
-			bytes: 1,
-			hash: 4,
 			priority: 1_000u64,
-			valid_till: 64u64,
+			runtime_priority: 1_000u64,
 			requires: vec![vec![3], vec![4]],
-			provides: vec![],
-			propagate: true,
+			..tx(4)
 		}).unwrap();
 		// future
 		pool.import(Transaction {
-			data: vec![6u8],
-			bytes: 1,
-			hash: 6,
 			priority: 1_000u64,
-			valid_till: 64u64,
+			runtime_priority: 1_000u64,
 			requires: vec![vec![11]],
-			provides: vec![],
-			propagate: true,
+			..tx(6)
 		}).unwrap();
 		assert_eq!(pool.ready().count(), 5);
 		assert_eq!(pool.future.len(), 1);
@@ -928,55 +2002,31 @@ mod tests {
 		let mut pool = pool();
 		// future (waiting for 0)
 		pool.import(Transaction {
-			data: vec![5u8],
-			bytes: 1,
-			hash: 5,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
 			provides: vec![vec![100]],
-			propagate: true,
+			..tx(5)
 		}).unwrap();
 		// ready
 		pool.import(Transaction {
-			data: vec![1u8],
-			bytes: 1,
-			hash: 1,
-			priority: 5u64,
-			valid_till: 64u64,
-			requires: vec![],
 			provides: vec![vec![1]],
-			propagate: true,
+			..tx(1)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![2u8],
-			bytes: 1,
-			hash: 2,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![2]],
 			provides: vec![vec![3]],
-			propagate: true,
+			..tx(2)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![3u8],
-			bytes: 1,
-			hash: 3,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![1]],
 			provides: vec![vec![2]],
-			propagate: true,
+			..tx(3)
 		}).unwrap();
 		pool.import(Transaction {
-			data: vec![4u8],
-			bytes: 1,
-			hash: 4,
 			priority: 1_000u64,
-			valid_till: 64u64,
+			runtime_priority: 1_000u64,
 			requires: vec![vec![3], vec![2]],
 			provides: vec![vec![4]],
-			propagate: true,
+			..tx(4)
 		}).unwrap();
 
 		assert_eq!(pool.ready().count(), 4);
@@ -1000,18 +2050,102 @@ mod tests {
 		assert_eq!(pool.ready().count(), 3);
 	}
 
+	#[test]
+	fn should_rollback_atomic_import() {
+		// given
+		let mut pool = pool();
+		// future (waiting for 0)
+		pool.import(Transaction {
+			requires: vec![vec![0]],
+			provides: vec![vec![1]],
+			..tx(1)
+		}).unwrap();
+		// ready, going to be replaced
+		pool.import(Transaction {
+			provides: vec![vec![2]],
+			..tx(2)
+		}).unwrap();
+
+		// when
+		let err = pool.import_atomic(vec![
+			// promotes 1
+			Transaction {
+				provides: vec![vec![0]],
+				..tx(3)
+			},
+			// replaces 2
+			Transaction {
+				priority: 10u64,
+				runtime_priority: 10u64,
+				provides: vec![vec![2]],
+				..tx(4)
+			},
+			// already imported
+			Transaction {
+				data: vec![2u8],
+				requires: vec![vec![0]],
+				provides: vec![vec![1]],
+				..tx(1)
+			},
+		]).unwrap_err();
+
+		// then
+		assert_eq!(err.0, 2);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_restore_transactions_failed_to_promote_on_rollback() {
+		// given
+		let mut pool = pool();
+		// ready, outbids the future one
+		pool.import(Transaction {
+			priority: 10u64,
+			runtime_priority: 10u64,
+			provides: vec![vec![5]],
+			..tx(5)
+		}).unwrap();
+		// future (waiting for 0), conflicting with the ready one
+		pool.import(Transaction {
+			priority: 1u64,
+			runtime_priority: 1u64,
+			requires: vec![vec![0]],
+			provides: vec![vec![5]],
+			..tx(6)
+		}).unwrap();
+
+		// when
+		let err = pool.import_atomic(vec![
+			// fails to promote 6
+			Transaction {
+				provides: vec![vec![0]],
+				..tx(7)
+			},
+			// already imported
+			Transaction {
+				priority: 10u64,
+				runtime_priority: 10u64,
+				provides: vec![vec![5]],
+				..tx(5)
+			},
+		]).unwrap_err();
+
+		// then
+		assert_eq!(err.0, 1);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![5]);
+		assert_eq!(pool.futures().map(|tx| tx.hash).collect::<Vec<_>>(), vec![6]);
+	}
+
 	#[test]
 	fn transaction_debug() {
 		assert_eq!(
 			format!("{:?}", Transaction {
-				data: vec![4u8],
-				bytes: 1,
-				hash: 4,
 				priority: 1_000u64,
-				valid_till: 64u64,
+				runtime_priority: 1_000u64,
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
-				propagate: true,
+				..tx(4)
 			}),
 			"Transaction { \
 hash: 4, priority: 1000, valid_till: 64, bytes: 1, propagate: true, \
@@ -1022,25 +2156,20 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 	#[test]
 	fn transaction_propagation() {
 		assert_eq!(Transaction {
-				data: vec![4u8],
-				bytes: 1,
-				hash: 4,
 				priority: 1_000u64,
-				valid_till: 64u64,
+				runtime_priority: 1_000u64,
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
-				propagate: true,
+				..tx(4)
 		}.is_propagateable(), true);
 
 		assert_eq!(Transaction {
-				data: vec![4u8],
-				bytes: 1,
-				hash: 4,
 				priority: 1_000u64,
-				valid_till: 64u64,
+				runtime_priority: 1_000u64,
 				requires: vec![vec![3], vec![2]],
 				provides: vec![vec![4]],
 				propagate: false,
+				..tx(4)
 		}.is_propagateable(), false);
 	}
 
@@ -1054,14 +2183,8 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 
 		// then
 		let err = pool.import(Transaction {
-			data: vec![5u8],
-			bytes: 1,
-			hash: 5,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
-			provides: vec![],
-			propagate: true,
+			..tx(5)
 		});
 
 		if let Err(error::Error::RejectedFutureTransaction) = err {
@@ -1077,14 +2200,8 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 
 		// when
 		pool.import(Transaction {
-			data: vec![5u8],
-			bytes: 1,
-			hash: 5,
-			priority: 5u64,
-			valid_till: 64u64,
 			requires: vec![vec![0]],
-			provides: vec![],
-			propagate: true,
+			..tx(5)
 		}).unwrap();
 
 		// then
@@ -1106,14 +2223,8 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 		// when
 		let flag_value = pool.with_futures_enabled(|pool, flag| {
 			pool.import(Transaction {
-				data: vec![5u8],
-				bytes: 1,
-				hash: 5,
-				priority: 5u64,
-				valid_till: 64u64,
 				requires: vec![vec![0]],
-				provides: vec![],
-				propagate: true,
+				..tx(5)
 			}).unwrap();
 
 			flag
@@ -1124,4 +2235,86 @@ requires: [03,02], provides: [04], data: [4]}".to_owned()
 		assert_eq!(pool.reject_future_transactions, true);
 		assert_eq!(pool.future.len(), 1);
 	}
+
+	#[test]
+	fn should_reject_transactions_forming_a_dependency_cycle() {
+		// given
+		let mut pool = pool().with_reject_dependency_cycles(true);
+		let tx = |hash: u64, requires: u8, provides: u8| Transaction {
+			requires: vec![vec![requires]],
+			provides: vec![vec![provides]],
+			..tx(hash)
+		};
+		pool.import(tx(1, 1, 2)).unwrap();
+
+		// when
+		let err = pool.import(tx(2, 2, 1)).unwrap_err();
+
+		// then
+		if let error::Error::DependencyCycle = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+		assert_eq!(pool.future.len(), 1);
+		assert_eq!(pool.tag_graph(), vec![(1, vec![vec![1]], vec![vec![2]])]);
+
+		// a transaction requiring its own tag is rejected too
+		let err = pool.import(tx(3, 3, 3)).unwrap_err();
+		if let error::Error::DependencyCycle = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+	}
+
+	fn conflicting(hash: u64) -> Transaction<Hash, Vec<u8>> {
+		Transaction {
+			provides: vec![vec![1]],
+			..tx(hash)
+		}
+	}
+
+	#[test]
+	fn should_reject_identical_resubmission_as_already_imported() {
+		// given
+		let mut pool = pool().with_replace_equal_priority(true);
+		pool.import(conflicting(1)).unwrap();
+
+		// when
+		let err = pool.import(conflicting(1)).unwrap_err();
+
+		// then
+		if let error::Error::AlreadyImported(_) = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+	}
+
+	#[test]
+	fn should_resolve_equal_priority_conflicts_according_to_policy() {
+		// given
+		let mut pool = pool();
+		pool.import(conflicting(1)).unwrap();
+
+		// when
+		let err = pool.import(conflicting(2)).unwrap_err();
+
+		// then
+		if let error::Error::TooLowPriority { old: 5, new: 5 } = err {
+		} else {
+			assert!(false, "Invalid error kind: {:?}", err);
+		}
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![1]);
+
+		// given
+		let mut pool = pool.with_replace_equal_priority(true);
+
+		// when
+		let imported = pool.import(conflicting(2)).unwrap();
+
+		// then
+		assert_eq!(imported.hash(), &2);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![2]);
+		assert!(!pool.ready.contains(&1));
+	}
 }
@@ -0,0 +1,145 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Rolling Bloom filter of recently seen transaction hashes.
+//!
+//! Two generations of fixed-size filters are kept. Once the newer one holds `capacity`
+//! entries, the older one is discarded and a fresh one is started. Any of the `capacity`
+//! most recently inserted hashes is always reported, older ones are eventually forgotten.
+//! False positives are possible.
+
+use std::{
+	collections::hash_map::RandomState,
+	hash::{self, BuildHasher, Hasher},
+	marker::PhantomData,
+	mem,
+};
+
+/// Number of most recently seen hashes that are guaranteed to be reported.
+pub const RECENTLY_SEEN_CAPACITY: usize = 16 * 1024;
+
+/// Number of bits per entry, together with `HASHES` gives about 1% of false positives.
+const BITS_PER_ENTRY: usize = 10;
+
+/// Number of bits set per entry.
+const HASHES: usize = 7;
+
+/// A single generation of the filter.
+struct Filter {
+	bits: Vec<u64>,
+	len: usize,
+}
+
+impl Filter {
+	fn new(bits: usize) -> Self {
+		Filter {
+			bits: vec![0; (bits + 63) / 64],
+			len: 0,
+		}
+	}
+
+	fn set(&mut self, index: usize) {
+		self.bits[index / 64] |= 1 << (index % 64);
+	}
+
+	fn get(&self, index: usize) -> bool {
+		self.bits[index / 64] & (1 << (index % 64)) != 0
+	}
+}
+
+/// Rolling Bloom filter of hashes.
+pub struct RecentlySeen<Hash> {
+	capacity: usize,
+	hasher: RandomState,
+	current: Filter,
+	previous: Filter,
+	_marker: PhantomData<Hash>,
+}
+
+impl<Hash: hash::Hash> Default for RecentlySeen<Hash> {
+	fn default() -> Self {
+		RecentlySeen::new(RECENTLY_SEEN_CAPACITY)
+	}
+}
+
+impl<Hash: hash::Hash> RecentlySeen<Hash> {
+	/// Creates a filter that reports at least `capacity` most recently inserted hashes.
+	pub fn new(capacity: usize) -> Self {
+		let capacity = capacity.max(1);
+		RecentlySeen {
+			capacity,
+			hasher: RandomState::new(),
+			current: Filter::new(capacity * BITS_PER_ENTRY),
+			previous: Filter::new(capacity * BITS_PER_ENTRY),
+			_marker: PhantomData,
+		}
+	}
+
+	/// Inserts given hash.
+	pub fn insert(&mut self, hash: &Hash) {
+		if self.current.len >= self.capacity {
+			let fresh = Filter::new(self.capacity * BITS_PER_ENTRY);
+			self.previous = mem::replace(&mut self.current, fresh);
+		}
+		for index in self.indices(hash) {
+			self.current.set(index);
+		}
+		self.current.len += 1;
+	}
+
+	/// Returns `true` if given hash might have been inserted recently.
+	pub fn contains(&self, hash: &Hash) -> bool {
+		let indices = self.indices(hash);
+		indices.iter().all(|index| self.current.get(*index))
+			|| indices.iter().all(|index| self.previous.get(*index))
+	}
+
+	fn indices(&self, hash: &Hash) -> [usize; HASHES] {
+		// double hashing, see Kirsch & Mitzenmacher "Less Hashing, Same Performance"
+		let mut hasher = self.hasher.build_hasher();
+		hash.hash(&mut hasher);
+		let first = hasher.finish();
+		hasher.write_u8(0);
+		let second = hasher.finish() | 1;
+		let bits = (self.capacity * BITS_PER_ENTRY) as u64;
+		let mut indices = [0; HASHES];
+		for (i, index) in indices.iter_mut().enumerate() {
+			*index = (first.wrapping_add((i as u64).wrapping_mul(second)) % bits) as usize;
+		}
+		indices
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn should_report_recently_inserted_hashes() {
+		// given
+		let mut seen = RecentlySeen::new(10);
+
+		// when
+		for hash in 0..25u64 {
+			seen.insert(&hash);
+		}
+
+		// then
+		assert!((15..25u64).all(|hash| seen.contains(&hash)));
+		// the oldest generation got discarded
+		assert!((0..10u64).any(|hash| !seen.contains(&hash)));
+	}
+}
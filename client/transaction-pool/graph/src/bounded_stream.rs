@@ -0,0 +1,146 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Notification streams with bounded buffer.
+
+use std::{
+	collections::VecDeque,
+	pin::Pin,
+	sync::Arc,
+};
+
+use futures::{
+	Stream,
+	task::{Context, Poll, Waker},
+};
+use parking_lot::Mutex;
+
+struct Shared<T> {
+	queue: VecDeque<T>,
+	capacity: usize,
+	lagged: bool,
+	closed: bool,
+	waker: Option<Waker>,
+}
+
+/// Sending side of the `BoundedStream`.
+pub struct BoundedSink<T> {
+	shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// Stream of notifications with bounded buffer.
+///
+/// When the buffer is full, the oldest notifications are dropped
+/// and the stream is marked as lagged.
+pub struct BoundedStream<T> {
+	shared: Arc<Mutex<Shared<T>>>,
+}
+
+/// Creates a new bounded stream that buffers up to `capacity` notifications.
+pub fn bounded<T>(capacity: usize) -> (BoundedSink<T>, BoundedStream<T>) {
+	let shared = Arc::new(Mutex::new(Shared {
+		queue: VecDeque::with_capacity(capacity.max(1)),
+		capacity: capacity.max(1),
+		lagged: false,
+		closed: false,
+		waker: None,
+	}));
+
+	(BoundedSink { shared: shared.clone() }, BoundedStream { shared })
+}
+
+impl<T> BoundedSink<T> {
+	/// Sends a notification, dropping the oldest buffered one if the buffer is full.
+	///
+	/// Returns `false` if the stream has been dropped.
+	pub fn send(&self, item: T) -> bool {
+		if Arc::strong_count(&self.shared) == 1 {
+			return false;
+		}
+
+		let mut shared = self.shared.lock();
+		if shared.queue.len() >= shared.capacity {
+			shared.queue.pop_front();
+			shared.lagged = true;
+		}
+		shared.queue.push_back(item);
+		if let Some(waker) = shared.waker.take() {
+			waker.wake();
+		}
+		true
+	}
+}
+
+impl<T> Drop for BoundedSink<T> {
+	fn drop(&mut self) {
+		let mut shared = self.shared.lock();
+		shared.closed = true;
+		if let Some(waker) = shared.waker.take() {
+			waker.wake();
+		}
+	}
+}
+
+impl<T> BoundedStream<T> {
+	/// Returns `true` if some notifications were dropped since the previous call.
+	pub fn take_lagged(&self) -> bool {
+		std::mem::replace(&mut self.shared.lock().lagged, false)
+	}
+}
+
+impl<T> Stream for BoundedStream<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<T>> {
+		let mut shared = self.shared.lock();
+		if let Some(item) = shared.queue.pop_front() {
+			return Poll::Ready(Some(item));
+		}
+		if shared.closed {
+			return Poll::Ready(None);
+		}
+		shared.waker = Some(cx.waker().clone());
+		Poll::Pending
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on_stream;
+
+	#[test]
+	fn should_drop_oldest_notifications_when_full() {
+		let (sink, stream) = bounded(2);
+		assert!(sink.send(1));
+		assert!(sink.send(2));
+		assert!(!stream.take_lagged());
+
+		assert!(sink.send(3));
+		assert!(stream.take_lagged());
+		assert!(!stream.take_lagged());
+
+		drop(sink);
+		assert_eq!(block_on_stream(stream).collect::<Vec<_>>(), vec![2, 3]);
+	}
+
+	#[test]
+	fn should_report_dropped_stream() {
+		let (sink, stream) = bounded(2);
+		drop(stream);
+		assert!(!sink.send(1));
+	}
+}
@@ -15,7 +15,7 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::{HashMap, HashSet},
+	collections::{BTreeMap, HashMap, HashSet},
 	fmt,
 	hash,
 	sync::Arc,
@@ -26,8 +26,9 @@ use sp_core::hexdisplay::HexDisplay;
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
 };
+use sp_transaction_pool::{SourceCounts, TransactionSource};
 
-use crate::base_pool::Transaction;
+use crate::base_pool::{SenderIndex, Transaction, TypeCounts};
 
 /// Transaction with partially satisfied dependencies.
 pub struct WaitingTransaction<Hash, Ex> {
@@ -113,15 +114,33 @@ impl<Hash, Ex> WaitingTransaction<Hash, Ex> {
 pub struct FutureTransactions<Hash: hash::Hash + Eq, Ex> {
 	/// tags that are not yet provided by any transaction and we await for them
 	wanted_tags: HashMap<Tag, HashSet<Hash>>,
+	/// tags that are provided by transactions in this queue
+	provided_tags: HashMap<Tag, HashSet<Hash>>,
 	/// Transactions waiting for a particular other transaction
 	waiting: HashMap<Hash, WaitingTransaction<Hash, Ex>>,
+	/// Number of transactions per source
+	counts: SourceCounts,
+	/// Number of transactions per sender
+	senders: SenderIndex,
+	/// Number of transactions per type
+	types: TypeCounts,
+	/// Approximate memory occupied by all transactions
+	bytes: usize,
+	/// Number of transactions per submission time
+	submitted_at: BTreeMap<time::Instant, usize>,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for FutureTransactions<Hash, Ex> {
 	fn default() -> Self {
 		FutureTransactions {
 			wanted_tags: Default::default(),
+			provided_tags: Default::default(),
 			waiting: Default::default(),
+			counts: Default::default(),
+			senders: Default::default(),
+			types: Default::default(),
+			bytes: 0,
+			submitted_at: Default::default(),
 		}
 	}
 }
@@ -151,14 +170,150 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		}
 
 		// Add the transaction to a by-hash waiting map
+		self.note_added(&tx.transaction);
 		self.waiting.insert(tx.transaction.hash.clone(), tx);
 	}
 
+	/// Updates the statistics after given transaction entered the queue.
+	fn note_added(&mut self, tx: &Transaction<Hash, Ex>) {
+		self.counts.add(tx.source);
+		self.senders.add(tx);
+		self.types.add(tx);
+		for tag in &tx.provides {
+			self.provided_tags.entry(tag.clone()).or_default().insert(tx.hash.clone());
+		}
+		self.bytes += tx.memory_footprint();
+		*self.submitted_at.entry(tx.submitted_at).or_insert(0) += 1;
+	}
+
+	/// Updates the statistics after given transaction left the queue.
+	fn note_removed(&mut self, tx: &Transaction<Hash, Ex>) {
+		self.counts.remove(tx.source);
+		self.senders.remove(tx);
+		self.types.remove(tx);
+		for tag in &tx.provides {
+			let remove = match self.provided_tags.get_mut(tag) {
+				Some(providers) => {
+					providers.remove(&tx.hash);
+					providers.is_empty()
+				},
+				None => false,
+			};
+			if remove {
+				self.provided_tags.remove(tag);
+			}
+		}
+		self.bytes -= tx.memory_footprint();
+		let remaining = self.submitted_at.get_mut(&tx.submitted_at).map(|count| {
+			*count -= 1;
+			*count
+		});
+		if remaining == Some(0) {
+			self.submitted_at.remove(&tx.submitted_at);
+		}
+	}
+
 	/// Returns true if given hash is part of the queue.
 	pub fn contains(&self, hash: &Hash) -> bool {
 		self.waiting.contains_key(hash)
 	}
 
+	/// Returns the length of the longest chain of future transactions given transaction
+	/// would be part of after it's imported.
+	///
+	/// The chain consists of the transaction itself, future transactions providing tags it
+	/// is still missing and future transactions waiting for tags it provides.
+	pub fn chain_depth(&self, tx: &WaitingTransaction<Hash, Ex>) -> usize {
+		let mut visited = HashMap::new();
+		let ancestors = tx.missing_tags
+			.iter()
+			.flat_map(|tag| self.provided_tags.get(tag).into_iter().flatten())
+			.map(|hash| self.ancestors_depth(hash, &mut visited))
+			.max()
+			.unwrap_or(0);
+
+		let mut visited = HashMap::new();
+		let descendants = tx.transaction.provides
+			.iter()
+			.flat_map(|tag| self.wanted_tags.get(tag).into_iter().flatten())
+			.map(|hash| self.descendants_depth(hash, &mut visited))
+			.max()
+			.unwrap_or(0);
+
+		ancestors + 1 + descendants
+	}
+
+	/// Returns true if given transaction would form a dependency cycle with
+	/// transactions in the future queue.
+	///
+	/// Transactions forming a cycle can never be promoted to the ready queue.
+	pub fn creates_cycle(&self, tx: &WaitingTransaction<Hash, Ex>) -> bool {
+		// walk the transactions waiting for tags provided by `tx`, looking for the ones `tx` waits for
+		let provides_missing = |provides: &[Tag]| provides.iter().any(|tag| tx.missing_tags.contains(tag));
+		if provides_missing(&tx.transaction.provides) {
+			return true;
+		}
+
+		let mut visited = HashSet::new();
+		let mut to_visit = tx.transaction.provides.iter().collect::<Vec<_>>();
+		while let Some(tag) = to_visit.pop() {
+			for hash in self.wanted_tags.get(tag).into_iter().flatten() {
+				if visited.insert(hash) {
+					let provides = &self.waiting.get(hash).expect(WAITING_PROOF).transaction.provides;
+					if provides_missing(provides) {
+						return true;
+					}
+					to_visit.extend(provides.iter());
+				}
+			}
+		}
+		false
+	}
+
+	fn ancestors_depth<'a>(
+		&'a self,
+		hash: &'a Hash,
+		visited: &mut HashMap<&'a Hash, usize>,
+	) -> usize {
+		if let Some(depth) = visited.get(hash) {
+			return *depth;
+		}
+		// make sure dependency cycles don't make us loop forever
+		visited.insert(hash, 0);
+
+		let tx = self.waiting.get(hash).expect(WAITING_PROOF);
+		let depth = 1 + tx.missing_tags
+			.iter()
+			.flat_map(|tag| self.provided_tags.get(tag).into_iter().flatten())
+			.map(|hash| self.ancestors_depth(hash, visited))
+			.max()
+			.unwrap_or(0);
+		visited.insert(hash, depth);
+		depth
+	}
+
+	fn descendants_depth<'a>(
+		&'a self,
+		hash: &'a Hash,
+		visited: &mut HashMap<&'a Hash, usize>,
+	) -> usize {
+		if let Some(depth) = visited.get(hash) {
+			return *depth;
+		}
+		// make sure dependency cycles don't make us loop forever
+		visited.insert(hash, 0);
+
+		let tx = self.waiting.get(hash).expect(WAITING_PROOF);
+		let depth = 1 + tx.transaction.provides
+			.iter()
+			.flat_map(|tag| self.wanted_tags.get(tag).into_iter().flatten())
+			.map(|hash| self.descendants_depth(hash, visited))
+			.max()
+			.unwrap_or(0);
+		visited.insert(hash, depth);
+		depth
+	}
+
 	/// Returns a list of known transactions
 	pub fn by_hashes(&self, hashes: &[Hash]) -> Vec<Option<Arc<Transaction<Hash, Ex>>>> {
 		hashes.iter().map(|h| self.waiting.get(h).map(|x| x.transaction.clone())).collect()
@@ -182,6 +337,7 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 
 					if is_ready {
 						let tx = self.waiting.remove(&hash).expect(WAITING_PROOF);
+						self.note_removed(&tx.transaction);
 						became_ready.push(tx);
 					}
 				}
@@ -198,6 +354,7 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		let mut removed = vec![];
 		for hash in hashes {
 			if let Some(waiting_tx) = self.waiting.remove(hash) {
+				self.note_removed(&waiting_tx.transaction);
 				// remove from wanted_tags as well
 				for tag in waiting_tx.missing_tags {
 					let remove = if let Some(wanted) = self.wanted_tags.get_mut(&tag) {
@@ -227,9 +384,20 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.values().map(|waiting| &*waiting.transaction)
 	}
 
+	/// Returns iterator over shared references to all future transactions.
+	pub fn all_shared(&self) -> impl Iterator<Item=&Arc<Transaction<Hash, Ex>>> {
+		self.waiting.values().map(|waiting| &waiting.transaction)
+	}
+
 	/// Removes and returns all future transactions.
 	pub fn clear(&mut self) -> Vec<Arc<Transaction<Hash, Ex>>> {
 		self.wanted_tags.clear();
+		self.provided_tags.clear();
+		self.counts = Default::default();
+		self.senders = Default::default();
+		self.types = Default::default();
+		self.bytes = 0;
+		self.submitted_at.clear();
 		self.waiting.drain().map(|(_, tx)| tx.transaction).collect()
 	}
 
@@ -238,8 +406,51 @@ impl<Hash: hash::Hash + Eq + Clone, Ex> FutureTransactions<Hash, Ex> {
 		self.waiting.len()
 	}
 
-	/// Returns sum of encoding lengths of all transactions in this queue.
+	/// Returns number of transactions in the Future queue per source.
+	pub fn count_by_source(&self) -> SourceCounts {
+		self.counts
+	}
+
+	/// Returns number of transactions of given type in this queue.
+	pub fn type_count(&self, index: usize) -> usize {
+		self.types.get(index)
+	}
+
+	/// Returns approximate memory occupied by all transactions in this queue.
 	pub fn bytes(&self) -> usize {
-		self.waiting.values().fold(0, |acc, tx| acc + tx.transaction.bytes)
+		self.bytes
+	}
+
+	/// Returns number of distinct senders of transactions in this queue.
+	pub fn distinct_senders(&self) -> usize {
+		self.senders.len()
+	}
+
+	/// Returns number of transactions per sender in this queue.
+	pub(crate) fn senders(&self) -> &SenderIndex {
+		&self.senders
+	}
+
+	/// Returns the submission time of the oldest transaction in this queue.
+	pub fn oldest_submitted_at(&self) -> Option<time::Instant> {
+		self.submitted_at.keys().next().cloned()
+	}
+}
+
+impl<Hash: hash::Hash + Eq + Clone, Ex: Clone> FutureTransactions<Hash, Ex> {
+	/// Changes source of given transaction.
+	///
+	/// Returns `false` if the transaction is not in the queue or already has given source.
+	pub fn set_source(&mut self, hash: &Hash, source: TransactionSource) -> bool {
+		let tx = match self.waiting.get_mut(hash) {
+			Some(tx) if tx.transaction.source != source => tx,
+			_ => return false,
+		};
+		let mut updated = tx.transaction.duplicate();
+		updated.source = source;
+		self.counts.remove(tx.transaction.source);
+		self.counts.add(source);
+		tx.transaction = Arc::new(updated);
+		true
 	}
 }
@@ -24,19 +24,31 @@
 #![warn(missing_docs)]
 #![warn(unused_extern_crates)]
 
+mod bloom;
+mod bounded_stream;
 mod future;
 mod listener;
 mod pool;
 mod ready;
 mod rotator;
+mod spill;
 mod validated_pool;
 
 pub mod base_pool;
 pub mod watcher;
 
 pub use self::base_pool::Transaction;
+pub use self::bounded_stream::BoundedStream;
+
+/// Default log target of the pool.
+pub const DEFAULT_LOG_TARGET: &str = "txpool";
 pub use self::pool::{
 	Pool,
-	Options, ChainApi, EventStream, ExtrinsicFor,
-	BlockHash, ExHash, NumberFor, TransactionFor,
+	Options, AdmissionOptions, LimitOptions, MaintenanceOptions, WatchOptions,
+	PoolExtensions, ChainApi, EventStream, DeadletterStream, ExtrinsicFor,
+	PriorityNormalizer, IdentityNormalizer, RevalidationOutcome, RejectionReason, SubmitResult, ValidationTimeHistogram,
+	FuturePromotionStats, PressureThresholds, RevalidationOrder,
+	FutureDistanceEstimator, UnknownDistance, WeightEstimator, ZeroWeight, ValidationContextProvider,
+	PreValidationFilter, ValueExtractor, ZeroValue, ValidFromHint,
+	BlockHash, ExHash, ExtensionsFor, NumberFor, TransactionFor,
 };
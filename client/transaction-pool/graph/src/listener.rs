@@ -16,29 +16,67 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::HashMap,
+	collections::{HashMap, HashSet},
 	fmt,
 	hash,
+	mem,
 };
+use futures::channel::mpsc;
 use serde::Serialize;
 use crate::watcher;
+use sp_transaction_pool::{AllStatusStream, TransactionStatus};
 use sp_runtime::traits;
 use log::{debug, trace, warn};
 
 /// Extrinsic pool default listener.
 pub struct Listener<H: hash::Hash + Eq, H2> {
-	watchers: HashMap<H, watcher::Sender<H, H2>>
+	watchers: HashMap<H, watcher::Sender<H, H2>>,
+	status_sinks: Vec<mpsc::UnboundedSender<(H, TransactionStatus<H, H2>)>>,
+	broadcasted_to: HashMap<H, HashSet<String>>,
+	log_target: &'static str,
+	/// Maximal number of status events emitted to watchers of a single transaction.
+	max_watch_events: Option<usize>,
+	/// Number of blocks within which blocks of watched transactions are expected to be finalized.
+	finality_timeout_blocks: Option<u32>,
+	/// Block hash and number of watched transactions awaiting finality of their block.
+	in_block: HashMap<H, (H2, u64)>,
 }
 
 impl<H: hash::Hash + Eq, H2> Default for Listener<H, H2> {
 	fn default() -> Self {
+		Self::new(crate::DEFAULT_LOG_TARGET)
+	}
+}
+
+impl<H: hash::Hash + Eq, H2> Listener<H, H2> {
+	/// Creates a new listener logging to given target.
+	pub fn new(log_target: &'static str) -> Self {
 		Listener {
 			watchers: Default::default(),
+			status_sinks: Default::default(),
+			broadcasted_to: Default::default(),
+			log_target,
+			max_watch_events: None,
+			finality_timeout_blocks: None,
+			in_block: Default::default(),
 		}
 	}
+
+	/// Limits number of status events emitted to watchers of a single transaction.
+	pub fn with_max_watch_events(mut self, max_watch_events: Option<usize>) -> Self {
+		self.max_watch_events = max_watch_events;
+		self
+	}
+
+	/// Keeps watching included transactions until their block is finalized,
+	/// for at most `finality_timeout_blocks` blocks.
+	pub fn with_finality_timeout(mut self, finality_timeout_blocks: Option<u32>) -> Self {
+		self.finality_timeout_blocks = finality_timeout_blocks;
+		self
+	}
 }
 
-impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + fmt::Debug> Listener<H, H2> {
+impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + PartialEq + fmt::Debug> Listener<H, H2> {
 	fn fire<F>(&mut self, hash: &H, fun: F) where F: FnOnce(&mut watcher::Sender<H, H2>) {
 		let clean = if let Some(h) = self.watchers.get_mut(hash) {
 			fun(h);
@@ -49,41 +87,136 @@ impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + fmt::Debug> Listene
 
 		if clean {
 			self.watchers.remove(hash);
+			self.in_block.remove(hash);
+		}
+	}
+
+	fn sender(&mut self, hash: H) -> &mut watcher::Sender<H, H2> {
+		let max_events = self.max_watch_events;
+		let track_finality = self.finality_timeout_blocks.is_some();
+		self.watchers.entry(hash)
+			.or_insert_with(|| watcher::Sender::with_max_events(max_events).tracking_finality(track_finality))
+	}
+
+	fn notify_all(&mut self, hash: &H, status: TransactionStatus<H, H2>) {
+		if self.status_sinks.is_empty() {
+			return;
 		}
+		self.status_sinks.retain(|sink| sink.unbounded_send((hash.clone(), status.clone())).is_ok());
+	}
+
+	/// Returns a stream of status events of all transactions.
+	pub fn all_status_stream(&mut self) -> AllStatusStream<H, H2> {
+		let (sink, stream) = mpsc::unbounded();
+		self.status_sinks.push(sink);
+		stream
 	}
 
 	/// Creates a new watcher for given verified extrinsic.
 	///
 	/// The watcher can be used to subscribe to lifecycle events of that extrinsic.
 	pub fn create_watcher(&mut self, hash: H) -> watcher::Watcher<H, H2> {
-		let sender = self.watchers.entry(hash.clone()).or_insert_with(watcher::Sender::default);
-		sender.new_watcher(hash)
+		self.sender(hash.clone()).new_watcher(hash)
+	}
+
+	/// Creates a new watcher for given verified extrinsic, that also receives queue position updates.
+	pub fn create_watcher_with_position(&mut self, hash: H) -> watcher::Watcher<H, H2> {
+		self.sender(hash.clone()).new_watcher_with_position(hash)
+	}
+
+	/// Creates a new watcher for given verified extrinsic, that receives only the terminal status.
+	pub fn create_watcher_terminal_only(&mut self, hash: H) -> watcher::Watcher<H, H2> {
+		self.sender(hash.clone()).new_watcher_terminal_only(hash)
+	}
+
+	/// Returns true if there are any watchers interested in queue position updates.
+	pub fn wants_positions(&self) -> bool {
+		self.watchers.values().any(|sender| sender.wants_position())
+	}
+
+	/// Forgets subscribers which dropped their receivers, without waiting for the next
+	/// event of their transactions.
+	pub fn remove_closed_watchers(&mut self) {
+		self.watchers.retain(|_, sender| {
+			sender.remove_closed();
+			!sender.is_done()
+		});
+		let watchers = &self.watchers;
+		self.in_block.retain(|hash, _| watchers.contains_key(hash));
+	}
+
+	/// Returns number of active subscribers across all watched transactions.
+	pub fn watchers_count(&self) -> usize {
+		self.watchers.values().map(|sender| sender.receivers_count()).sum()
+	}
+
+	/// Returns approximate memory occupied by the watchers.
+	///
+	/// Accounts for the watchers index and a single pending status per subscriber,
+	/// actual memory might be higher if subscribers don't consume their streams.
+	pub fn memory_usage(&self) -> usize {
+		self.watchers.values().fold(0, |acc, sender| {
+			acc + mem::size_of::<H>() + mem::size_of::<watcher::Sender<H, H2>>()
+				+ sender.receivers_count() * (
+					mem::size_of::<futures::channel::mpsc::UnboundedSender<TransactionStatus<H, H2>>>()
+					+ mem::size_of::<TransactionStatus<H, H2>>()
+				)
+		}) + self.in_block.len() * mem::size_of::<(H, H2, u64)>()
 	}
 
 	/// Notify the listeners about extrinsic broadcast.
+	///
+	/// Only peers the extrinsic hasn't been broadcast to yet are reported,
+	/// nothing is emitted if all of them have been reported already.
 	pub fn broadcasted(&mut self, hash: &H, peers: Vec<String>) {
-		trace!(target: "txpool", "[{:?}] Broadcasted", hash);
+		let known = self.broadcasted_to.entry(hash.clone()).or_default();
+		let peers = peers.into_iter().filter(|peer| known.insert(peer.clone())).collect::<Vec<_>>();
+		if peers.is_empty() {
+			return;
+		}
+
+		trace!(target: self.log_target, "[{:?}] Broadcasted", hash);
+		self.notify_all(hash, TransactionStatus::Broadcast(peers.clone()));
 		self.fire(hash, |watcher| watcher.broadcast(peers));
 	}
 
 	/// New transaction was added to the ready pool or promoted from the future pool.
 	pub fn ready(&mut self, tx: &H, old: Option<&H>) {
-		trace!(target: "txpool", "[{:?}] Ready (replaced: {:?})", tx, old);
+		trace!(target: self.log_target, "[{:?}] Ready (replaced: {:?})", tx, old);
+		// re-entered the pool, e.g. after its block got retracted
+		self.in_block.remove(tx);
+		self.notify_all(tx, TransactionStatus::Ready);
 		self.fire(tx, |watcher| watcher.ready());
 		if let Some(old) = old {
+			self.broadcasted_to.remove(old);
+			self.notify_all(old, TransactionStatus::Usurped(tx.clone()));
 			self.fire(old, |watcher| watcher.usurped(tx.clone()));
 		}
 	}
 
 	/// New transaction was added to the future pool.
 	pub fn future(&mut self, tx: &H) {
-		trace!(target: "txpool", "[{:?}] Future", tx);
+		trace!(target: self.log_target, "[{:?}] Future", tx);
+		self.in_block.remove(tx);
+		self.notify_all(tx, TransactionStatus::Future);
 		self.fire(tx, |watcher| watcher.future());
 	}
 
+	/// Future transaction was moved to the disk-backed spill.
+	pub fn spilled(&mut self, tx: &H) {
+		trace!(target: self.log_target, "[{:?}] Spilled", tx);
+		self.notify_all(tx, TransactionStatus::Spilled);
+		self.fire(tx, |watcher| watcher.spilled());
+	}
+
 	/// Transaction was dropped from the pool because of the limit.
 	pub fn dropped(&mut self, tx: &H, by: Option<&H>) {
-		trace!(target: "txpool", "[{:?}] Dropped (replaced by {:?})", tx, by);
+		trace!(target: self.log_target, "[{:?}] Dropped (replaced by {:?})", tx, by);
+		self.broadcasted_to.remove(tx);
+		self.notify_all(tx, match by {
+			Some(t) => TransactionStatus::Usurped(t.clone()),
+			None => TransactionStatus::Dropped,
+		});
 		self.fire(tx, |watcher| match by {
 			Some(t) => watcher.usurped(t.clone()),
 			None => watcher.dropped(),
@@ -93,16 +226,73 @@ impl<H: hash::Hash + traits::Member + Serialize, H2: Clone + fmt::Debug> Listene
 	/// Transaction was removed as invalid.
 	pub fn invalid(&mut self, tx: &H, warn: bool) {
 		if warn {
-			warn!(target: "txpool", "Extrinsic invalid: {:?}", tx);
+			warn!(target: self.log_target, "Extrinsic invalid: {:?}", tx);
 		} else {
-			debug!(target: "txpool", "Extrinsic invalid: {:?}", tx);
+			debug!(target: self.log_target, "Extrinsic invalid: {:?}", tx);
 		}
+		self.broadcasted_to.remove(tx);
+		self.notify_all(tx, TransactionStatus::Invalid);
 		self.fire(tx, |watcher| watcher.invalid());
 	}
 
-	/// Transaction was pruned from the pool.
-	pub fn pruned(&mut self, header_hash: H2, tx: &H) {
-		debug!(target: "txpool", "[{:?}] Pruned at {:?}", tx, header_hash);
-		self.fire(tx, |watcher| watcher.in_block(header_hash))
+	/// Notify the listeners about positions of ready transactions.
+	///
+	/// `ready` is expected to iterate over the ready set in its current order.
+	pub fn queued<'a>(&mut self, ready: impl IntoIterator<Item=&'a H>, total: usize) where H: 'a {
+		for (idx, hash) in ready.into_iter().enumerate() {
+			if let Some(sender) = self.watchers.get_mut(hash) {
+				if sender.wants_position() {
+					sender.queued(idx + 1, total);
+				}
+			}
+		}
+	}
+
+	/// Transaction was pruned from the pool, because it's included in block with given hash and number.
+	pub fn pruned(&mut self, header_hash: H2, block_number: u64, tx: &H) {
+		debug!(target: self.log_target, "[{:?}] Pruned at {:?}", tx, header_hash);
+		self.broadcasted_to.remove(tx);
+		self.notify_all(tx, TransactionStatus::InBlock(header_hash.clone()));
+		self.fire(tx, |watcher| watcher.in_block(header_hash.clone()));
+		if self.finality_timeout_blocks.is_some() && self.watchers.contains_key(tx) {
+			self.in_block.insert(tx.clone(), (header_hash, block_number));
+		}
+	}
+
+	/// Notify the listeners about transactions which blocks haven't been finalized in time.
+	///
+	/// `block_number` is the number of the new best block. Watchers of transactions
+	/// included at least `finality_timeout_blocks` blocks ago are closed.
+	pub fn best_block(&mut self, block_number: u64) {
+		let timeout = match self.finality_timeout_blocks {
+			Some(timeout) => u64::from(timeout),
+			None => return,
+		};
+		let timed_out = self.in_block.iter()
+			.filter(|(_, (_, included_at))| block_number.saturating_sub(*included_at) >= timeout)
+			.map(|(tx, (header_hash, _))| (tx.clone(), header_hash.clone()))
+			.collect::<Vec<_>>();
+		for (tx, header_hash) in timed_out {
+			debug!(target: self.log_target, "[{:?}] Block {:?} not finalized in time", tx, header_hash);
+			self.in_block.remove(&tx);
+			self.notify_all(&tx, TransactionStatus::FinalityTimeout(header_hash.clone()));
+			self.fire(&tx, |watcher| watcher.finality_timeout(header_hash));
+		}
+	}
+
+	/// Notify the listeners about finalization of block with given hash and number.
+	///
+	/// Finalization covers the ancestors of the block too. Transactions of retracted blocks
+	/// re-enter the pool, so the lower blocks that are still awaited are assumed to be ancestors.
+	pub fn finalized(&mut self, header_hash: &H2, block_number: u64) {
+		let finalized = self.in_block.iter()
+			.filter(|(_, (block, included_at))| block == header_hash || *included_at < block_number)
+			.map(|(tx, _)| tx.clone())
+			.collect::<Vec<_>>();
+		for tx in finalized {
+			trace!(target: self.log_target, "[{:?}] Block finalized", tx);
+			self.in_block.remove(&tx);
+			self.fire(&tx, |watcher| watcher.block_finalized());
+		}
 	}
 }
@@ -15,14 +15,20 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
+	fmt,
 	hash,
-	collections::HashMap,
-	sync::Arc,
+	panic,
+	collections::{BTreeMap, HashMap, HashSet},
+	sync::{Arc, atomic::{AtomicBool, Ordering}},
+	time::{Duration, Instant},
 };
 
+use codec::Encode;
 use crate::base_pool as base;
+use crate::bounded_stream::BoundedStream;
 use crate::watcher::Watcher;
 use serde::Serialize;
+use parking_lot::{Mutex, RwLock};
 
 use futures::{
 	Future, FutureExt,
@@ -31,15 +37,24 @@ use futures::{
 use sp_runtime::{
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
-	transaction_validity::{TransactionValidity, TransactionTag as Tag, TransactionValidityError},
+	transaction_validity::{
+		TransactionValidity, TransactionTag as Tag, TransactionValidityError, ValidTransaction,
+		TransactionPriority as Priority, InvalidTransaction,
+	},
+};
+use sp_transaction_pool::{
+	error, AllStatusStream, FutureStatus, InPoolTransaction, PoolStatus, PressureLevel, SourceCounts,
+	TransactionSource, TransactionWeight as Weight,
 };
-use sp_transaction_pool::{error, PoolStatus};
 
-use crate::validated_pool::{ValidatedPool, ValidatedTransaction};
+use crate::validated_pool::{ValidatedPool, ValidatedTransaction, WatchMode};
 
 /// Modification notification event stream type;
 pub type EventStream<H> = mpsc::UnboundedReceiver<H>;
 
+/// Stream of permanently rejected transactions with the reason of rejection.
+pub type DeadletterStream<H> = mpsc::UnboundedReceiver<(H, RejectionReason)>;
+
 /// Extrinsic hash type for a pool.
 pub type ExHash<A> = <A as ChainApi>::Hash;
 /// Block hash type for a pool.
@@ -50,6 +65,8 @@ pub type ExtrinsicFor<A> = <<A as ChainApi>::Block as traits::Block>::Extrinsic;
 pub type NumberFor<A> = traits::NumberFor<<A as ChainApi>::Block>;
 /// A type of transaction stored in the pool
 pub type TransactionFor<A> = Arc<base::Transaction<ExHash<A>, ExtrinsicFor<A>>>;
+/// Extension points type for a pool.
+pub type ExtensionsFor<A> = PoolExtensions<ExHash<A>, ExtrinsicFor<A>>;
 /// A type of validated transaction stored in the pool.
 pub type ValidatedTransactionFor<A> = ValidatedTransaction<
 	ExHash<A>,
@@ -77,6 +94,22 @@ pub trait ChainApi: Send + Sync {
 		uxt: ExtrinsicFor<Self>,
 	) -> Self::ValidationFuture;
 
+	/// Verify extrinsic at given block, passing additional opaque context to the runtime.
+	///
+	/// The context is provided by `PoolExtensions::context_provider`. Runtimes opting in
+	/// are expected to expose a validation entry point accepting the SCALE-encoded
+	/// extrinsic followed by the context encoded as `Vec<u8>`, implementations should
+	/// check that the runtime supports it. The default implementation ignores the context
+	/// and falls back to `validate_transaction`.
+	fn validate_transaction_with_context(
+		&self,
+		at: &BlockId<Self::Block>,
+		uxt: ExtrinsicFor<Self>,
+		_context: Vec<u8>,
+	) -> Self::ValidationFuture {
+		self.validate_transaction(at, uxt)
+	}
+
 	/// Returns a block number given the block id.
 	fn block_id_to_number(&self, at: &BlockId<Self::Block>) -> Result<Option<NumberFor<Self>>, Self::Error>;
 
@@ -90,6 +123,216 @@ pub trait ChainApi: Send + Sync {
 	fn block_body(&self, at: &BlockId<Self::Block>) -> Self::BodyFuture;
 }
 
+/// Normalizes priorities of validated transactions.
+///
+/// On some chains priorities returned by the runtime are not directly comparable
+/// across transaction types. The normalized priority is used by the pool for ordering
+/// of the ready queue and eviction decisions only, it has no consensus meaning.
+pub trait PriorityNormalizer: Send + Sync + fmt::Debug {
+	/// Returns the priority that the pool should use for given transaction.
+	fn normalize(&self, tx: &ValidTransaction) -> u64;
+}
+
+/// Priority normalizer that keeps the priority returned by the runtime.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct IdentityNormalizer;
+
+impl PriorityNormalizer for IdentityNormalizer {
+	fn normalize(&self, tx: &ValidTransaction) -> u64 {
+		tx.priority
+	}
+}
+
+/// Estimates how far in the future transactions are.
+///
+/// Tags are opaque to the pool, so knowledge how to interpret `requires`
+/// (e.g. as account nonces) has to be provided by the runtime-aware estimator.
+pub trait FutureDistanceEstimator: Send + Sync + fmt::Debug {
+	/// Returns the number of missing predecessors of given transaction validated at given block.
+	///
+	/// `None` if the distance can't be estimated, such transactions are always accepted.
+	fn distance(&self, block_number: u64, tx: &ValidTransaction) -> Option<u64>;
+}
+
+/// Future distance estimator that doesn't know how to estimate anything.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UnknownDistance;
+
+impl FutureDistanceEstimator for UnknownDistance {
+	fn distance(&self, _block_number: u64, _tx: &ValidTransaction) -> Option<u64> {
+		None
+	}
+}
+
+/// Estimates weight of validated transactions.
+///
+/// Validity returned by the runtime doesn't carry the weight, so it has to be derived
+/// from the transaction itself.
+pub trait WeightEstimator: Send + Sync + fmt::Debug {
+	/// Returns weight of given transaction with given encoded length.
+	fn weight(&self, len: usize, tx: &ValidTransaction) -> Weight;
+}
+
+/// Weight estimator that considers all transactions weightless.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZeroWeight;
+
+impl WeightEstimator for ZeroWeight {
+	fn weight(&self, _len: usize, _tx: &ValidTransaction) -> Weight {
+		0
+	}
+}
+
+/// Provides additional context for validation of transactions.
+///
+/// See `ChainApi::validate_transaction_with_context` for how the context is passed to the runtime.
+pub trait ValidationContextProvider: Send + Sync + fmt::Debug {
+	/// Returns context for validation of given SCALE-encoded transaction.
+	///
+	/// `None` if there is no context, the transaction is validated the standard way then.
+	fn context(&self, source: TransactionSource, encoded: &[u8]) -> Option<Vec<u8>>;
+}
+
+/// Extracts value committed by transactions (see `Pool::committed_value_for`).
+///
+/// Value is not part of the validity returned by the runtime, so it has to be derived
+/// from the transaction itself.
+pub trait ValueExtractor<Hash, Ex>: Send + Sync + fmt::Debug {
+	/// Returns value committed by given transaction.
+	fn value_of(&self, tx: &dyn InPoolTransaction<Transaction=Ex, Hash=Hash>) -> u128;
+}
+
+/// Value extractor that considers all transactions to commit no value.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ZeroValue;
+
+impl<Hash, Ex> ValueExtractor<Hash, Ex> for ZeroValue {
+	fn value_of(&self, _tx: &dyn InPoolTransaction<Transaction=Ex, Hash=Hash>) -> u128 {
+		0
+	}
+}
+
+/// Node-level policy deciding which transactions are worth validating at all.
+pub trait PreValidationFilter: Send + Sync + fmt::Debug {
+	/// Returns `false` if given SCALE-encoded transaction should be rejected without validation.
+	fn allow(&self, encoded: &[u8]) -> bool;
+}
+
+/// Determines the block starting from which transactions become valid.
+///
+/// Validity returned by the runtime doesn't carry such hint, so it has to be derived
+/// from the transaction itself.
+pub trait ValidFromHint: Send + Sync + fmt::Debug {
+	/// Returns number of the first block at which given SCALE-encoded transaction is valid.
+	///
+	/// `None` if the transaction is not time-gated.
+	fn valid_from(&self, encoded: &[u8]) -> Option<u64>;
+}
+
+/// Extension points of the pool.
+#[derive(Debug, Clone)]
+pub struct PoolExtensions<Hash, Ex> {
+	/// Priority normalizer applied to transactions before they enter the pool.
+	pub priority_normalizer: Arc<dyn PriorityNormalizer>,
+	/// Estimator of the future transactions distance (see `AdmissionOptions::max_future_distance`).
+	pub future_distance: Arc<dyn FutureDistanceEstimator>,
+	/// Estimator of transactions weight (see `LimitOptions::max_weight`).
+	pub weight_estimator: Arc<dyn WeightEstimator>,
+	/// Provider of additional validation context.
+	///
+	/// Validity of transactions validated with context is never cached.
+	pub context_provider: Option<Arc<dyn ValidationContextProvider>>,
+	/// Filter consulted before runtime validation of submitted transactions.
+	///
+	/// Transactions it rejects fail with `Error::Filtered` and are not banned.
+	pub pre_validation_filter: Option<Arc<dyn PreValidationFilter>>,
+	/// Extractor of value committed by transactions (see `Pool::committed_value_for`).
+	pub value_extractor: Arc<dyn ValueExtractor<Hash, Ex>>,
+	/// Hint of the first block at which transactions become valid.
+	///
+	/// Transactions submitted before that block are kept aside and imported by
+	/// `Pool::submit_time_gated` once the chain reaches it.
+	pub valid_from_hint: Option<Arc<dyn ValidFromHint>>,
+}
+
+impl<Hash, Ex> Default for PoolExtensions<Hash, Ex> {
+	fn default() -> Self {
+		PoolExtensions {
+			priority_normalizer: Arc::new(IdentityNormalizer),
+			future_distance: Arc::new(UnknownDistance),
+			weight_estimator: Arc::new(ZeroWeight),
+			context_provider: None,
+			pre_validation_filter: None,
+			value_extractor: Arc::new(ZeroValue),
+			valid_from_hint: None,
+		}
+	}
+}
+
+/// Order in which ready transactions are picked for a limited revalidation batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevalidationOrder {
+	/// Transactions submitted first are revalidated first.
+	Oldest,
+	/// Transactions with higher priority are revalidated first, the oldest ones among
+	/// transactions with equal priority.
+	HighestPriority,
+}
+
+impl Default for RevalidationOrder {
+	fn default() -> Self {
+		RevalidationOrder::Oldest
+	}
+}
+
+impl RevalidationOrder {
+	/// Sorts given transactions so that the ones to revalidate first go first.
+	fn sort<Hash, Ex>(&self, txs: &mut [Arc<base::Transaction<Hash, Ex>>]) {
+		match self {
+			RevalidationOrder::Oldest => txs.sort_by_key(|tx| tx.submitted_at),
+			RevalidationOrder::HighestPriority => txs.sort_by(|a, b| b.priority.cmp(&a.priority)
+				.then_with(|| a.submitted_at.cmp(&b.submitted_at))
+			),
+		}
+	}
+}
+
+/// Pool usage thresholds of the pressure levels.
+///
+/// Both values are fractions (`0.0..=1.0`) of the pool limits, the usage is the highest
+/// fraction of any of the ready and future count and size limits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PressureThresholds {
+	/// Usage at which the pressure becomes `PressureLevel::Medium`.
+	pub medium: f32,
+	/// Usage at which the pressure becomes `PressureLevel::High`.
+	pub high: f32,
+}
+
+impl Default for PressureThresholds {
+	fn default() -> Self {
+		PressureThresholds {
+			medium: 0.5,
+			high: 0.8,
+		}
+	}
+}
+
+impl PressureThresholds {
+	/// Returns pressure level at given usage of the pool limits.
+	pub fn level(&self, usage: f64) -> PressureLevel {
+		if usage >= 1.0 {
+			PressureLevel::Full
+		} else if usage >= f64::from(self.high) {
+			PressureLevel::High
+		} else if usage >= f64::from(self.medium) {
+			PressureLevel::Medium
+		} else {
+			PressureLevel::Low
+		}
+	}
+}
+
 /// Pool configuration options.
 #[derive(Debug, Clone)]
 pub struct Options {
@@ -99,6 +342,16 @@ pub struct Options {
 	pub future: base::Limit,
 	/// Reject future transactions.
 	pub reject_future_transactions: bool,
+	/// Target used for all logs of the pool.
+	pub log_target: &'static str,
+	/// Policies deciding which transactions are admitted to the pool.
+	pub admission: AdmissionOptions,
+	/// Additional limits of the pool and the way they are enforced.
+	pub limits: LimitOptions,
+	/// Configuration of the pool maintenance and revalidation.
+	pub maintenance: MaintenanceOptions,
+	/// Limits of watching transactions in the pool.
+	pub watch: WatchOptions,
 }
 
 impl Default for Options {
@@ -113,104 +366,1225 @@ impl Default for Options {
 				total_bytes: 1 * 1024 * 1024,
 			},
 			reject_future_transactions: false,
+			log_target: crate::DEFAULT_LOG_TARGET,
+			admission: Default::default(),
+			limits: Default::default(),
+			maintenance: Default::default(),
+			watch: Default::default(),
+		}
+	}
+}
+
+/// Options deciding which transactions are admitted to the pool.
+#[derive(Debug, Clone)]
+pub struct AdmissionOptions {
+	/// How many times validation is retried if the runtime api call fails.
+	///
+	/// Only transient errors (the api call itself failing) are retried,
+	/// transactions that are deemed invalid are rejected immediately.
+	pub submit_retries: usize,
+	/// Maximal distance of future transactions (see `PoolExtensions::future_distance`).
+	///
+	/// More distant transactions are rejected instead of being parked in the future queue.
+	pub max_future_distance: Option<u64>,
+	/// Maximal length of a dependency chain formed by transactions in the future queue.
+	///
+	/// Transactions that would make the chain longer are rejected.
+	pub max_future_chain_depth: Option<usize>,
+	/// Reject transactions forming a dependency cycle with transactions in the future queue.
+	///
+	/// Otherwise such transactions are only promoted if some other transaction provides
+	/// one of the tags required within the cycle. Enabled by default, the check only visits
+	/// future transactions depending on the submitted one.
+	pub reject_dependency_cycles: bool,
+	/// Replace ready transactions providing the same tags as a new transaction with equal priority.
+	///
+	/// By default the transactions already in the pool are kept and the new one is rejected.
+	/// Resubmission of a transaction that's already in the pool is always rejected.
+	pub replace_equal_priority: bool,
+	/// Minimal priority of accepted transactions.
+	///
+	/// Local transactions are accepted regardless of their priority.
+	pub min_priority: Option<Priority>,
+	/// Minimal longevity of accepted transactions.
+	///
+	/// Local transactions are accepted regardless of their longevity.
+	pub min_longevity: Option<u64>,
+	/// Upgrade transactions resubmitted locally.
+	///
+	/// When set, a `TransactionSource::Local` submission of a transaction that is already
+	/// in the pool from another source doesn't fail as a duplicate. The source of the existing
+	/// transaction is changed to local in place and it's pinned, so that it's no longer evicted
+	/// because of the pool limits. No status events are fired for the upgrade.
+	pub upgrade_local_resubmissions: bool,
+	/// Reuse runtime validity of transactions submitted from the same source at the same block.
+	///
+	/// Saves repeated validation of the same transaction, e.g. when it's resubmitted
+	/// during maintenance and then submitted again. Only validity at the most recently
+	/// used block is kept. Revalidation of transactions in the pool never uses the cache.
+	pub validation_cache: bool,
+	/// How long idempotency keys of submitted extrinsics are remembered (see `Pool::submit_one_idempotent`).
+	pub idempotency_key_ttl: std::time::Duration,
+}
+
+impl Default for AdmissionOptions {
+	fn default() -> Self {
+		AdmissionOptions {
+			submit_retries: 0,
+			max_future_distance: None,
+			max_future_chain_depth: None,
+			reject_dependency_cycles: true,
+			replace_equal_priority: false,
+			min_priority: None,
+			min_longevity: None,
+			upgrade_local_resubmissions: false,
+			validation_cache: false,
+			idempotency_key_ttl: std::time::Duration::from_secs(60 * 30),
+		}
+	}
+}
+
+/// Options of the pool limits beyond the ready and future queue size.
+#[derive(Debug, Clone)]
+pub struct LimitOptions {
+	/// Partitioning of the ready queue limits between local and external transactions.
+	pub source_quotas: base::SourceQuotas,
+	/// Maximal total weight of ready transactions.
+	pub max_weight: Option<Weight>,
+	/// Limits of the number of transactions of certain types in the pool.
+	///
+	/// The type of a transaction is identified by a prefix of its SCALE encoding, transactions
+	/// matching several prefixes are counted against the first one. Transactions exceeding
+	/// the limit of their type are rejected with `Error::TypeLimitExceeded`, a replacement
+	/// of a transaction of the same type is always allowed.
+	pub type_limits: Vec<(Vec<u8>, usize)>,
+	/// Minimal interval between eviction passes.
+	///
+	/// During the interval ready and future limits are allowed to be exceeded
+	/// by `eviction_cooldown_overflow`, evictions are batched until the next pass instead.
+	/// `Duration::default()` disables the cool-down.
+	pub eviction_cooldown: std::time::Duration,
+	/// Share of the limits by which the queues might grow during the eviction cool-down.
+	///
+	/// The extended (hard) limits are always enforced, also during the cool-down.
+	pub eviction_cooldown_overflow: f32,
+	/// Spill future transactions evicted because of the pool limits to a file at given path.
+	///
+	/// Spilled transactions are not counted in the pool status. They are read back and
+	/// revalidated as soon as any tag they require is provided by a ready transaction.
+	/// The file is truncated on start.
+	pub future_spill_path: Option<std::path::PathBuf>,
+	/// Thresholds of the pool pressure levels (see `Pool::pressure_level`).
+	pub pressure_thresholds: PressureThresholds,
+}
+
+impl Default for LimitOptions {
+	fn default() -> Self {
+		LimitOptions {
+			source_quotas: Default::default(),
+			max_weight: None,
+			type_limits: Vec::new(),
+			eviction_cooldown: Default::default(),
+			eviction_cooldown_overflow: 0.25,
+			future_spill_path: None,
+			pressure_thresholds: Default::default(),
+		}
+	}
+}
+
+/// Options of the pool maintenance and revalidation.
+#[derive(Debug, Clone)]
+pub struct MaintenanceOptions {
+	/// Maximal random delay added to time-based revalidation schedule.
+	///
+	/// Spreads revalidation of nodes started at the same time. Disabled (zero) by default.
+	pub revalidation_jitter: std::time::Duration,
+	/// Maximal time a revalidation might stay in progress.
+	///
+	/// Once exceeded, the revalidation is considered stuck and gets rescheduled.
+	/// `None` waits for the revalidation indefinitely.
+	pub revalidation_timeout: Option<std::time::Duration>,
+	/// Order in which ready transactions are revalidated when only a limited number of them
+	/// is revalidated at once.
+	pub revalidation_order: RevalidationOrder,
+	/// Number of blocks during which transactions from retracted blocks that failed
+	/// to be resubmitted as invalid are retried.
+	///
+	/// Smooths over transient invalidity caused by reorgs. `0` disables retries.
+	pub reorg_grace_blocks: u32,
+	/// Number of blocks after which transactions still waiting in the future queue
+	/// are reported as stuck during maintenance.
+	///
+	/// Many stuck transactions usually mean a nonce gap that's never going to be filled.
+	/// `None` disables the check.
+	pub future_stuck_threshold: Option<u32>,
+	/// Resubmit non-propagable transactions from retracted blocks.
+	///
+	/// Chains where such transactions are node-local and can't be re-obtained
+	/// might prefer to skip them during reorg handling.
+	pub resubmit_non_propagable: bool,
+	/// Number of most recently maintained blocks for which snapshots of the pool are kept.
+	///
+	/// Meant for diagnostics, `0` disables the history.
+	pub history_depth: usize,
+	/// Skip maintenance calls that got superseded by a newer one while waiting for their turn.
+	///
+	/// Maintenance is always serialized. By default the queued calls run one after another,
+	/// otherwise only the latest one runs and takes over pruning of the skipped blocks
+	/// and resubmission of transactions they have retracted.
+	pub supersede_maintenance: bool,
+	/// Prune transactions included in maintained blocks automatically.
+	///
+	/// When disabled, maintenance doesn't prune anything and transactions have to be pruned
+	/// explicitly (e.g. with `prune_known` or `prune_tags`). Until then, already included
+	/// transactions stay in the ready queue and are still returned by `ready`.
+	pub auto_prune: bool,
+	/// Keep transactions invalidated during revalidation in a quarantine for inspection.
+	pub quarantine_invalid: bool,
+	/// Drop ready transactions depending on the ones removed as invalid.
+	///
+	/// By default such transactions are moved back to the future queue, since
+	/// they may become valid again once their requirements are provided.
+	pub drop_invalid_dependents: bool,
+}
+
+impl Default for MaintenanceOptions {
+	fn default() -> Self {
+		MaintenanceOptions {
+			revalidation_jitter: std::time::Duration::from_secs(0),
+			revalidation_timeout: Some(std::time::Duration::from_secs(60 * 5)),
+			revalidation_order: Default::default(),
+			reorg_grace_blocks: 0,
+			future_stuck_threshold: None,
+			resubmit_non_propagable: true,
+			history_depth: 0,
+			supersede_maintenance: false,
+			auto_prune: true,
+			quarantine_invalid: false,
+			drop_invalid_dependents: false,
+		}
+	}
+}
+
+/// Options of watching transactions in the pool.
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+	/// Maximal number of concurrently watched transactions per source (see `submit_and_watch_from`).
+	pub max_watchers_per_source: Option<usize>,
+	/// Maximal number of status events emitted to watchers of a single transaction.
+	///
+	/// Once exceeded, `TransactionStatus::Invalid` is emitted as the last event and
+	/// the watchers are closed, the transaction itself stays in the pool.
+	pub max_watch_events: Option<usize>,
+	/// Number of blocks within which the block a watched transaction is included in
+	/// is expected to be finalized.
+	///
+	/// If set, watchers are kept after `TransactionStatus::InBlock` until the block is finalized
+	/// (see `Pool::on_block_finalized`). Once exceeded, `TransactionStatus::FinalityTimeout`
+	/// is emitted as the last event instead. `None` ends watching at the inclusion.
+	pub finality_timeout_blocks: Option<u32>,
+}
+
+impl Default for WatchOptions {
+	fn default() -> Self {
+		WatchOptions {
+			max_watchers_per_source: None,
+			max_watch_events: None,
+			finality_timeout_blocks: None,
+		}
+	}
+}
+
+/// Reason of a transaction being rejected by the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectionReason {
+	/// The transaction is temporarily banned.
+	Banned,
+	/// The transaction priority is too low to enter the pool or replace another transaction.
+	PriorityTooLow,
+	/// The transaction is too large to fit in a block.
+	TooLarge,
+	/// The transaction longevity is too short to enter the pool.
+	LongevityTooShort,
+	/// The pool doesn't accept the transaction to the future queue.
+	FutureRejected,
+	/// The submitter has reached its limit of concurrently watched transactions.
+	RateLimited,
+	/// The transaction was dropped because of the pool limits.
+	PoolFull,
+	/// The runtime considers the transaction invalid.
+	InvalidValidity,
+	/// The runtime is not able to determine validity of the transaction.
+	UnknownValidity,
+	/// The transaction is already in the pool.
+	AlreadyImported,
+	/// The transaction was rejected by the pre-validation filter.
+	Filtered,
+	/// Any other reason.
+	Other,
+}
+
+impl RejectionReason {
+	pub(crate) fn from_error<E: error::IntoPoolError>(error: &E) -> Self {
+		match error.as_pool_error() {
+			Some(error::Error::TemporarilyBanned) => RejectionReason::Banned,
+			Some(error::Error::PriorityTooLow { .. }) | Some(error::Error::TooLowPriority { .. }) =>
+				RejectionReason::PriorityTooLow,
+			Some(error::Error::LongevityTooShort { .. }) => RejectionReason::LongevityTooShort,
+			Some(error::Error::RejectedFutureTransaction)
+				| Some(error::Error::TooDistantFuture { .. })
+				| Some(error::Error::ChainTooDeep { .. }) => RejectionReason::FutureRejected,
+			Some(error::Error::TooManyWatchersForSource { .. }) => RejectionReason::RateLimited,
+			Some(error::Error::ImmediatelyDropped { .. })
+				| Some(error::Error::WeightLimitExceeded { .. })
+				| Some(error::Error::TypeLimitExceeded { .. }) => RejectionReason::PoolFull,
+			Some(error::Error::InvalidTransaction(InvalidTransaction::ExhaustsResources)) =>
+				RejectionReason::TooLarge,
+			Some(error::Error::InvalidTransaction(_))
+				| Some(error::Error::NoTagsProvided)
+				| Some(error::Error::DependencyCycle) => RejectionReason::InvalidValidity,
+			Some(error::Error::UnknownTransaction(_)) => RejectionReason::UnknownValidity,
+			Some(error::Error::AlreadyImported(_)) => RejectionReason::AlreadyImported,
+			Some(error::Error::Filtered) => RejectionReason::Filtered,
+			_ => RejectionReason::Other,
+		}
+	}
+
+	/// Returns `true` if resubmitting the same transaction is not going to help.
+	pub fn is_terminal(&self) -> bool {
+		match self {
+			RejectionReason::InvalidValidity | RejectionReason::Filtered => true,
+			_ => false,
+		}
+	}
+}
+
+/// Outcome of a single ready transactions revalidation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RevalidationOutcome {
+	/// Number of transactions that have been revalidated.
+	pub checked: usize,
+	/// Number of transactions that are still valid and were kept in the pool.
+	pub kept: usize,
+	/// Number of transactions that are no longer valid and were removed from the pool.
+	pub removed: usize,
+}
+
+/// Outcome of a single transaction submission.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubmitResult<Hash> {
+	/// Hash of the submitted transaction.
+	pub hash: Hash,
+	/// Hash of the ready transaction that got replaced by the submitted one, if any.
+	///
+	/// If several transactions were replaced at once, only one of them is reported.
+	pub replaced: Option<Hash>,
+}
+
+/// Cumulative outcomes of transactions in the future queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FuturePromotionStats {
+	/// Number of future transactions that became ready.
+	pub promoted: u64,
+	/// Number of future transactions removed because their longevity ended.
+	pub expired: u64,
+}
+
+/// Upper bounds of the validation time histogram buckets.
+const VALIDATION_TIME_BUCKETS: [Duration; 7] = [
+	Duration::from_millis(1),
+	Duration::from_millis(5),
+	Duration::from_millis(10),
+	Duration::from_millis(50),
+	Duration::from_millis(100),
+	Duration::from_millis(500),
+	Duration::from_secs(1),
+];
+
+/// Histogram of transaction validation times.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationTimeHistogram {
+	/// Upper bounds of the buckets with number of validations that took at most that long.
+	///
+	/// Buckets are cumulative, slower validations are only counted in `count`.
+	pub buckets: Vec<(Duration, u64)>,
+	/// Number of all observed validations.
+	pub count: u64,
+	/// Total time of all observed validations.
+	pub sum: Duration,
+}
+
+impl ValidationTimeHistogram {
+	fn new() -> Self {
+		ValidationTimeHistogram {
+			buckets: VALIDATION_TIME_BUCKETS.iter().map(|bound| (*bound, 0)).collect(),
+			count: 0,
+			sum: Default::default(),
+		}
+	}
+
+	fn observe(&mut self, time: Duration) {
+		for (_, count) in self.buckets.iter_mut().filter(|(bound, _)| time <= *bound) {
+			*count += 1;
+		}
+		self.count += 1;
+		self.sum += time;
+	}
+}
+
+/// Purpose of a transaction verification, determining which pool policies apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verification {
+	/// A new submission, subject to all pool policies.
+	Submission,
+	/// A trusted submission that bypasses the temporary bans.
+	ForcedSubmission,
+	/// Re-verification of a transaction that has already been in the pool.
+	///
+	/// Submission policies (pre-validation filter, minimal priority and longevity)
+	/// are not applied again.
+	Reverification,
+}
+
+/// Delay before the first validation retry, the following retries wait proportionally longer.
+const SUBMIT_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Maximal number of validity entries kept in the validation cache.
+const VALIDATION_CACHE_SIZE: usize = 8192;
+
+/// Runtime validity of transactions submitted at a single block, per source.
+struct ValidationCache<B: ChainApi> {
+	block: Option<BlockHash<B>>,
+	entries: HashMap<(ExHash<B>, TransactionSource), TransactionValidity>,
+}
+
+impl<B: ChainApi> Default for ValidationCache<B> {
+	fn default() -> Self {
+		ValidationCache {
+			block: None,
+			entries: Default::default(),
+		}
+	}
+}
+
+impl<B: ChainApi> ValidationCache<B> {
+	/// Returns validity of given transaction submitted from given source at given block, if it's known.
+	fn get(&self, block: &BlockHash<B>, hash: ExHash<B>, source: TransactionSource) -> Option<TransactionValidity> {
+		if self.block.as_ref() != Some(block) {
+			return None;
+		}
+		self.entries.get(&(hash, source)).cloned()
+	}
+
+	/// Remembers validity of given transaction, forgetting all entries of other blocks.
+	fn insert(
+		&mut self,
+		block: BlockHash<B>,
+		hash: ExHash<B>,
+		source: TransactionSource,
+		validity: TransactionValidity,
+	) {
+		if self.block.as_ref() != Some(&block) {
+			self.entries.clear();
+			self.block = Some(block);
+		}
+		if self.entries.len() < VALIDATION_CACHE_SIZE {
+			self.entries.insert((hash, source), validity);
+		}
+	}
+
+	/// Forgets all entries.
+	fn clear(&mut self) {
+		self.entries.clear();
+		self.block = None;
+	}
+}
+
+/// Transactions submitted before the block they become valid at (see `PoolExtensions::valid_from_hint`).
+struct TimeGated<B: ChainApi> {
+	/// Parked transactions keyed by the block they become valid at.
+	by_block: BTreeMap<u64, Vec<(ExHash<B>, TransactionSource, ExtrinsicFor<B>)>>,
+	/// Memory footprint of every parked transaction.
+	parked: HashMap<ExHash<B>, usize>,
+	/// Total memory footprint of parked transactions.
+	bytes: usize,
+}
+
+impl<B: ChainApi> Default for TimeGated<B> {
+	fn default() -> Self {
+		TimeGated {
+			by_block: Default::default(),
+			parked: Default::default(),
+			bytes: 0,
+		}
+	}
+}
+
+impl<B: ChainApi> TimeGated<B> {
+	/// Keeps aside given validated transaction until `valid_from` block.
+	///
+	/// Fails if the transaction is parked already or if parking it would exceed `limit`.
+	fn park(
+		&mut self,
+		valid_from: u64,
+		tx: &base::Transaction<ExHash<B>, ExtrinsicFor<B>>,
+		limit: &base::Limit,
+	) -> Result<(), error::Error> {
+		if self.parked.contains_key(&tx.hash) {
+			return Err(error::Error::AlreadyImported(Box::new(tx.hash.clone())));
+		}
+		let footprint = tx.memory_footprint();
+		if limit.is_exceeded(self.parked.len() + 1, self.bytes + footprint) {
+			return Err(limit.exceeded(error::ExceededLimit::Future, self.parked.len(), self.bytes));
+		}
+		self.parked.insert(tx.hash.clone(), footprint);
+		self.bytes += footprint;
+		self.by_block.entry(valid_from).or_insert_with(Vec::new).push((tx.hash.clone(), tx.source, tx.data.clone()));
+		Ok(())
+	}
+
+	/// Takes all transactions valid at given block.
+	fn take_due(&mut self, block_number: u64) -> Vec<(ExHash<B>, TransactionSource, ExtrinsicFor<B>)> {
+		let later = self.by_block.split_off(&block_number.saturating_add(1));
+		let due = std::mem::replace(&mut self.by_block, later);
+		let due = due.into_iter().flat_map(|(_, txs)| txs).collect::<Vec<_>>();
+		for (hash, _, _) in &due {
+			if let Some(footprint) = self.parked.remove(hash) {
+				self.bytes -= footprint;
+			}
+		}
+		due
+	}
+
+	/// Forgets given parked transaction.
+	fn unpark(&mut self, hash: &ExHash<B>) {
+		if let Some(footprint) = self.parked.remove(hash) {
+			self.bytes -= footprint;
+			for txs in self.by_block.values_mut() {
+				txs.retain(|(parked, _, _)| parked != hash);
+			}
+			self.by_block.retain(|_, txs| !txs.is_empty());
 		}
 	}
+
+	fn len(&self) -> usize {
+		self.parked.len()
+	}
+
+	fn is_empty(&self) -> bool {
+		self.parked.is_empty()
+	}
 }
 
 /// Extrinsics pool that performs validation.
 pub struct Pool<B: ChainApi> {
 	validated_pool: Arc<ValidatedPool<B>>,
+	/// Recently seen idempotency keys with the hash of the extrinsic and expiration time.
+	idempotency_keys: Arc<RwLock<HashMap<[u8; 32], (ExHash<B>, Instant)>>>,
+	/// Number of rejected submissions per reason.
+	rejections: Arc<Mutex<HashMap<RejectionReason, u64>>>,
+	/// Validity of recently validated transactions (see `AdmissionOptions::validation_cache`).
+	validation_cache: Arc<Mutex<ValidationCache<B>>>,
+	/// Ready transactions to be revalidated first (see `schedule_full_revalidation`).
+	stale_ready: Arc<Mutex<HashSet<ExHash<B>>>>,
+	/// Whether new submissions are accepted (see `set_accepting`).
+	accepting: Arc<AtomicBool>,
+	/// Histogram of validation times of submitted transactions.
+	validation_times: Arc<Mutex<ValidationTimeHistogram>>,
+	/// Transactions submitted before the block they become valid at.
+	time_gated: Arc<Mutex<TimeGated<B>>>,
 }
 
 impl<B: ChainApi> Pool<B> {
 	/// Create a new transaction pool.
 	pub fn new(options: Options, api: Arc<B>) -> Self {
+		Self::with_extensions(options, Default::default(), api)
+	}
+
+	/// Create a new transaction pool with custom extension points.
+	pub fn with_extensions(options: Options, extensions: ExtensionsFor<B>, api: Arc<B>) -> Self {
 		Pool {
-			validated_pool: Arc::new(ValidatedPool::new(options, api)),
+			validated_pool: Arc::new(ValidatedPool::new(options, extensions, api)),
+			idempotency_keys: Default::default(),
+			rejections: Default::default(),
+			validation_cache: Default::default(),
+			stale_ready: Default::default(),
+			accepting: Arc::new(AtomicBool::new(true)),
+			validation_times: Arc::new(Mutex::new(ValidationTimeHistogram::new())),
+			time_gated: Default::default(),
 		}
 	}
 
+	/// Replaces the chain api used to validate transactions, e.g. after a runtime upgrade.
+	///
+	/// All validations started after this call use the new api. Validations that are
+	/// already in flight complete with the previous api and their results are imported
+	/// as usual, so transactions already in the pool (or being imported concurrently)
+	/// should be revalidated (see `revalidate_ready`) if the validity rules changed.
+	/// The validation cache is cleared.
+	pub fn set_api(&self, api: Arc<B>) {
+		self.validated_pool.set_api(api);
+		self.validation_cache.lock().clear();
+	}
+
+	/// Returns the chain api currently used to validate transactions.
+	pub fn api(&self) -> Arc<B> {
+		self.validated_pool.api()
+	}
+
+	/// Starts or stops accepting new submissions.
+	///
+	/// While not accepting, submissions fail with `Error::NotAccepting`. Transactions already
+	/// in the pool are still maintained, and forced re-imports (e.g. of transactions from
+	/// retracted blocks) are not affected.
+	pub fn set_accepting(&self, accepting: bool) {
+		self.accepting.store(accepting, Ordering::Relaxed);
+	}
+
+	/// Returns `true` if new submissions are accepted.
+	pub fn is_accepting(&self) -> bool {
+		self.accepting.load(Ordering::Relaxed)
+	}
+
 	/// Imports a bunch of unverified extrinsics to the pool
 	pub async fn submit_at<T>(&self, at: &BlockId<B::Block>, xts: T, force: bool)
 		-> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error>
 	where
 		T: IntoIterator<Item=ExtrinsicFor<B>>
 	{
-		let validated_pool = self.validated_pool.clone();
-		self.verify(at, xts, force)
-			.map(move |validated_transactions| validated_transactions
-				.map(|validated_transactions| validated_pool.submit(validated_transactions
-					.into_iter()
-					.map(|(_, tx)| tx))))
-			.await
+		self.submit_at_with_source(at, TransactionSource::External, xts, force).await
 	}
 
-	/// Imports one unverified extrinsic to the pool
-	pub async fn submit_one(
+	/// Imports a bunch of unverified extrinsics coming from given source to the pool
+	pub async fn submit_at_with_source<T>(
 		&self,
 		at: &BlockId<B::Block>,
-		xt: ExtrinsicFor<B>,
-	) -> Result<ExHash<B>, B::Error> {
-		self.submit_at(at, std::iter::once(xt), false)
-			.map(|import_result| import_result.and_then(|mut import_result| import_result
-				.pop()
-				.expect("One extrinsic passed; one result returned; qed")
-			))
-			.await
+		source: TransactionSource,
+		xts: T,
+		force: bool,
+	) -> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error>
+	where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		if !force && !self.is_accepting() {
+			return Ok(xts.into_iter().map(|_| Err(self.rejection(error::Error::NotAccepting))).collect())
+		}
+		let results = self.import_at(at, source, xts, force).await;
+		// forced imports are re-imports (e.g. of retracted transactions), not new submissions
+		if let (false, Ok(results)) = (force, &results) {
+			results.iter().filter_map(|result| result.as_ref().err()).for_each(|e| self.note_rejection(e));
+		}
+		results
 	}
 
-	/// Import a single extrinsic and starts to watch their progress in the pool.
-	pub async fn submit_and_watch(
+	/// Same as `submit_at_with_source`, but regardless of `set_accepting`.
+	async fn import_at<T>(
 		&self,
 		at: &BlockId<B::Block>,
-		xt: ExtrinsicFor<B>,
-	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		source: TransactionSource,
+		xts: T,
+		force: bool,
+	) -> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error>
+	where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
 		let block_number = self.resolve_block_number(at)?;
-		let (_, tx) = self.verify_one(at, block_number, xt, false).await;
-		self.validated_pool.submit_and_watch(tx)
+		let verification = if force { Verification::ForcedSubmission } else { Verification::Submission };
+		let results = self.verify(at, xts.into_iter().map(|xt| (source, xt)), verification)
+			.map(|verified| verified.map(|verified| self.submit_or_park(block_number, verified)))
+			.await;
+		self.reload_spilled(at).await;
+		results
 	}
 
-	/// Revalidate all ready transactions.
+	/// Imports verified transactions, keeping aside the ones that become valid only at a later block.
 	///
-	/// Returns future that performs validation of all ready transactions and
-	/// then resubmits all transactions back to the pool.
-	pub async fn revalidate_ready(
+	/// Returns the results in the order of `verified`.
+	fn submit_or_park(
 		&self,
-		at: &BlockId<B::Block>,
-		max: Option<usize>,
-	) -> Result<(), B::Error> {
-		use std::time::Instant;
-		log::debug!(target: "txpool",
-			"Fetching ready transactions (up to: {})",
-			max.map(|x| format!("{}", x)).unwrap_or_else(|| "all".into())
-		);
-		let validated_pool = self.validated_pool.clone();
-		let ready = self.validated_pool.ready()
-			.map(|tx| tx.data.clone())
-			.take(max.unwrap_or_else(usize::max_value));
-
-		let now = Instant::now();
-		let revalidated_transactions = self.verify(at, ready, false).await?;
-		log::debug!(target: "txpool",
-			"Re-verified transactions, took {} ms. Resubmitting.",
-			now.elapsed().as_millis()
-		);
+		block_number: NumberFor<B>,
+		verified: Vec<Result<ValidatedTransactionFor<B>, B::Error>>,
+	) -> Vec<Result<ExHash<B>, B::Error>> {
+		let mut parked = Vec::with_capacity(verified.len());
+		let mut to_submit = Vec::new();
+		for verified in verified {
+			match verified.as_ref().ok().and_then(|tx| self.park_time_gated(block_number, tx)) {
+				Some(result) => parked.push(Some(result)),
+				None => {
+					parked.push(None);
+					to_submit.push(verified);
+				},
+			}
+		}
+		let mut submitted = self.submit_verified(to_submit).into_iter();
+		parked.into_iter()
+			.map(|parked| parked.unwrap_or_else(|| submitted.next().expect("One result per submitted transaction; qed")))
+			.collect()
+	}
+
+	/// Keeps aside a valid transaction that becomes valid only at a block after given one.
+	///
+	/// Returns `None` if the transaction is not time-gated and should be imported right away.
+	fn park_time_gated(
+		&self,
+		block_number: NumberFor<B>,
+		tx: &ValidatedTransactionFor<B>,
+	) -> Option<Result<ExHash<B>, B::Error>> {
+		let hint = self.validated_pool.extensions().valid_from_hint.as_ref()?;
+		let tx = match tx {
+			ValidatedTransaction::Valid(tx) => tx,
+			_ => return None,
+		};
+		let block_number = block_number.saturated_into::<u64>();
+		let valid_from = hint.valid_from(&tx.data.encode()).filter(|valid_from| *valid_from > block_number)?;
+		let parked = self.time_gated.lock().park(valid_from, tx, &self.validated_pool.options().future);
+		if parked.is_ok() {
+			log::debug!(target: self.log_target(), "[{:?}] Kept aside until block {}", tx.hash, valid_from);
+		}
+		Some(parked.map(|()| tx.hash.clone()).map_err(Into::into))
+	}
+
+	/// Imports time-gated transactions that became valid at given block.
+	///
+	/// Transactions are kept aside when submitted before the block returned by
+	/// `PoolExtensions::valid_from_hint`, this should be called for every new best block.
+	pub async fn submit_time_gated(&self, at: &BlockId<B::Block>) -> Vec<Result<ExHash<B>, B::Error>> {
+		if self.time_gated.lock().is_empty() {
+			return Vec::new();
+		}
+		let block_number = match self.resolve_block_number(at) {
+			Ok(block_number) => block_number.saturated_into::<u64>(),
+			Err(e) => {
+				log::debug!(target: self.log_target(), "Failed to import time-gated transactions: {:?}", e);
+				return Vec::new();
+			},
+		};
+		let due = self.time_gated.lock().take_due(block_number);
+
+		let mut results = Vec::new();
+		for (hash, source, xt) in due {
+			let result = match self.import_at(at, source, std::iter::once(xt), false).await {
+				Ok(mut imported) => imported.pop().expect("One extrinsic passed; one result returned; qed"),
+				Err(e) => Err(e),
+			};
+			if let Err(ref e) = result {
+				self.validated_pool.time_gated_rejected(&hash, e);
+			}
+			results.push(result);
+		}
+		results
+	}
+
+	/// Returns number of time-gated transactions kept aside (see `submit_time_gated`).
+	pub fn time_gated_count(&self) -> usize {
+		self.time_gated.lock().len()
+	}
+
+	/// Re-imports spilled future transactions which requirements got satisfied.
+	async fn reload_spilled(&self, at: &BlockId<B::Block>) {
+		loop {
+			let spilled = self.validated_pool.take_spilled();
+			if spilled.is_empty() {
+				return;
+			}
+			log::debug!(target: self.log_target(), "Reloading {} spilled transactions", spilled.len());
+			// spilled transactions are still part of the pool, not new submissions
+			match self.verify(at, spilled, Verification::Reverification).await {
+				Ok(verified) => {
+					self.validated_pool.submit(verified.into_iter().filter_map(Result::ok));
+				},
+				Err(e) => {
+					log::debug!(target: self.log_target(), "Failed to reload spilled transactions: {:?}", e);
+					return;
+				},
+			}
+		}
+	}
+
+	/// Returns number of future transactions spilled to disk (see `LimitOptions::future_spill_path`).
+	pub fn spilled_count(&self) -> usize {
+		self.validated_pool.spilled_count()
+	}
+
+	/// Imports one unverified extrinsic to the pool
+	pub async fn submit_one(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<ExHash<B>, B::Error> {
+		self.submit_one_with_source(at, TransactionSource::External, xt).await
+	}
+
+	/// Imports one unverified extrinsic coming from given source to the pool
+	pub async fn submit_one_with_source(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		xt: ExtrinsicFor<B>,
+	) -> Result<ExHash<B>, B::Error> {
+		self.submit_at_with_source(at, source, std::iter::once(xt), false)
+			.map(|import_result| import_result.and_then(|mut import_result| import_result
+				.pop()
+				.expect("One extrinsic passed; one result returned; qed")
+			))
+			.await
+	}
+
+	/// Imports one unverified extrinsic to the pool, reporting the transaction it replaced.
+	pub async fn submit_one_detailed(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<SubmitResult<ExHash<B>>, B::Error> {
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let (_, tx) = self.verify_one(
+			at,
+			block_number,
+			TransactionSource::External,
+			xt,
+			Verification::Submission,
+		).await?;
+		let result = match self.park_time_gated(block_number, &tx) {
+			Some(parked) => parked.map(|hash| SubmitResult { hash, replaced: None }),
+			None => self.validated_pool.submit_detailed(tx),
+		}.map_err(|e| { self.note_rejection(&e); e });
+		self.reload_spilled(at).await;
+		result
+	}
+
+	/// Replaces a transaction in the pool with a new one coming from given source.
+	///
+	/// The new extrinsic is validated first and `old` is only removed if the new one
+	/// enters the pool within its limits, so that there is no window without any of them
+	/// pending. Otherwise the pool is left intact and the error is returned.
+	///
+	/// If the new extrinsic becomes valid only at a later block, it's kept aside
+	/// (see `PoolExtensions::valid_from_hint`) and `old` stays in the pool, so that
+	/// it's still pending in the meantime.
+	pub async fn replace(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		old: ExHash<B>,
+		new: ExtrinsicFor<B>,
+	) -> Result<ExHash<B>, B::Error> {
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let (_, tx) = self.verify_one(at, block_number, source, new, Verification::Submission).await?;
+		match self.park_time_gated(block_number, &tx) {
+			Some(parked) => parked,
+			None => self.validated_pool.replace(&old, tx),
+		}.map_err(|e| { self.note_rejection(&e); e })
+	}
+
+	/// Imports one unverified extrinsic to the pool, tagged with an idempotency key.
+	///
+	/// If an extrinsic with the same key has been submitted recently (or is being submitted),
+	/// the hash of that extrinsic is returned and nothing is imported. Unlike hash-based
+	/// deduplication this also covers re-submissions of the same logical intent with different
+	/// bytes. Failed submissions release the key, so they can be retried.
+	pub async fn submit_one_idempotent(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+		key: [u8; 32],
+	) -> Result<ExHash<B>, B::Error> {
+		let now = Instant::now();
+		let hash = self.try_hash_and_length(&xt).map_err(|e| self.rejection(e))?.0;
+		{
+			let mut keys = self.idempotency_keys.write();
+			keys.retain(|_, (_, valid_until)| *valid_until >= now);
+			if let Some((hash, _)) = keys.get(&key) {
+				return Ok(hash.clone());
+			}
+			// reserved before the validation, so that concurrent retries don't import twice
+			let valid_until = now + self.validated_pool.options().admission.idempotency_key_ttl;
+			keys.insert(key, (hash.clone(), valid_until));
+		}
+
+		let result = self.submit_one(at, xt).await;
+		if result.is_err() {
+			let mut keys = self.idempotency_keys.write();
+			if keys.get(&key).map_or(false, |(reserved, _)| *reserved == hash) {
+				keys.remove(&key);
+			}
+		}
+		result
+	}
+
+	/// Imports a bunch of unverified extrinsics to the pool atomically.
+	///
+	/// All extrinsics are validated first and the batch is imported only if every one of them
+	/// is acceptable. Otherwise none of them is inserted and the index of the first failing
+	/// extrinsic is returned together with the error. Extrinsics that become valid only
+	/// at a later block are kept aside as part of the batch, and released if the batch fails.
+	pub async fn submit_batch_atomic<T>(&self, at: &BlockId<B::Block>, xts: T)
+		-> Result<Result<Vec<ExHash<B>>, (usize, B::Error)>, B::Error>
+	where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let mut validated_transactions = Vec::new();
+		for (idx, xt) in xts.into_iter().enumerate() {
+			match self.verify_one(
+				at,
+				block_number,
+				TransactionSource::External,
+				xt,
+				Verification::Submission,
+			).await {
+				Ok((_, tx)) => validated_transactions.push((idx, tx)),
+				Err(e) => return Ok(Err((idx, e))),
+			}
+		}
+
+		// every extrinsic gets either parked or submitted, keeping its position in `hashes`
+		let mut hashes = Vec::with_capacity(validated_transactions.len());
+		let mut parked = Vec::new();
+		let mut to_submit = Vec::new();
+		let mut result = Ok(());
+		for (idx, tx) in validated_transactions {
+			match self.park_time_gated(block_number, &tx) {
+				Some(Ok(hash)) => {
+					parked.push(hash.clone());
+					hashes.push(Some(hash));
+				},
+				Some(Err(e)) => {
+					result = Err((idx, e));
+					break;
+				},
+				None => {
+					hashes.push(None);
+					to_submit.push((idx, tx));
+				},
+			}
+		}
+		let result = result.and_then(|()| {
+			let (indices, to_submit): (Vec<_>, Vec<_>) = to_submit.into_iter().unzip();
+			self.validated_pool.submit_atomic(to_submit)
+				.map_err(|(idx, e)| (indices[idx], e))
+		});
+		let result = match result {
+			Ok(submitted) => {
+				let mut submitted = submitted.into_iter();
+				Ok(hashes.into_iter()
+					.map(|hash| hash.unwrap_or_else(|| submitted.next().expect("One hash per submitted transaction; qed")))
+					.collect())
+			},
+			Err((idx, e)) => {
+				let mut time_gated = self.time_gated.lock();
+				for hash in &parked {
+					time_gated.unpark(hash);
+				}
+				self.note_rejection(&e);
+				Err((idx, e))
+			},
+		};
+		Ok(result)
+	}
+
+	/// Imports a bunch of unverified extrinsics to the pool, stopping at the first failure.
+	///
+	/// Extrinsics are validated and imported one by one, extrinsics following the first
+	/// failing one are not even validated. Results are returned up to and including
+	/// the failing one. Unlike `submit_batch_atomic`, extrinsics imported before
+	/// the failure stay in the pool.
+	pub async fn submit_at_stop_on_error<T>(&self, at: &BlockId<B::Block>, xts: T)
+		-> Result<Vec<Result<ExHash<B>, B::Error>>, B::Error>
+	where
+		T: IntoIterator<Item=ExtrinsicFor<B>>
+	{
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let mut results = Vec::new();
+		for xt in xts {
+			let tx = match self.verify_one(
+				at,
+				block_number,
+				TransactionSource::External,
+				xt,
+				Verification::Submission,
+			).await {
+				Ok((_, tx)) => tx,
+				Err(e) => {
+					results.push(Err(e));
+					break;
+				},
+			};
+			let result = match self.park_time_gated(block_number, &tx) {
+				Some(parked) => parked,
+				None => self.validated_pool.submit(std::iter::once(tx))
+					.pop()
+					.expect("One extrinsic passed; one result returned; qed"),
+			};
+			let failed = match result {
+				Err(ref e) => {
+					self.note_rejection(e);
+					true
+				},
+				Ok(_) => false,
+			};
+			results.push(result);
+			if failed {
+				break;
+			}
+		}
+		Ok(results)
+	}
+
+	/// Import a single extrinsic and starts to watch their progress in the pool.
+	pub async fn submit_and_watch(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_source(at, TransactionSource::External, xt).await
+	}
+
+	/// Import a single extrinsic coming from given source and starts to watch its progress in the pool.
+	pub async fn submit_and_watch_with_source(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		xt: ExtrinsicFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(at, source, xt, WatchMode::All).await
+	}
+
+	/// Import a single extrinsic submitted by given source and starts to watch its progress in the pool.
+	///
+	/// `source_id` identifies the submitter (e.g. RPC connection), which is only allowed
+	/// to have `WatchOptions::max_watchers_per_source` watchers alive at the same time.
+	pub async fn submit_and_watch_from(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		source_id: &str,
+		xt: ExtrinsicFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		let slot = self.validated_pool.reserve_watcher_slot(source_id)
+			.map_err(|e| { self.note_rejection(&e); e })?;
+		self.submit_and_watch_with_source(at, source, xt).await
+			.map(|watcher| watcher.with_slot(slot))
+	}
+
+	/// Import a single extrinsic and starts to watch its progress in the pool,
+	/// including the approximate position of the extrinsic in the ready queue.
+	///
+	/// Positions are updated on every `notify_positions` call.
+	pub async fn submit_and_watch_with_position(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(at, TransactionSource::External, xt, WatchMode::WithPosition).await
+	}
+
+	/// Import a single extrinsic and starts to watch its progress in the pool,
+	/// reporting only the terminal status (see `TransactionStatus::is_terminal`).
+	pub async fn submit_and_watch_terminal_only(
+		&self,
+		at: &BlockId<B::Block>,
+		xt: ExtrinsicFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(at, TransactionSource::External, xt, WatchMode::TerminalOnly).await
+	}
+
+	async fn submit_and_watch_with_mode(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		xt: ExtrinsicFor<B>,
+		mode: WatchMode,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let (_, tx) = self.verify_one(at, block_number, source, xt, Verification::Submission).await?;
+		let result = match self.park_time_gated(block_number, &tx) {
+			Some(parked) => parked.map(|hash| self.validated_pool.watch_time_gated(hash, mode)),
+			None => self.validated_pool.submit_and_watch_with_mode(tx, mode),
+		};
+		result.map_err(|e| { self.note_rejection(&e); e })
+	}
+
+	/// Validates given extrinsic as if it was submitted from given source, without importing it to the pool.
+	///
+	/// Returns the validity exactly as reported by the runtime. The source-dependent submission
+	/// policies are applied on top of it: the pre-validation filter, `min_priority` and `min_longevity`
+	/// (local transactions are exempt from the latter two), so the result of a local and an external
+	/// call might differ. Valid transactions that violate them fail with the same error as
+	/// the submission would.
+	pub async fn compute_validity(
+		&self,
+		at: &BlockId<B::Block>,
+		source: TransactionSource,
+		xt: ExtrinsicFor<B>,
+	) -> Result<TransactionValidity, B::Error> {
+		let extensions = self.validated_pool.extensions();
+		let encoded = xt.encode();
+		if let Some(filter) = extensions.pre_validation_filter.as_ref() {
+			if !filter.allow(&encoded) {
+				return Err(error::Error::Filtered.into())
+			}
+		}
+		let context = extensions.context_provider.as_ref()
+			.and_then(|provider| provider.context(source, &encoded));
+		let validity = self.validate_with_retries(at, &xt, context).await?;
+		if let Ok(ref validity) = validity {
+			let priority = extensions.priority_normalizer.normalize(validity);
+			self.check_admission(source, priority, validity)?;
+		}
+		Ok(validity)
+	}
+
+	/// Checks the source-dependent submission policies of a valid transaction.
+	///
+	/// Local transactions are accepted regardless of their priority and longevity.
+	fn check_admission(
+		&self,
+		source: TransactionSource,
+		priority: Priority,
+		validity: &ValidTransaction,
+	) -> Result<(), error::Error> {
+		if source == TransactionSource::Local {
+			return Ok(())
+		}
+		let admission = &self.validated_pool.options().admission;
+		if let Some(min) = admission.min_priority.filter(|min| priority < *min) {
+			return Err(error::Error::PriorityTooLow { priority, min })
+		}
+		if let Some(min) = admission.min_longevity.filter(|min| validity.longevity < *min) {
+			return Err(error::Error::LongevityTooShort { longevity: validity.longevity, min })
+		}
+		Ok(())
+	}
+
+	/// Re-applies the priority normalizer to the ready transactions and reorders them.
+	///
+	/// Useful when the normalizer depends on external data (e.g. a fee oracle) that got updated.
+	pub fn reorder_ready(&self) {
+		self.validated_pool.reorder_ready()
+	}
+
+	/// Notify watchers about current positions of their transactions in the ready queue.
+	pub fn notify_positions(&self) {
+		self.validated_pool.notify_positions()
+	}
+
+	/// Marks all ready transactions as stale, without revalidating them.
+	///
+	/// Subsequent `revalidate_ready` calls revalidate stale transactions first,
+	/// so that batched revalidation eventually covers the whole ready set.
+	pub fn schedule_full_revalidation(&self) {
+		*self.stale_ready.lock() = self.validated_pool.ready().map(|tx| tx.hash.clone()).collect();
+	}
+
+	/// Revalidate all ready transactions.
+	///
+	/// Returns future that performs validation of all ready transactions and
+	/// then resubmits all transactions back to the pool.
+	/// Transactions marked as stale by `schedule_full_revalidation` are revalidated first.
+	pub async fn revalidate_ready(
+		&self,
+		at: &BlockId<B::Block>,
+		max: Option<usize>,
+	) -> Result<RevalidationOutcome, B::Error> {
+		log::debug!(target: self.log_target(),
+			"Fetching ready transactions (up to: {})",
+			max.map(|x| format!("{}", x)).unwrap_or_else(|| "all".into())
+		);
+		let validated_pool = self.validated_pool.clone();
+		let max = max.unwrap_or_else(usize::max_value);
+		let order = self.validated_pool.options().maintenance.revalidation_order;
+		let select = |mut txs: Vec<TransactionFor<B>>, max: usize| {
+			if txs.len() > max {
+				order.sort(&mut txs);
+				txs.truncate(max);
+			}
+			txs
+		};
+		let ready = {
+			let mut stale = self.stale_ready.lock();
+			if stale.is_empty() {
+				select(self.validated_pool.ready().collect(), max)
+			} else {
+				let (ready, rest): (Vec<_>, Vec<_>) = self.validated_pool.ready()
+					.partition(|tx| stale.contains(&tx.hash));
+				// forget stale transactions that are not ready anymore
+				*stale = ready.iter().map(|tx| tx.hash.clone()).collect();
+				let mut ready = select(ready, max);
+				for tx in &ready {
+					stale.remove(&tx.hash);
+				}
+				let remaining = max - ready.len();
+				ready.extend(select(rest, remaining));
+				ready
+			}
+		};
+
+		let now = Instant::now();
+		let verified = self.verify(
+			at,
+			ready.iter().map(|tx| (tx.source, tx.data.clone())),
+			Verification::Reverification,
+		).await?;
+		log::debug!(target: self.log_target(),
+			"Re-verified transactions, took {} ms. Resubmitting.",
+			now.elapsed().as_millis()
+		);
+
+		// transactions that failed to encode can't be revalidated, let's get rid of them
+		let mut revalidated_transactions = HashMap::new();
+		let mut encoding_failed = Vec::new();
+		for (tx, verified) in ready.iter().zip(verified) {
+			match verified {
+				Ok(verified) => {
+					revalidated_transactions.insert(tx.hash.clone(), verified);
+				},
+				Err(_) => encoding_failed.push(tx.hash.clone()),
+			}
+		}
+		if !encoding_failed.is_empty() {
+			log::error!(target: self.log_target(),
+				"Removing transactions that can't be encoded: {:?}", encoding_failed
+			);
+			self.validated_pool.remove_invalid(&encoding_failed);
+		}
+		// release references to the transactions, so that they don't have to be duplicated on resubmission
+		drop(ready);
+
+		let checked = revalidated_transactions.len() + encoding_failed.len();
+		let kept = revalidated_transactions.values()
+			.filter(|tx| match tx {
+				ValidatedTransaction::Valid(_) => true,
+				_ => false,
+			})
+			.count();
+		let outcome = RevalidationOutcome {
+			checked,
+			kept,
+			removed: checked - kept,
+		};
 
 		let now = Instant::now();
 		self.validated_pool.resubmit(revalidated_transactions);
-		log::debug!(target: "txpool",
+		log::debug!(target: self.log_target(),
 			"Resubmitted. Took {} ms. Status: {:?}",
 			now.elapsed().as_millis(),
 			validated_pool.status()
 		);
-		Ok(())
+		Ok(outcome)
 	}
 
 	/// Prunes known ready transactions.
 	///
 	/// Used to clear the pool from transactions that were part of recently imported block.
 	/// The main difference from the `prune` is that we do not revalidate any transactions
-	/// and ignore unknown passed hashes.
+	/// and ignore unknown passed hashes. Hence transactions conflicting with included ones
+	/// that are not in the pool (e.g. a different transaction with the same nonce)
+	/// are not detected, `prune` has to be used for that.
 	pub fn prune_known(&self, at: &BlockId<B::Block>, hashes: &[ExHash<B>]) -> Result<(), B::Error> {
 		// Get details of all extrinsics that are already in the pool
 		let in_pool_tags = self.validated_pool.extrinsics_tags(hashes)
@@ -223,6 +1597,27 @@ impl<B: ChainApi> Pool<B> {
 		self.validated_pool.fire_pruned(at, pruned_transactions)
 	}
 
+	/// Notify watchers of included transactions about a new best block.
+	///
+	/// Should be called for every new best block after it's pruned,
+	/// see `WatchOptions::finality_timeout_blocks`.
+	pub fn on_best_block(&self, block_number: NumberFor<B>) {
+		self.validated_pool.on_best_block(block_number.saturated_into::<u64>())
+	}
+
+	/// Notify watchers of included transactions about finalization of given block.
+	///
+	/// Watching of transactions included in that block (or its ancestors) ends.
+	pub fn on_block_finalized(&self, at: &BlockId<B::Block>) -> Result<(), B::Error> {
+		let api = self.validated_pool.api();
+		let header_hash = api.block_id_to_hash(at)?
+			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
+		let block_number = api.block_id_to_number(at)?
+			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
+		self.validated_pool.on_block_finalized(&header_hash, block_number.saturated_into::<u64>());
+		Ok(())
+	}
+
 	/// Prunes ready transactions.
 	///
 	/// Used to clear the pool from transactions that were part of recently imported block.
@@ -236,23 +1631,34 @@ impl<B: ChainApi> Pool<B> {
 		extrinsics: &[ExtrinsicFor<B>],
 	) -> Result<(), B::Error> {
 		log::debug!(
-			target: "txpool",
+			target: self.log_target(),
 			"Starting pruning of block {:?} (extrinsics: {})",
 			at,
 			extrinsics.len()
 		);
+		// Extrinsics that can't be encoded can't be in the pool and don't provide anything we could prune
+		let extrinsics = extrinsics.iter()
+			.filter_map(|extrinsic| match self.try_hash_and_length(extrinsic) {
+				Ok((hash, _)) => Some((extrinsic, hash)),
+				Err(e) => {
+					log::error!(target: self.log_target(), "[{:?}] Skipping pruned transaction: {}", at, e);
+					None
+				},
+			})
+			.collect::<Vec<_>>();
 		// Get details of all extrinsics that are already in the pool
-		let in_pool_hashes = extrinsics.iter().map(|extrinsic| self.hash_of(extrinsic)).collect::<Vec<_>>();
+		let in_pool_hashes = extrinsics.iter().map(|(_, hash)| hash.clone()).collect::<Vec<_>>();
 		let in_pool_tags = self.validated_pool.extrinsics_tags(&in_pool_hashes);
 
-		// Zip the ones from the pool with the full list (we get pairs `(Extrinsic, Option<Vec<Tag>>)`)
-		let all = extrinsics.iter().zip(in_pool_tags.into_iter());
+		// Zip the ones from the pool with the full list (we get pairs `((Extrinsic, Hash), Option<Vec<Tag>>)`)
+		let all = extrinsics.into_iter().zip(in_pool_tags.into_iter());
 
 		let mut future_tags = Vec::new();
-		for (extrinsic, in_pool_tags) in all {
-			match in_pool_tags {
+		let mut providers = HashMap::new();
+		for ((extrinsic, hash), in_pool_tags) in all {
+			let tags = match in_pool_tags {
 				// reuse the tags for extrinsics that were found in the pool
-				Some(tags) => future_tags.extend(tags),
+				Some(tags) => tags,
 				// if it's not found in the pool query the runtime at parent block
 				// to get validity info and tags that the extrinsic provides.
 				None => {
@@ -260,14 +1666,17 @@ impl<B: ChainApi> Pool<B> {
 						.validate_transaction(parent, extrinsic.clone())
 						.await;
 
-					if let Ok(Ok(validity)) = validity {
-						future_tags.extend(validity.provides);
+					match validity {
+						Ok(Ok(validity)) => validity.provides,
+						_ => Vec::new(),
 					}
 				},
-			}
+			};
+			providers.extend(tags.iter().map(|tag| (tag.clone(), hash.clone())));
+			future_tags.extend(tags);
 		}
 
-		self.prune_tags(at, future_tags, in_pool_hashes).await
+		self.prune_tags_with_providers(at, future_tags, in_pool_hashes, providers).await
 	}
 
 	/// Prunes ready transactions that provide given list of tags.
@@ -297,7 +1706,23 @@ impl<B: ChainApi> Pool<B> {
 		tags: impl IntoIterator<Item=Tag>,
 		known_imported_hashes: impl IntoIterator<Item=ExHash<B>> + Clone,
 	) -> Result<(), B::Error> {
-		log::debug!(target: "txpool", "Pruning at {:?}", at);
+		self.prune_tags_with_providers(at, tags, known_imported_hashes, HashMap::new()).await
+	}
+
+	/// Same as `prune_tags`, but knowing which imported transactions provide which tags.
+	///
+	/// Pruned transactions that are not part of `known_imported_hashes`, but provide
+	/// a tag provided by an imported transaction conflict with it. If they turn out
+	/// to be invalid after reverification they are reported as `Usurped` by the imported
+	/// transaction instead of being reported as included in the block.
+	async fn prune_tags_with_providers(
+		&self,
+		at: &BlockId<B::Block>,
+		tags: impl IntoIterator<Item=Tag>,
+		known_imported_hashes: impl IntoIterator<Item=ExHash<B>> + Clone,
+		providers: HashMap<Tag, ExHash<B>>,
+	) -> Result<(), B::Error> {
+		log::debug!(target: self.log_target(), "Pruning at {:?}", at);
 		// Prune all transactions that provide given tags
 		let prune_status = match self.validated_pool.prune_tags(tags) {
 			Ok(prune_status) => prune_status,
@@ -309,22 +1734,42 @@ impl<B: ChainApi> Pool<B> {
 		// inputs are pruned so such transaction would go to future again.
 		self.validated_pool.ban(&std::time::Instant::now(), known_imported_hashes.clone().into_iter());
 
+		// Pruned transactions that provide the same tag as a different imported transaction
+		// were not included, but conflict with the imported one.
+		let imported = known_imported_hashes.clone().into_iter().collect::<HashSet<_>>();
+		let conflicts = prune_status.pruned.iter()
+			.filter(|tx| !imported.contains(&tx.hash))
+			.filter_map(|tx| tx.provides.iter()
+				.filter_map(|tag| providers.get(tag))
+				.find(|provider| **provider != tx.hash)
+				.map(|provider| (tx.hash.clone(), provider.clone()))
+			)
+			.collect::<HashMap<_, _>>();
+
 		// Try to re-validate pruned transactions since some of them might be still valid.
 		// note that `known_imported_hashes` will be rejected here due to temporary ban.
 		let pruned_hashes = prune_status.pruned.iter().map(|tx| tx.hash.clone()).collect::<Vec<_>>();
-		let pruned_transactions = prune_status.pruned.into_iter().map(|tx| tx.data.clone());
+		let pruned_transactions = prune_status.pruned.into_iter().map(|tx| (tx.source, tx.data.clone()));
 
-		let reverified_transactions = self.verify(at, pruned_transactions, false).await?;
+		let verified = self.verify(at, pruned_transactions, Verification::Reverification).await?;
+		// transactions that failed to encode stay pruned
+		let (pruned_hashes, reverified_transactions): (Vec<_>, Vec<_>) = pruned_hashes.into_iter()
+			.zip(verified)
+			.filter_map(|(hash, verified)| verified.ok().map(|verified| (hash, verified)))
+			.unzip();
 
-		log::trace!(target: "txpool", "Prunning at {:?}. Resubmitting transactions.", at);
+		log::trace!(target: self.log_target(), "Prunning at {:?}. Resubmitting transactions.", at);
 		// And finally - submit reverified transactions back to the pool
 
-		self.validated_pool.resubmit_pruned(
+		let result = self.validated_pool.resubmit_pruned(
 			&at,
 			known_imported_hashes,
 			pruned_hashes,
-			reverified_transactions.into_iter().map(|(_, xt)| xt).collect(),
-		)
+			reverified_transactions,
+			conflicts,
+		);
+		self.reload_spilled(at).await;
+		result
 	}
 
 	/// Return an event stream of notifications for when transactions are imported to the pool.
@@ -335,6 +1780,19 @@ impl<B: ChainApi> Pool<B> {
 		self.validated_pool.import_notification_stream()
 	}
 
+	/// Return an event stream of transactions imported to the pool, buffering up to `capacity` hashes.
+	///
+	/// Unlike `import_notification_stream` the memory is bounded, oldest notifications
+	/// are dropped if the subscriber doesn't keep up.
+	pub fn import_notification_stream_bounded(&self, capacity: usize) -> BoundedStream<ExHash<B>> {
+		self.validated_pool.import_notification_stream_bounded(capacity)
+	}
+
+	/// Return an event stream of status changes of all transactions in the pool.
+	pub fn all_status_stream(&self) -> AllStatusStream<ExHash<B>, BlockHash<B>> {
+		self.validated_pool.all_status_stream()
+	}
+
 	/// Invoked when extrinsics are broadcasted.
 	pub fn on_broadcasted(&self, propagated: HashMap<ExHash<B>, Vec<String>>) {
 		self.validated_pool.on_broadcasted(propagated)
@@ -345,19 +1803,357 @@ impl<B: ChainApi> Pool<B> {
 		self.validated_pool.remove_invalid(hashes)
 	}
 
-	/// Get an iterator for ready transactions ordered by priority
-	pub fn ready(&self) -> impl Iterator<Item=TransactionFor<B>> {
-		self.validated_pool.ready()
+	/// Return an event stream of transactions that got permanently rejected or removed as invalid.
+	pub fn deadletter_stream(&self) -> DeadletterStream<ExHash<B>> {
+		self.validated_pool.deadletter_stream()
 	}
 
-	/// Returns pool status.
-	pub fn status(&self) -> PoolStatus {
-		self.validated_pool.status()
+	/// Moves a future transaction to the ready queue, ignoring its unsatisfied requirements.
+	///
+	/// NOTE this bypasses dependency checks and is meant to be used for testing only.
+	pub fn force_promote(&self, hash: &ExHash<B>) -> Result<(), B::Error> {
+		self.validated_pool.force_promote(hash)
 	}
 
-	/// Returns transaction hash
-	pub fn hash_of(&self, xt: &ExtrinsicFor<B>) -> ExHash<B> {
-		self.validated_pool.api().hash_and_length(xt).0
+	/// Pins a transaction, so that it's never evicted because of the pool limits.
+	///
+	/// Pinned transactions are still pruned when included and removed when invalid.
+	/// Returns `false` if the transaction is not in the pool.
+	pub fn pin(&self, hash: &ExHash<B>) -> bool {
+		self.validated_pool.pin(hash)
+	}
+
+	/// Unpins a transaction.
+	///
+	/// Returns `false` if the transaction was not pinned.
+	pub fn unpin(&self, hash: &ExHash<B>) -> bool {
+		self.validated_pool.unpin(hash)
+	}
+
+	/// Takes up to `limit` best ready transactions out of the ready set, e.g. to build
+	/// a block elsewhere.
+	///
+	/// Reserved transactions stay in the pool, but are not returned by `ready` (neither are
+	/// transactions depending on them) until they are released or leave the pool.
+	pub fn reserve_ready(&self, limit: usize) -> Vec<TransactionFor<B>> {
+		self.validated_pool.reserve_ready(limit)
+	}
+
+	/// Returns reserved transactions back to the ready set.
+	///
+	/// Returns number of transactions that were reserved.
+	pub fn release_reserved(&self, hashes: &[ExHash<B>]) -> usize {
+		self.validated_pool.release_reserved(hashes)
+	}
+
+	/// Get an iterator for ready transactions ordered by priority
+	pub fn ready(&self) -> impl Iterator<Item=TransactionFor<B>> {
+		self.validated_pool.ready()
+	}
+
+	/// Returns a page of up to `limit` ready transactions following the one at `cursor`.
+	///
+	/// Transactions are returned in the same order as by `ready`, starting with the best one
+	/// if `cursor` is `None`. The returned cursor points to the last transaction of the page
+	/// and is `None` if there are no more transactions.
+	///
+	/// The order is stable as long as `generation` doesn't change. Transactions imported
+	/// between page requests might be skipped if they're ordered before the cursor, and
+	/// if the transaction at the cursor is no longer ready an empty page is returned,
+	/// so the pagination has to start over.
+	pub fn ready_page(
+		&self,
+		cursor: Option<&ExHash<B>>,
+		limit: usize,
+	) -> (Vec<TransactionFor<B>>, Option<ExHash<B>>) {
+		let mut ready = self.ready();
+		if let Some(cursor) = cursor {
+			if !ready.any(|tx| tx.hash == *cursor) {
+				return (Vec::new(), None);
+			}
+		}
+
+		let page = ready.by_ref().take(limit).collect::<Vec<_>>();
+		let next = match (page.last(), ready.next()) {
+			(Some(last), Some(_)) => Some(last.hash.clone()),
+			_ => None,
+		};
+		(page, next)
+	}
+
+	/// Returns total value committed by ready and future transactions providing a tag
+	/// that starts with given prefix, e.g. pending transactions of some account.
+	///
+	/// Value of transactions is determined by `PoolExtensions::value_extractor`,
+	/// with the default extractor it's always `0`.
+	pub fn committed_value_for(&self, provides_prefix: &[u8]) -> u128 {
+		self.validated_pool.committed_value_for(provides_prefix)
+	}
+
+	/// Returns all transactions in the future queue.
+	pub fn futures(&self) -> Vec<TransactionFor<B>> {
+		self.validated_pool.futures()
+	}
+
+	/// Returns hashes of all transactions in the future queue.
+	pub fn future_hashes(&self) -> Vec<ExHash<B>> {
+		self.validated_pool.future_hashes()
+	}
+
+	/// Returns current pressure level of the pool.
+	///
+	/// The usage of the pool is the highest fraction of any of the ready and future
+	/// limits, either by count or by size, levels are given by `LimitOptions::pressure_thresholds`.
+	pub fn pressure_level(&self) -> PressureLevel {
+		let status = self.status();
+		let options = self.validated_pool.options();
+		let fraction = |used: usize, limit: usize| used as f64 / limit.max(1) as f64;
+		let usage = [
+			fraction(status.ready, options.ready.count),
+			fraction(status.ready_bytes, options.ready.total_bytes),
+			fraction(status.future, options.future.count),
+			fraction(status.future_bytes, options.future.total_bytes),
+		].iter().cloned().fold(0.0, f64::max);
+		options.limits.pressure_thresholds.level(usage)
+	}
+
+	/// Get an iterator for ready transactions ordered by priority per encoded byte
+	pub fn ready_by_fee_density(&self) -> impl Iterator<Item=TransactionFor<B>> {
+		self.validated_pool.ready_by_fee_density()
+	}
+
+	/// Get ready transactions shuffled deterministically by given seed, e.g. a block hash
+	pub fn ready_shuffled(&self, seed: [u8; 32]) -> Vec<TransactionFor<B>> {
+		self.validated_pool.ready_shuffled(seed)
+	}
+
+	/// Returns all transactions in the pool together with their required and provided tags.
+	pub fn tag_graph(&self) -> Vec<(ExHash<B>, Vec<Tag>, Vec<Tag>)> {
+		self.validated_pool.tag_graph()
+	}
+
+	/// Get an iterator for ready transactions that should be propagated, ordered by priority
+	pub fn ready_propagable(&self) -> impl Iterator<Item=TransactionFor<B>> {
+		self.validated_pool.ready_propagable()
+	}
+
+	/// Get an iterator for at most `max` best ready transactions, ordered by priority
+	pub fn ready_limited(&self, max: usize) -> impl Iterator<Item=TransactionFor<B>> {
+		self.ready().take(max)
+	}
+
+	/// Get an iterator for the best ready transactions with total weight up to `max`
+	pub fn ready_within_weight(&self, max: Weight) -> impl Iterator<Item=TransactionFor<B>> {
+		self.validated_pool.ready_within_weight(max)
+	}
+
+	/// Returns the best ready transactions with total size up to `max_bytes` and total
+	/// weight up to `max_weight`, i.e. the ones the next block would be built from.
+	pub fn next_block_candidates(&self, max_bytes: usize, max_weight: Weight) -> Vec<TransactionFor<B>> {
+		self.validated_pool.next_block_candidates(max_bytes, max_weight)
+	}
+
+	/// Imports a bundle of extrinsics that are only picked by `next_block_candidates`
+	/// all together.
+	///
+	/// Nothing is imported if any of the extrinsics is not valid. If the import of
+	/// some of them fails, the others stay in the pool, but the bundle is never picked.
+	/// Members that become valid only at a later block are kept aside as usual
+	/// and the bundle is not picked until they enter the pool.
+	pub async fn submit_bundle(
+		&self,
+		at: &BlockId<B::Block>,
+		xts: Vec<ExtrinsicFor<B>>,
+		id: base::BundleId,
+	) -> Result<Vec<ExHash<B>>, B::Error> {
+		self.ensure_accepting()?;
+		let block_number = self.resolve_block_number(at)?;
+		let mut validated = Vec::with_capacity(xts.len());
+		for xt in xts {
+			let (hash, tx) = self.verify_one(
+				at,
+				block_number,
+				TransactionSource::External,
+				xt,
+				Verification::Submission,
+			).await?;
+			match tx {
+				ValidatedTransaction::Valid(_) => validated.push((hash, tx)),
+				ValidatedTransaction::Invalid(hash, e) => {
+					self.validated_pool.ban(&Instant::now(), std::iter::once(hash));
+					self.note_rejection(&e);
+					return Err(e);
+				},
+				ValidatedTransaction::Unknown(_, e) => {
+					self.note_rejection(&e);
+					return Err(e);
+				},
+			}
+		}
+
+		let hashes = validated.iter().map(|(hash, _)| hash.clone()).collect::<Vec<_>>();
+		let mut results = Vec::with_capacity(validated.len());
+		let mut to_submit = Vec::new();
+		for (_, tx) in validated {
+			match self.park_time_gated(block_number, &tx) {
+				Some(parked) => results.push(parked),
+				None => to_submit.push(tx),
+			}
+		}
+		results.extend(self.validated_pool.submit(to_submit));
+		// members that failed to enter the pool keep the bundle from being picked
+		self.validated_pool.set_bundle(&hashes, id);
+		for result in results {
+			result.map_err(|e| { self.note_rejection(&e); e })?;
+		}
+		Ok(hashes)
+	}
+
+	/// Returns up to `limit` transactions that spent the longest time in the pool.
+	///
+	/// Both ready and future transactions are considered, oldest first.
+	pub fn oldest_pending(&self, limit: usize) -> Vec<TransactionFor<B>> {
+		self.validated_pool.oldest_pending(limit)
+	}
+
+	/// Returns transactions invalidated during revalidation together with the reason.
+	pub fn quarantined(&self) -> Vec<(TransactionFor<B>, String)> {
+		self.validated_pool.quarantined()
+	}
+
+	/// Returns pool status.
+	pub fn status(&self) -> PoolStatus {
+		self.validated_pool.status()
+	}
+
+	/// Returns aggregate statistics of the future queue.
+	pub fn future_status(&self) -> FutureStatus {
+		self.validated_pool.future_status()
+	}
+
+	/// Returns number of transactions per source.
+	pub fn count_by_source(&self) -> SourceCounts {
+		self.validated_pool.count_by_source()
+	}
+
+	/// Returns number of distinct senders of transactions in the pool.
+	///
+	/// Senders are derived from the first `provides` tag of each transaction,
+	/// assuming that it encodes the sender (as `(AccountId, Index)` in standard runtimes).
+	pub fn distinct_senders(&self) -> usize {
+		self.validated_pool.distinct_senders()
+	}
+
+	/// Returns sender tags of transactions in the pool (see `distinct_senders`).
+	pub fn sender_tags(&self) -> Vec<Tag> {
+		self.validated_pool.sender_tags()
+	}
+
+	/// Returns true if the pool is close to its limits.
+	pub fn is_near_capacity(&self) -> bool {
+		self.validated_pool.is_near_capacity()
+	}
+
+	/// Returns number of active transaction watchers.
+	pub fn watchers_count(&self) -> usize {
+		self.validated_pool.watchers_count()
+	}
+
+	/// Returns number of submissions rejected since the pool was created, per reason.
+	///
+	/// Forced re-imports (see `submit_at`) and maintenance of the pool are not accounted.
+	pub fn rejection_stats(&self) -> HashMap<RejectionReason, u64> {
+		self.rejections.lock().clone()
+	}
+
+	/// Returns a counter increased on every structural change of the pool.
+	pub fn generation(&self) -> u64 {
+		self.validated_pool.generation()
+	}
+
+	/// Returns approximate memory used by the pool.
+	pub fn memory_usage(&self) -> usize {
+		self.validated_pool.memory_usage()
+	}
+
+	/// Returns transaction hash
+	pub fn hash_of(&self, xt: &ExtrinsicFor<B>) -> ExHash<B> {
+		self.validated_pool.api().hash_and_length(xt).0
+	}
+
+	/// Computes hash and encoded length of the transaction.
+	///
+	/// Fails with `Error::EncodingFailed` instead of panicking if the transaction can't be encoded.
+	pub fn try_hash_and_length(&self, xt: &ExtrinsicFor<B>) -> Result<(ExHash<B>, usize), error::Error> {
+		let api = self.validated_pool.api();
+		panic::catch_unwind(panic::AssertUnwindSafe(|| api.hash_and_length(xt)))
+			.map_err(|_| error::Error::EncodingFailed)
+	}
+
+	/// Records rejected submission.
+	fn note_rejection(&self, error: &B::Error) {
+		*self.rejections.lock().entry(RejectionReason::from_error(error)).or_insert(0) += 1;
+	}
+
+	/// Records rejected submission, returning the error.
+	fn rejection(&self, error: error::Error) -> B::Error {
+		let error = error.into();
+		self.note_rejection(&error);
+		error
+	}
+
+	/// Fails with `Error::NotAccepting` if new submissions are not accepted (see `set_accepting`).
+	fn ensure_accepting(&self) -> Result<(), B::Error> {
+		if self.is_accepting() {
+			Ok(())
+		} else {
+			Err(self.rejection(error::Error::NotAccepting))
+		}
+	}
+
+	/// Returns number of future transactions that became ready or expired since the pool was created.
+	pub fn future_promotion_stats(&self) -> FuturePromotionStats {
+		self.validated_pool.future_promotion_stats()
+	}
+
+	/// Returns histogram of validation times of transactions submitted so far.
+	pub fn validation_time_histogram(&self) -> ValidationTimeHistogram {
+		self.validation_times.lock().clone()
+	}
+
+	/// Returns the configured log target.
+	pub fn log_target(&self) -> &'static str {
+		self.validated_pool.options().log_target
+	}
+
+	/// Returns `true` if transactions included in maintained blocks are pruned automatically.
+	pub fn auto_prune(&self) -> bool {
+		self.validated_pool.options().maintenance.auto_prune
+	}
+
+	/// Returns the configured number of maintained blocks the pool snapshots are kept for.
+	pub fn history_depth(&self) -> usize {
+		self.validated_pool.options().maintenance.history_depth
+	}
+
+	/// Returns the configured maximal time a revalidation might stay in progress.
+	pub fn revalidation_timeout(&self) -> Option<std::time::Duration> {
+		self.validated_pool.options().maintenance.revalidation_timeout
+	}
+
+	/// Returns `true` if superseded maintenance calls should be skipped.
+	pub fn supersede_maintenance(&self) -> bool {
+		self.validated_pool.options().maintenance.supersede_maintenance
+	}
+
+	/// Returns the configured number of blocks retracted transactions are retried for.
+	pub fn reorg_grace_blocks(&self) -> u32 {
+		self.validated_pool.options().maintenance.reorg_grace_blocks
+	}
+
+	/// Returns the configured number of blocks after which future transactions are stuck.
+	pub fn future_stuck_threshold(&self) -> Option<u32> {
+		self.validated_pool.options().maintenance.future_stuck_threshold
 	}
 
 	/// Resolves block number by id.
@@ -368,63 +2164,182 @@ impl<B: ChainApi> Pool<B> {
 	}
 
 	/// Returns future that validates a bunch of transactions at given block.
+	///
+	/// Results are returned in the order of `xts`, transactions that can't be encoded
+	/// yield `Error::EncodingFailed`.
 	async fn verify(
 		&self,
 		at: &BlockId<B::Block>,
-		xts: impl IntoIterator<Item=ExtrinsicFor<B>>,
-		force: bool,
-	) -> Result<HashMap<ExHash<B>, ValidatedTransactionFor<B>>, B::Error> {
+		xts: impl IntoIterator<Item=(TransactionSource, ExtrinsicFor<B>)>,
+		verification: Verification,
+	) -> Result<Vec<Result<ValidatedTransactionFor<B>, B::Error>>, B::Error> {
 		// we need a block number to compute tx validity
 		let block_number = self.resolve_block_number(at)?;
-		let mut result = HashMap::new();
-
-		for xt in xts {
-			let (hash, validated_tx) = self.verify_one(at, block_number, xt, force).await;
-			result.insert(hash, validated_tx);
+		let mut result = Vec::new();
+
+		for (source, xt) in xts {
+			let hash_and_length = match self.try_hash_and_length(&xt) {
+				Ok(hash_and_length) => hash_and_length,
+				Err(e) => {
+					log::error!(target: self.log_target(), "Skipping transaction: {}", e);
+					result.push(Err(e.into()));
+					continue;
+				},
+			};
+			let (_, validated_tx) = self.verify_hashed(at, block_number, source, xt, hash_and_length, verification).await;
+			result.push(Ok(validated_tx));
 		}
 
 		Ok(result)
 	}
 
+	/// Imports verified transactions, returning the results in the order of `verified`.
+	fn submit_verified(
+		&self,
+		verified: Vec<Result<ValidatedTransactionFor<B>, B::Error>>,
+	) -> Vec<Result<ExHash<B>, B::Error>> {
+		let total = verified.len();
+		let mut failed = Vec::new();
+		let transactions = verified.into_iter()
+			.enumerate()
+			.filter_map(|(idx, verified)| verified.map_err(|e| failed.push((idx, e))).ok())
+			.collect::<Vec<_>>();
+		let mut imported = self.validated_pool.submit(transactions).into_iter();
+		let mut failed = failed.into_iter().peekable();
+		(0..total)
+			.map(|idx| match failed.peek() {
+				Some((failed_idx, _)) if *failed_idx == idx =>
+					Err(failed.next().expect("Just peeked; qed").1),
+				_ => imported.next().expect("One result per submitted transaction; qed"),
+			})
+			.collect()
+	}
+
 	/// Returns future that validates single transaction at given block.
+	///
+	/// Fails with `Error::EncodingFailed` (recorded as a rejection) if the transaction can't be encoded.
 	async fn verify_one(
 		&self,
 		block_id: &BlockId<B::Block>,
 		block_number: NumberFor<B>,
+		source: TransactionSource,
 		xt: ExtrinsicFor<B>,
-		force: bool,
+		verification: Verification,
+	) -> Result<(ExHash<B>, ValidatedTransactionFor<B>), B::Error> {
+		let hash_and_length = self.try_hash_and_length(&xt).map_err(|e| self.rejection(e))?;
+		Ok(self.verify_hashed(block_id, block_number, source, xt, hash_and_length, verification).await)
+	}
+
+	/// Same as `verify_one`, but with already computed hash and encoded length of the transaction.
+	async fn verify_hashed(
+		&self,
+		block_id: &BlockId<B::Block>,
+		block_number: NumberFor<B>,
+		source: TransactionSource,
+		xt: ExtrinsicFor<B>,
+		(hash, bytes): (ExHash<B>, usize),
+		verification: Verification,
 	) -> (ExHash<B>, ValidatedTransactionFor<B>) {
-		let (hash, bytes) = self.validated_pool.api().hash_and_length(&xt);
-		if !force && self.validated_pool.is_banned(&hash) {
+		// re-verified transactions were noted and subject to the pool policies on submission already
+		let is_submission = verification != Verification::Reverification;
+		if is_submission {
+			self.validated_pool.note_seen(&hash);
+		}
+		if verification != Verification::ForcedSubmission && self.validated_pool.is_banned(&hash) {
 			return (
 				hash.clone(),
 				ValidatedTransaction::Invalid(hash, error::Error::TemporarilyBanned.into()),
 			)
 		}
 
-		let validation_result = self.validated_pool.api().validate_transaction(block_id, xt.clone()).await;
+		let extensions = self.validated_pool.extensions();
+		let pre_validation_filter = extensions.pre_validation_filter.as_ref().filter(|_| is_submission);
+		let type_limits = &self.validated_pool.options().limits.type_limits;
+		let encoded = if pre_validation_filter.is_some()
+			|| extensions.context_provider.is_some()
+			|| !type_limits.is_empty()
+		{
+			xt.encode()
+		} else {
+			Vec::new()
+		};
+		let type_index = type_limits.iter().position(|(prefix, _)| encoded.starts_with(prefix));
+		let is_filtered = pre_validation_filter
+			.map(|filter| !filter.allow(&encoded))
+			.unwrap_or(false);
+		if is_filtered {
+			// it's a local policy, not a validity issue => not banned
+			return (hash.clone(), ValidatedTransaction::Unknown(hash, error::Error::Filtered.into()))
+		}
+
+		let context = extensions.context_provider.as_ref()
+			.and_then(|provider| provider.context(source, &encoded));
+		let validation_started = Instant::now();
+		let validation_result = match context {
+			Some(context) => self.validate_with_retries(block_id, &xt, Some(context)).await,
+			None if is_submission => self.validate_cached(block_id, &hash, source, &xt).await,
+			None => self.validate_with_retries(block_id, &xt, None).await,
+		};
+		let validation_time = validation_started.elapsed();
+		self.validation_times.lock().observe(validation_time);
 
 		let status = match validation_result {
 			Ok(status) => status,
 			Err(e) => return (hash.clone(), ValidatedTransaction::Invalid(hash, e)),
 		};
 
+		let options = self.validated_pool.options();
 		let validity = match status {
 			Ok(validity) => {
+				let block_number = block_number.saturated_into::<u64>();
+				let distance = options.admission.max_future_distance.and_then(|max| extensions.future_distance
+					.distance(block_number, &validity)
+					.filter(|distance| *distance > max)
+					.map(|distance| (distance, max))
+				);
+				let priority = extensions.priority_normalizer.normalize(&validity);
+				let admission = if is_submission {
+					self.check_admission(source, priority, &validity)
+				} else {
+					Ok(())
+				};
+
 				if validity.provides.is_empty() {
 					ValidatedTransaction::Invalid(hash.clone(), error::Error::NoTagsProvided.into())
+				} else if let Some((distance, max)) = distance {
+					// the transaction might still become valid later, so it's not banned
+					ValidatedTransaction::Unknown(
+						hash.clone(),
+						error::Error::TooDistantFuture { distance, max }.into(),
+					)
+				} else if !validity.propagate
+					&& source == TransactionSource::InBlock
+					&& !options.maintenance.resubmit_non_propagable
+				{
+					ValidatedTransaction::Unknown(
+						hash.clone(),
+						error::Error::NonPropagableResubmission.into(),
+					)
+				} else if let Err(e) = admission {
+					// it's a local policy, not a validity issue => not banned either
+					ValidatedTransaction::Unknown(hash.clone(), e.into())
 				} else {
+					let weight = extensions.weight_estimator.weight(bytes, &validity);
 					ValidatedTransaction::Valid(base::Transaction {
 						data: xt,
 						bytes,
 						hash: hash.clone(),
-						priority: validity.priority,
+						priority,
+						runtime_priority: validity.priority,
 						requires: validity.requires,
 						provides: validity.provides,
 						propagate: validity.propagate,
-						valid_till: block_number
-							.saturated_into::<u64>()
-							.saturating_add(validity.longevity),
+						valid_till: block_number.saturating_add(validity.longevity),
+						submitted_at: std::time::Instant::now(),
+						source,
+						weight,
+						validation_time,
+						type_index,
 					})
 				}
 			},
@@ -438,16 +2353,118 @@ impl<B: ChainApi> Pool<B> {
 		(hash, validity)
 	}
 
+	/// Returns runtime validity of a submitted transaction, reusing cached validity if enabled.
+	async fn validate_cached(
+		&self,
+		block_id: &BlockId<B::Block>,
+		hash: &ExHash<B>,
+		source: TransactionSource,
+		xt: &ExtrinsicFor<B>,
+	) -> Result<TransactionValidity, B::Error> {
+		let block = if self.validated_pool.options().admission.validation_cache {
+			self.validated_pool.api().block_id_to_hash(block_id).ok().and_then(|block| block)
+		} else {
+			None
+		};
+		let block = match block {
+			Some(block) => block,
+			None => return self.validate_with_retries(block_id, xt, None).await,
+		};
+
+		let cached = self.validation_cache.lock().get(&block, hash.clone(), source);
+		if let Some(validity) = cached {
+			return Ok(validity);
+		}
+
+		let result = self.validate_with_retries(block_id, xt, None).await;
+		if let Ok(ref validity) = result {
+			self.validation_cache.lock().insert(block, hash.clone(), source, validity.clone());
+		}
+		result
+	}
+
+	/// Calls runtime validation, retrying transient errors according to `AdmissionOptions::submit_retries`.
+	///
+	/// A panic during validation is reported as `Error::ValidationPanicked` and is never retried.
+	async fn validate_with_retries(
+		&self,
+		block_id: &BlockId<B::Block>,
+		xt: &ExtrinsicFor<B>,
+		context: Option<Vec<u8>>,
+	) -> Result<TransactionValidity, B::Error> {
+		let retries = self.validated_pool.options().admission.submit_retries;
+		let mut attempt = 0;
+		loop {
+			let api = self.validated_pool.api();
+			let validation = panic::catch_unwind(panic::AssertUnwindSafe(|| match context {
+				Some(ref context) => api.validate_transaction_with_context(block_id, xt.clone(), context.clone()),
+				None => api.validate_transaction(block_id, xt.clone()),
+			}));
+			let result = match validation {
+				Ok(validation) => panic::AssertUnwindSafe(validation).catch_unwind().await,
+				Err(panic) => Err(panic),
+			};
+			let result = match result {
+				Ok(result) => result,
+				Err(_) => {
+					log::error!(target: self.log_target(), "Validation panicked at {:?}", block_id);
+					return Err(error::Error::ValidationPanicked.into());
+				},
+			};
+			match result {
+				Err(e) if attempt < retries => {
+					attempt += 1;
+					log::debug!(target: self.log_target(),
+						"Validation failed at {:?} ({:?}), retrying ({}/{})", block_id, e, attempt, retries,
+					);
+					futures_timer::Delay::new(SUBMIT_RETRY_BACKOFF * attempt as u32).await;
+				},
+				result => return result,
+			}
+		}
+	}
+
 	/// Get ready transaction by hash, if it present in the pool.
 	pub fn ready_transaction(&self, hash: &ExHash<B>) -> Option<TransactionFor<B>> {
 		self.validated_pool.ready_by_hash(hash)
 	}
+
+	/// Returns SCALE-encoded transaction (ready or future), if it is present in the pool.
+	pub fn encoded_of(&self, hash: &ExHash<B>) -> Option<Vec<u8>> {
+		self.validated_pool.by_hash(hash).map(|tx| tx.data.encode())
+	}
+
+	/// Returns encoded length of transaction (ready or future), if it is present in the pool.
+	///
+	/// The length is computed on insertion, so the transaction is not encoded again.
+	pub fn encoded_size_of(&self, hash: &ExHash<B>) -> Option<usize> {
+		self.validated_pool.by_hash(hash).map(|tx| tx.bytes)
+	}
+
+	/// Returns true if transaction with given hash might have been seen recently.
+	///
+	/// See `ValidatedPool::maybe_seen` for details.
+	pub fn maybe_seen(&self, hash: &ExHash<B>) -> bool {
+		self.validated_pool.maybe_seen(hash)
+	}
+
+	/// Returns true if transaction with given hash is currently banned from the pool.
+	pub fn is_banned(&self, hash: &ExHash<B>) -> bool {
+		self.validated_pool.is_banned(hash)
+	}
 }
 
 impl<B: ChainApi> Clone for Pool<B> {
 	fn clone(&self) -> Self {
 		Self {
 			validated_pool: self.validated_pool.clone(),
+			idempotency_keys: self.idempotency_keys.clone(),
+			rejections: self.rejections.clone(),
+			validation_cache: self.validation_cache.clone(),
+			stale_ready: self.stale_ready.clone(),
+			accepting: self.accepting.clone(),
+			validation_times: self.validation_times.clone(),
+			time_gated: self.time_gated.clone(),
 		}
 	}
 }
@@ -476,6 +2493,11 @@ mod tests {
 		invalidate: Arc<Mutex<HashSet<u64>>>,
 		clear_requirements: Arc<Mutex<HashSet<u64>>>,
 		add_requirements: Arc<Mutex<HashSet<u64>>>,
+		fail_once: Arc<Mutex<HashSet<u64>>>,
+		fail_encoding: Arc<Mutex<HashSet<u64>>>,
+		panic_validation: Arc<Mutex<HashSet<u64>>>,
+		bump_priority: Arc<Mutex<HashSet<u64>>>,
+		contexts: Arc<Mutex<Vec<Vec<u8>>>>,
 	}
 
 	impl ChainApi for TestApi {
@@ -509,11 +2531,19 @@ mod tests {
 				return futures::future::ready(Ok(InvalidTransaction::Custom(0).into()));
 			}
 
+			if self.fail_once.lock().remove(&hash) {
+				return futures::future::ready(Err(error::Error::InvalidBlockId(format!("{:?}", at))));
+			}
+
+			if self.panic_validation.lock().contains(&hash) {
+				panic!("Validation of {} panicked", hash);
+			}
+
 			futures::future::ready(if nonce < block_number {
 				Ok(InvalidTransaction::Stale.into())
 			} else {
 				let mut transaction = ValidTransaction {
-					priority: 4,
+					priority: if self.bump_priority.lock().contains(&hash) { 5 } else { 4 },
 					requires: if nonce > block_number { vec![vec![nonce as u8 - 1]] } else { vec![] },
 					provides: if nonce == INVALID_NONCE { vec![] } else { vec![vec![nonce as u8]] },
 					longevity: 3,
@@ -532,6 +2562,16 @@ mod tests {
 			})
 		}
 
+		fn validate_transaction_with_context(
+			&self,
+			at: &BlockId<Self::Block>,
+			uxt: ExtrinsicFor<Self>,
+			context: Vec<u8>,
+		) -> Self::ValidationFuture {
+			self.contexts.lock().push(context);
+			self.validate_transaction(at, uxt)
+		}
+
 		/// Returns a block number given the block id.
 		fn block_id_to_number(&self, at: &BlockId<Self::Block>) -> Result<Option<NumberFor<Self>>, Self::Error> {
 			Ok(match at {
@@ -551,10 +2591,11 @@ mod tests {
 		/// Hash the extrinsic.
 		fn hash_and_length(&self, uxt: &ExtrinsicFor<Self>) -> (Self::Hash, usize) {
 			let len = uxt.encode().len();
-			(
-				(H256::from(uxt.transfer().from.clone()).to_low_u64_be() << 5) + uxt.transfer().nonce,
-				len
-			)
+			let hash = (H256::from(uxt.transfer().from.clone()).to_low_u64_be() << 5) + uxt.transfer().nonce;
+			if self.fail_encoding.lock().contains(&hash) {
+				panic!("Failed to encode transaction {}", hash);
+			}
+			(hash, len)
 		}
 
 		fn block_body(&self, _id: &BlockId<Self::Block>) -> Self::BodyFuture {
@@ -588,126 +2629,1512 @@ mod tests {
 	}
 
 	#[test]
-	fn should_reject_if_temporarily_banned() {
+	fn should_return_encoded_transactions() {
 		// given
 		let pool = pool();
-		let uxt = uxt(Transfer {
+		let ready = uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
 			amount: 5,
 			nonce: 0,
 		});
+		let future = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 5,
+		});
 
 		// when
-		pool.validated_pool.rotator().ban(&Instant::now(), vec![pool.hash_of(&uxt)]);
-		let res = block_on(pool.submit_one(&BlockId::Number(0), uxt));
-		assert_eq!(pool.status().ready, 0);
-		assert_eq!(pool.status().future, 0);
+		let ready_hash = block_on(pool.submit_one(&BlockId::Number(0), ready.clone())).unwrap();
+		let future_hash = block_on(pool.submit_one(&BlockId::Number(0), future.clone())).unwrap();
+		assert_eq!(pool.status().future, 1);
 
 		// then
-		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
+		assert_eq!(pool.encoded_of(&ready_hash), Some(ready.encode()));
+		assert_eq!(pool.encoded_of(&future_hash), Some(future.encode()));
+		assert_eq!(pool.encoded_of(&42), None);
+		assert_eq!(pool.encoded_size_of(&ready_hash), Some(ready.encode().len()));
+		assert_eq!(pool.encoded_size_of(&future_hash), Some(future.encode().len()));
+		assert_eq!(pool.encoded_size_of(&42), None);
 	}
 
 	#[test]
-	fn should_notify_about_pool_events() {
-		let stream = {
+	fn should_revalidate_highest_priority_transactions_first() {
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let revalidate = |order| {
 			// given
-			let pool = pool();
-			let stream = pool.import_notification_stream();
+			let api = TestApi::default();
+			let pool = Pool::new(Options {
+				maintenance: MaintenanceOptions { revalidation_order: order, ..Default::default() },
+				..Default::default()
+			}, api.clone().into());
+			api.bump_priority.lock().insert(pool.hash_of(&transfer(2)));
+			let hashes = (0..4)
+				.map(|nonce| block_on(pool.submit_one(&BlockId::Number(0), transfer(nonce))).unwrap())
+				.collect::<Vec<_>>();
+			assert_eq!(pool.status().ready, 4);
 
 			// when
-			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
-				from: AccountId::from_h256(H256::from_low_u64_be(1)),
-				to: AccountId::from_h256(H256::from_low_u64_be(2)),
-				amount: 5,
-				nonce: 0,
-			}))).unwrap();
-			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
-				from: AccountId::from_h256(H256::from_low_u64_be(1)),
-				to: AccountId::from_h256(H256::from_low_u64_be(2)),
-				amount: 5,
-				nonce: 1,
-			}))).unwrap();
-			// future doesn't count
-			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
-				from: AccountId::from_h256(H256::from_low_u64_be(1)),
-				to: AccountId::from_h256(H256::from_low_u64_be(2)),
-				amount: 5,
-				nonce: 3,
-			}))).unwrap();
+			api.invalidate.lock().extend(hashes.iter().cloned());
+			let outcome = block_on(pool.revalidate_ready(&BlockId::Number(0), Some(2))).unwrap();
 
-			assert_eq!(pool.status().ready, 2);
-			assert_eq!(pool.status().future, 1);
-			stream
+			// then
+			assert_eq!(outcome.checked, 2);
+			assert_eq!(pool.status().ready, 0);
+			// dependents of the revalidated transactions end up in the future queue
+			let future = pool.future_hashes();
+			(0..4).filter(|nonce| future.contains(&hashes[*nonce])).collect::<Vec<_>>()
 		};
 
-		// then
-		let mut it = futures::executor::block_on_stream(stream);
-		assert_eq!(it.next(), Some(32));
-		assert_eq!(it.next(), Some(33));
-		assert_eq!(it.next(), None);
+		assert_eq!(revalidate(RevalidationOrder::Oldest), vec![2, 3]);
+		assert_eq!(revalidate(RevalidationOrder::HighestPriority), vec![1, 3]);
 	}
 
 	#[test]
-	fn should_clear_stale_transactions() {
-		// given
-		let pool = pool();
-		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+	fn should_sum_value_committed_by_transactions() {
+		#[derive(Debug)]
+		struct Amount;
+		impl ValueExtractor<u64, Extrinsic> for Amount {
+			fn value_of(&self, tx: &dyn InPoolTransaction<Transaction=Extrinsic, Hash=u64>) -> u128 {
+				tx.data().transfer().amount as u128
+			}
+		}
+
+		let transfer = |nonce, amount| uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
-			amount: 5,
-			nonce: 0,
-		}))).unwrap();
+			amount,
+			nonce,
+		});
+		let submit_all = |pool: &Pool<TestApi>| {
+			block_on(pool.submit_one(&BlockId::Number(0), transfer(0, 5))).unwrap();
+			block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 7))).unwrap();
+			block_on(pool.submit_one(&BlockId::Number(0), transfer(3, 11))).unwrap();
+			assert_eq!(pool.status().future, 1);
+		};
+
+		// given
+		let default_pool = pool();
+		submit_all(&default_pool);
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			value_extractor: Arc::new(Amount),
+			..Default::default()
+		}, TestApi::default().into());
+		submit_all(&pool);
+
+		// then
+		assert_eq!(default_pool.committed_value_for(&[]), 0);
+		assert_eq!(pool.committed_value_for(&[]), 23);
+		assert_eq!(pool.committed_value_for(&[1]), 7);
+		assert_eq!(pool.committed_value_for(&[3]), 11);
+		assert_eq!(pool.committed_value_for(&[2]), 0);
+	}
+
+	#[test]
+	fn should_limit_number_of_returned_ready_transactions() {
+		// given
+		let pool = pool();
+		for nonce in 0..3 {
+			block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce,
+			}))).unwrap();
+		}
+		let all = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+
+		// then
+		assert_eq!(pool.ready_limited(2).map(|tx| tx.hash).collect::<Vec<_>>(), all[..2].to_vec());
+		assert_eq!(pool.ready_limited(5).map(|tx| tx.hash).collect::<Vec<_>>(), all);
+		assert_eq!(pool.ready_limited(0).count(), 0);
+	}
+
+	#[test]
+	fn should_return_ready_transactions_in_pages() {
+		// given
+		let pool = pool();
+		for nonce in 0..5 {
+			block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce,
+			}))).unwrap();
+		}
+		let all = pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		assert_eq!(all.len(), 5);
+
+		// when
+		let mut pages = vec![];
+		let mut cursor = None;
+		loop {
+			let (page, next) = pool.ready_page(cursor.as_ref(), 2);
+			pages.push(page.iter().map(|tx| tx.hash).collect::<Vec<_>>());
+			cursor = next;
+			if cursor.is_none() {
+				break;
+			}
+		}
+
+		// then
+		assert_eq!(pages, vec![all[0..2].to_vec(), all[2..4].to_vec(), all[4..].to_vec()]);
+		let (page, next) = pool.ready_page(Some(&all[3]), 1);
+		assert_eq!(page.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![all[4]]);
+		assert_eq!(next, None);
+		let (page, next) = pool.ready_page(Some(&42), 2);
+		assert!(page.is_empty());
+		assert_eq!(next, None);
+	}
+
+	#[test]
+	fn should_report_recently_seen_transactions() {
+		// given
+		let pool = pool();
+		let uxt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		assert!(!pool.maybe_seen(&42));
+
+		// when
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), uxt)).unwrap();
+		pool.validated_pool.ban(&Instant::now(), vec![42]);
+
+		// then
+		assert!(pool.maybe_seen(&hash));
+		assert!(pool.maybe_seen(&42));
+		assert!(!pool.maybe_seen(&43));
+	}
+
+	#[test]
+	fn should_reject_if_temporarily_banned() {
+		// given
+		let pool = pool();
+		let uxt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+
+		// when
+		pool.validated_pool.rotator().ban(&Instant::now(), vec![pool.hash_of(&uxt)]);
+		let res = block_on(pool.submit_one(&BlockId::Number(0), uxt));
+		assert_eq!(pool.status().ready, 0);
+		assert_eq!(pool.status().future, 0);
+
+		// then
+		assert_matches!(res.unwrap_err(), error::Error::TemporarilyBanned);
+	}
+
+	#[test]
+	fn should_notify_about_pool_events() {
+		let stream = {
+			// given
+			let pool = pool();
+			let stream = pool.import_notification_stream();
+
+			// when
+			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}))).unwrap();
+			// future doesn't count
+			let _hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 3,
+			}))).unwrap();
+
+			assert_eq!(pool.status().ready, 2);
+			assert_eq!(pool.status().future, 1);
+			stream
+		};
+
+		// then
+		let mut it = futures::executor::block_on_stream(stream);
+		assert_eq!(it.next(), Some(32));
+		assert_eq!(it.next(), Some(33));
+		assert_eq!(it.next(), None);
+	}
+
+	#[test]
+	fn should_drop_oldest_import_notifications_of_slow_subscriber() {
+		let stream = {
+			// given
+			let pool = pool();
+			let stream = pool.import_notification_stream_bounded(1);
+
+			// when
+			for nonce in 0..2 {
+				block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+					from: AccountId::from_h256(H256::from_low_u64_be(1)),
+					to: AccountId::from_h256(H256::from_low_u64_be(2)),
+					amount: 5,
+					nonce,
+				}))).unwrap();
+			}
+
+			stream
+		};
+
+		// then
+		assert!(stream.take_lagged());
+		let mut it = futures::executor::block_on_stream(stream);
+		assert_eq!(it.next(), Some(33));
+		assert_eq!(it.next(), None);
+	}
+
+	#[test]
+	fn should_clear_stale_transactions() {
+		// given
+		let pool = pool();
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
 		let hash2 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
 			amount: 5,
-			nonce: 1,
+			nonce: 1,
+		}))).unwrap();
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 3,
+		}))).unwrap();
+
+		// when
+		pool.validated_pool.clear_stale(&BlockId::Number(5)).unwrap();
+
+		// then
+		assert_eq!(pool.ready().count(), 0);
+		assert_eq!(pool.status().future, 0);
+		assert_eq!(pool.status().ready, 0);
+		// make sure they are temporarily banned as well
+		assert!(pool.validated_pool.rotator().is_banned(&hash1));
+		assert!(pool.validated_pool.rotator().is_banned(&hash2));
+		assert!(pool.validated_pool.rotator().is_banned(&hash3));
+	}
+
+	#[test]
+	fn should_ban_mined_transactions() {
+		// given
+		let pool = pool();
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+
+		// when
+		block_on(pool.prune_tags(&BlockId::Number(1), vec![vec![0]], vec![hash1.clone()])).unwrap();
+
+		// then
+		assert!(pool.validated_pool.rotator().is_banned(&hash1));
+	}
+
+	#[test]
+	fn should_quarantine_transactions_invalidated_during_revalidation() {
+		// given
+		let options = Options {
+			maintenance: MaintenanceOptions { quarantine_invalid: true, ..Default::default() },
+			..Default::default()
+		};
+		let pool = Pool::new(options, TestApi::default().into());
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+		assert!(pool.quarantined().is_empty());
+
+		// when
+		pool.validated_pool.api().invalidate.lock().insert(hash);
+		block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 0);
+		let quarantined = pool.quarantined();
+		assert_eq!(quarantined.len(), 1);
+		assert_eq!(quarantined[0].0.hash, hash);
+		assert!(!quarantined[0].1.is_empty());
+	}
+
+	#[test]
+	fn should_not_quarantine_by_default() {
+		// given
+		let pool = pool();
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+
+		// when
+		pool.validated_pool.api().invalidate.lock().insert(hash);
+		block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 0);
+		assert!(pool.quarantined().is_empty());
+	}
+
+	#[test]
+	fn should_remove_transactions_that_fail_to_encode_during_revalidation() {
+		// given
+		let pool = pool();
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+		let hash = *watcher.hash();
+		block_on(pool.submit_one(&BlockId::Number(1), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		}))).unwrap();
+		assert_eq!(pool.status().ready, 2);
+
+		// when
+		pool.validated_pool.api().fail_encoding.lock().insert(hash);
+		let outcome = block_on(pool.revalidate_ready(&BlockId::Number(1), None)).unwrap();
+
+		// then
+		assert_eq!(outcome, RevalidationOutcome { checked: 2, kept: 1, removed: 1 });
+		assert_eq!(pool.status().ready, 1);
+		assert!(pool.validated_pool.ready_by_hash(&hash).is_none());
+		assert_eq!(
+			futures::executor::block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+			vec![TransactionStatus::Ready, TransactionStatus::Invalid],
+		);
+	}
+
+	#[test]
+	fn should_report_transactions_that_fail_to_encode_on_submission() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		pool.validated_pool.api().fail_encoding.lock().insert(pool.hash_of(&transfer(1)));
+
+		// when
+		let results = block_on(pool.submit_at(&BlockId::Number(0), vec![transfer(0), transfer(1), transfer(2)], false))
+			.unwrap();
+		let single = block_on(pool.submit_one(&BlockId::Number(0), transfer(1)));
+
+		// then
+		assert_eq!(results.len(), 3);
+		assert_eq!(results[0].as_ref().ok(), Some(&pool.hash_of(&transfer(0))));
+		assert_matches!(results[1], Err(error::Error::EncodingFailed));
+		assert_eq!(results[2].as_ref().ok(), Some(&pool.hash_of(&transfer(2))));
+		assert_matches!(single, Err(error::Error::EncodingFailed));
+	}
+
+	#[test]
+	fn should_limit_ready_transactions_by_weight() {
+		#[derive(Debug)]
+		struct ConstantWeight;
+		impl WeightEstimator for ConstantWeight {
+			fn weight(&self, _len: usize, _tx: &ValidTransaction) -> Weight {
+				10
+			}
+		}
+
+		// given
+		let pool = Pool::with_extensions(Options {
+			limits: LimitOptions { max_weight: Some(25), ..Default::default() },
+			..Default::default()
+		}, PoolExtensions {
+			weight_estimator: Arc::new(ConstantWeight),
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(1), transfer(1))).unwrap();
+		assert_eq!(pool.status().ready, 2);
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(2), transfer(2))).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(pool.ready_within_weight(15).count(), 1);
+		assert_eq!(pool.ready_within_weight(20).count(), 2);
+	}
+
+	#[test]
+	fn should_report_weight_limit_of_dropped_transaction() {
+		#[derive(Debug)]
+		struct ConstantWeight;
+		impl WeightEstimator for ConstantWeight {
+			fn weight(&self, _len: usize, _tx: &ValidTransaction) -> Weight {
+				10
+			}
+		}
+
+		// given
+		let pool = Pool::with_extensions(Options {
+			limits: LimitOptions { max_weight: Some(15), ..Default::default() },
+			..Default::default()
+		}, PoolExtensions {
+			weight_estimator: Arc::new(ConstantWeight),
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+
+		// when
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::WeightLimitExceeded { current: 20, limit: 15 });
+		assert_eq!(pool.status().ready, 0);
+	}
+
+	#[test]
+	fn should_return_next_block_candidates_within_budget() {
+		#[derive(Debug)]
+		struct ConstantWeight;
+		impl WeightEstimator for ConstantWeight {
+			fn weight(&self, _len: usize, _tx: &ValidTransaction) -> Weight {
+				10
+			}
+		}
+
+		// given
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			weight_estimator: Arc::new(ConstantWeight),
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let bytes = pool.validated_pool.api().hash_and_length(&transfer(0)).1;
+		for nonce in 0..3 {
+			block_on(pool.submit_one(&BlockId::Number(0), transfer(nonce))).unwrap();
+		}
+		assert_eq!(pool.status().ready, 3);
+
+		// when
+		let nonces = |candidates: Vec<TransactionFor<TestApi>>| candidates
+			.into_iter()
+			.map(|tx| tx.data.transfer().nonce)
+			.collect::<Vec<_>>();
+
+		// then
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 3, 30)), vec![0, 1, 2]);
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 3, 25)), vec![0, 1]);
+		assert_eq!(nonces(pool.next_block_candidates(bytes, 30)), vec![0]);
+		assert!(pool.next_block_candidates(bytes - 1, 30).is_empty());
+	}
+
+	#[test]
+	fn should_return_bundles_as_a_whole_or_not_at_all() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let bytes = pool.validated_pool.api().hash_and_length(&transfer(0)).1;
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		let bundle = block_on(pool.submit_bundle(&BlockId::Number(0), vec![transfer(1), transfer(2)], [1; 32]))
+			.unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3))).unwrap();
+		assert_eq!(pool.status().ready, 4);
+
+		// when
+		let nonces = |candidates: Vec<TransactionFor<TestApi>>| candidates
+			.into_iter()
+			.map(|tx| tx.data.transfer().nonce)
+			.collect::<Vec<_>>();
+
+		// then
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 4, 0)), vec![0, 1, 2, 3]);
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 3, 0)), vec![0, 1, 2]);
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 2, 0)), vec![0]);
+
+		// when
+		pool.validated_pool.remove_invalid(&[bundle[1]]);
+
+		// then
+		assert_eq!(nonces(pool.next_block_candidates(bytes * 4, 0)), vec![0]);
+	}
+
+	#[test]
+	fn should_place_bundles_after_their_requirements() {
+		// given
+		let api = TestApi::default();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		// the transaction unlocking the bundle doesn't depend on its other member
+		api.clear_requirements.lock().insert(api.hash_and_length(&transfer(4)).0);
+		let pool = Pool::new(Default::default(), api.into());
+		block_on(pool.submit_bundle(&BlockId::Number(0), vec![transfer(0), transfer(5)], [1; 32])).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(4))).unwrap();
+		assert_eq!(pool.status().ready, 3);
+
+		// when
+		let candidates = pool.next_block_candidates(usize::max_value(), 0)
+			.into_iter()
+			.map(|tx| tx.data.transfer().nonce)
+			.collect::<Vec<_>>();
+
+		// then
+		assert_eq!(candidates, vec![4, 0, 5]);
+	}
+
+	#[test]
+	fn should_reject_bundles_with_invalid_transactions() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		pool.validated_pool.api().invalidate.lock().insert(pool.hash_of(&transfer(1)));
+
+		// when
+		let err = block_on(pool.submit_bundle(&BlockId::Number(0), vec![transfer(0), transfer(1)], [1; 32]))
+			.unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::InvalidTransaction(_));
+		assert_eq!(pool.status().ready, 0);
+	}
+
+	#[test]
+	fn should_reject_too_distant_future_transactions() {
+		#[derive(Debug)]
+		struct NonceGap;
+		impl FutureDistanceEstimator for NonceGap {
+			fn distance(&self, block_number: u64, tx: &ValidTransaction) -> Option<u64> {
+				tx.requires.first().map(|tag| tag[0] as u64 + 1 - block_number)
+			}
+		}
+
+		// given
+		let pool = Pool::with_extensions(Options {
+			admission: AdmissionOptions { max_future_distance: Some(2), ..Default::default() },
+			..Default::default()
+		}, PoolExtensions {
+			future_distance: Arc::new(NonceGap),
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(2))).unwrap();
+		let distant = pool.hash_of(&transfer(3));
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(3))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::TooDistantFuture { distance: 3, max: 2 });
+		assert_eq!(pool.status().future, 1);
+		assert!(!pool.validated_pool.rotator().is_banned(&distant));
+	}
+
+	#[test]
+	fn should_reject_future_transactions_forming_too_deep_chain() {
+		// given
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { max_future_chain_depth: Some(2), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(2))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3))).unwrap();
+		assert_eq!(pool.status().future, 2);
+
+		// when
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(4))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::ChainTooDeep { depth: 3, max: 2 });
+		assert_eq!(pool.status().future, 2);
+	}
+
+	#[test]
+	fn should_spill_evicted_future_transactions_and_reload_them() {
+		// given
+		let path = std::env::temp_dir().join(format!("txpool-future-spill-{}", std::process::id()));
+		let pool = Pool::new(Options {
+			future: base::Limit { count: 1, total_bytes: 1_000_000 },
+			limits: LimitOptions { future_spill_path: Some(path.clone()), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(2))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3))).unwrap();
+
+		// then
+		assert_eq!(pool.status().future, 1);
+		assert_eq!(pool.spilled_count(), 1);
+		let mut stream = Box::pin(watcher.into_stream());
+		assert_eq!(stream.next().now_or_never(), Some(Some(TransactionStatus::Future)));
+		assert_eq!(stream.next().now_or_never(), Some(Some(TransactionStatus::Spilled)));
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 4);
+		assert_eq!(pool.status().future, 0);
+		assert_eq!(pool.spilled_count(), 0);
+		assert_eq!(stream.next().now_or_never(), Some(Some(TransactionStatus::Ready)));
+		std::fs::remove_file(path).ok();
+	}
+
+	#[test]
+	fn should_enforce_limits_of_transaction_types() {
+		let transfer = |from, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(from)),
+			to: AccountId::from_h256(H256::from_low_u64_be(5)),
+			amount: 5,
+			nonce,
+		});
+		// variant index followed by the sender
+		let type_of = |from| transfer(from, 0).encode()[..33].to_vec();
+		let pool = Pool::new(Options {
+			limits: LimitOptions { type_limits: vec![(type_of(1), 1), (type_of(2), 2)], ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 1))).unwrap_err();
+		assert_matches!(err, error::Error::TypeLimitExceeded { current: 1, limit: 1, .. });
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(2, 2))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(2, 3))).unwrap();
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(2, 4))).unwrap_err();
+		assert_matches!(err, error::Error::TypeLimitExceeded { current: 2, limit: 2, .. });
+		// transactions of other types are not limited
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3, 5))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(3, 6))).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready + pool.status().future, 5);
+	}
+
+	#[test]
+	fn should_reject_transactions_below_min_priority() {
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let pool_with_min_priority = |min_priority| Pool::new(Options {
+			admission: AdmissionOptions { min_priority: Some(min_priority), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+
+		// priority of all test transactions is 4
+		let pool = pool_with_min_priority(4);
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+
+		let pool = pool_with_min_priority(5);
+		let rejected = pool.hash_of(&transfer(0));
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap_err();
+		assert_matches!(err, error::Error::PriorityTooLow { priority: 4, min: 5 });
+		assert_eq!(pool.status().ready, 0);
+		assert!(!pool.validated_pool.rotator().is_banned(&rejected));
+
+		block_on(pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(0))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+	}
+
+	#[test]
+	fn should_reject_transactions_below_min_longevity() {
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let pool_with_min_longevity = |min_longevity| Pool::new(Options {
+			admission: AdmissionOptions { min_longevity: Some(min_longevity), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+
+		// longevity of all test transactions is 3
+		let pool = pool_with_min_longevity(3);
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+
+		let pool = pool_with_min_longevity(4);
+		let rejected = pool.hash_of(&transfer(0));
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap_err();
+		assert_matches!(err, error::Error::LongevityTooShort { longevity: 3, min: 4 });
+		assert_eq!(pool.status().ready, 0);
+		assert!(!pool.validated_pool.rotator().is_banned(&rejected));
+		assert_eq!(pool.rejection_stats().get(&RejectionReason::LongevityTooShort), Some(&1));
+
+		block_on(pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(0))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+	}
+
+	#[test]
+	fn should_apply_submission_policies_only_on_submission() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { min_priority: Some(5), ..Default::default() },
+			..Default::default()
+		}, api.clone().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		let hash = pool.hash_of(&xt);
+		api.bump_priority.lock().insert(hash);
+		block_on(pool.submit_one(&BlockId::Number(0), xt)).unwrap();
+
+		// when
+		api.bump_priority.lock().clear();
+		let outcome = block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+
+		// then
+		assert_eq!(outcome, RevalidationOutcome { checked: 1, kept: 1, removed: 0 });
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+	}
+
+	#[test]
+	fn should_apply_source_policies_when_computing_validity() {
+		// given
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { min_priority: Some(5), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+
+		// when
+		let local = block_on(pool.compute_validity(&BlockId::Number(0), TransactionSource::Local, xt.clone()));
+		let external = block_on(pool.compute_validity(&BlockId::Number(0), TransactionSource::External, xt));
+
+		// then
+		assert_matches!(local, Ok(Ok(ValidTransaction { priority: 4, .. })));
+		assert_matches!(external, Err(error::Error::PriorityTooLow { priority: 4, min: 5 }));
+		assert_eq!(pool.status().ready, 0);
+	}
+
+	#[test]
+	fn should_count_rejections_per_reason() {
+		// given
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { min_priority: Some(5), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap_err();
+		block_on(pool.submit_at(&BlockId::Number(0), vec![transfer(0), transfer(1)], false)).unwrap();
+		block_on(pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(0))).unwrap();
+		block_on(pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(0))).unwrap_err();
+		// forced re-imports are not accounted
+		block_on(pool.submit_at(&BlockId::Number(0), vec![transfer(0), transfer(1)], true)).unwrap();
+
+		// then
+		let stats = pool.rejection_stats();
+		assert_eq!(stats.len(), 2);
+		assert_eq!(stats.get(&RejectionReason::PriorityTooLow), Some(&3));
+		assert_eq!(stats.get(&RejectionReason::AlreadyImported), Some(&1));
+	}
+
+	#[test]
+	fn should_reuse_validity_of_transactions_submitted_at_the_same_block() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { validation_cache: true, ..Default::default() },
+			..Default::default()
+		}, api.clone().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		});
+		let resubmit = |source| block_on(pool.submit_at_with_source(&BlockId::Number(1), source, vec![xt.clone()], true))
+			.unwrap()
+			.pop()
+			.unwrap();
+		let hash = resubmit(TransactionSource::InBlock).unwrap();
+		pool.remove_invalid(&[hash]);
+		api.invalidate.lock().insert(hash);
+
+		// when
+		let other_source = resubmit(TransactionSource::Local);
+		let same_source = resubmit(TransactionSource::InBlock);
+		let revalidated = block_on(pool.revalidate_ready(&BlockId::Number(1), None)).unwrap();
+
+		// then
+		assert_matches!(other_source, Err(error::Error::InvalidTransaction(_)));
+		assert_eq!(same_source.unwrap(), hash);
+		// revalidation doesn't use the cache
+		assert_eq!(revalidated, RevalidationOutcome { checked: 1, kept: 0, removed: 1 });
+		assert_eq!(pool.status().ready, 0);
+	}
+
+	#[test]
+	fn should_retry_transient_validation_errors() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { submit_retries: 1, ..Default::default() },
+			..Default::default()
+		}, api.clone().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		api.fail_once.lock().insert(pool.hash_of(&xt));
+
+		// when
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), xt)).unwrap();
+
+		// then
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+	}
+
+	#[test]
+	fn should_not_retry_without_configured_retries() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Default::default(), api.clone().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		api.fail_once.lock().insert(pool.hash_of(&xt));
+
+		// when
+		let result = block_on(pool.submit_one(&BlockId::Number(0), xt));
+
+		// then
+		assert!(result.is_err());
+		assert_eq!(pool.status().ready, 0);
+	}
+
+	#[test]
+	fn should_reject_transactions_which_validation_panics() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { submit_retries: 1, ..Default::default() },
+			..Default::default()
+		}, api.clone().into());
+		let xt = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		api.panic_validation.lock().insert(pool.hash_of(&xt));
+
+		// when
+		let result = block_on(pool.submit_one(&BlockId::Number(0), xt));
+
+		// then
+		assert_matches!(result, Err(error::Error::ValidationPanicked));
+		assert_eq!(pool.status().ready, 0);
+		assert_eq!(pool.status().future, 0);
+	}
+
+	#[test]
+	fn should_count_promoted_and_expired_future_transactions() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(2))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(5))).unwrap();
+		assert_eq!(pool.status().future, 3);
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+
+		// then
+		assert_eq!(pool.future_promotion_stats(), FuturePromotionStats { promoted: 2, expired: 0 });
+
+		// when
+		pool.validated_pool.clear_stale(&BlockId::Number(5)).unwrap();
+
+		// then
+		assert_eq!(pool.future_promotion_stats(), FuturePromotionStats { promoted: 2, expired: 1 });
+	}
+
+	#[test]
+	fn should_notify_about_permanently_rejected_transactions() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Default::default(), api.clone().into());
+		let stream = pool.deadletter_stream();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let invalid = pool.hash_of(&transfer(1));
+		api.invalidate.lock().insert(invalid);
+
+		// when
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		assert!(block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).is_err());
+		// banned, but it's not a permanent rejection
+		assert!(block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).is_err());
+		pool.remove_invalid(&[hash]);
+		drop(pool);
+
+		// then
+		let events = futures::executor::block_on_stream(stream).collect::<Vec<_>>();
+		assert_eq!(events, vec![
+			(invalid, RejectionReason::InvalidValidity),
+			(hash, RejectionReason::InvalidValidity),
+		]);
+	}
+
+	#[test]
+	fn should_record_validation_time() {
+		// given
+		let pool = pool();
+		let started = Instant::now();
+
+		// when
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}))).unwrap();
+
+		// then
+		let tx = pool.ready_transaction(&hash).unwrap();
+		assert!(tx.validation_time <= started.elapsed());
+		let histogram = pool.validation_time_histogram();
+		assert_eq!(histogram.count, 1);
+		assert_eq!(histogram.sum, tx.validation_time);
+		assert_eq!(histogram.buckets.len(), VALIDATION_TIME_BUCKETS.len());
+	}
+
+	#[test]
+	fn should_report_replaced_transaction() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Default::default(), api.clone().into());
+		let transfer = |account| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		});
+		let first = block_on(pool.submit_one_detailed(&BlockId::Number(0), transfer(1))).unwrap();
+		assert_eq!(first.replaced, None);
+
+		// when
+		api.bump_priority.lock().insert(pool.hash_of(&transfer(2)));
+		let second = block_on(pool.submit_one_detailed(&BlockId::Number(0), transfer(2))).unwrap();
+
+		// then
+		assert_eq!(second.replaced, Some(first.hash));
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![second.hash]);
+	}
+
+	#[test]
+	fn should_reject_submissions_while_not_accepting() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+
+		// when
+		pool.set_accepting(false);
+
+		// then
+		assert!(!pool.is_accepting());
+		assert_matches!(
+			block_on(pool.submit_one(&BlockId::Number(0), transfer(1))),
+			Err(error::Error::NotAccepting)
+		);
+		assert_matches!(
+			block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(1))),
+			Err(error::Error::NotAccepting)
+		);
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+
+		// transactions already in the pool are still maintained
+		let outcome = block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+		assert_eq!(outcome, RevalidationOutcome { checked: 1, kept: 1, removed: 0 });
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+
+		// when
+		pool.set_accepting(true);
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 2);
+	}
+
+	#[test]
+	fn should_reserve_ready_capacity_for_local_transactions() {
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 4,
+				total_bytes: 100_000,
+			},
+			limits: LimitOptions {
+				source_quotas: base::SourceQuotas {
+					local: 0.5,
+					external: 0.5,
+				},
+				..Default::default()
+			},
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |account, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(1), transfer(2, 1))).unwrap();
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(2), transfer(3, 2))).unwrap();
+		let local = block_on(pool.submit_one_with_source(
+			&BlockId::Number(3),
+			TransactionSource::Local,
+			transfer(4, 3),
+		)).unwrap();
+
+		// then
+		// external transactions are limited to their share of the pool
+		assert!(pool.validated_pool.rotator().is_banned(&hash1));
+		let ready = pool.ready().map(|tx| tx.hash).collect::<HashSet<_>>();
+		assert_eq!(ready, vec![hash2, hash3, local].into_iter().collect());
+	}
+
+	#[test]
+	fn should_not_evict_pinned_transactions() {
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 2,
+				total_bytes: 100_000,
+			},
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |account, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(1), transfer(2, 1))).unwrap();
+		assert!(pool.pin(&hash1));
+		assert!(!pool.pin(&0));
+
+		// when
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(2), transfer(3, 2))).unwrap();
+
+		// then
+		assert!(pool.validated_pool.rotator().is_banned(&hash2));
+		let ready = pool.ready().map(|tx| tx.hash).collect::<HashSet<_>>();
+		assert_eq!(ready, vec![hash1, hash3].into_iter().collect());
+		assert!(pool.unpin(&hash1));
+		assert!(!pool.unpin(&hash1));
+	}
+
+	#[test]
+	fn should_replace_transaction_or_roll_back() {
+		// given
+		let api = TestApi::default();
+		let pool = Pool::new(Options {
+			reject_future_transactions: true,
+			..Default::default()
+		}, api.clone().into());
+		let transfer = |account, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let old = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let dependent = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 1))).unwrap();
+		let ready = || pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		assert_eq!(ready(), vec![old, dependent]);
+
+		// when
+		let invalid = transfer(2, 0);
+		api.invalidate.lock().insert(pool.hash_of(&invalid));
+		let err = block_on(pool.replace(&BlockId::Number(0), TransactionSource::External, old, invalid)).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::InvalidTransaction(_));
+		assert_eq!(ready(), vec![old, dependent]);
+
+		// when
+		let err = block_on(pool.replace(&BlockId::Number(0), TransactionSource::External, old, transfer(3, 5))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::RejectedFutureTransaction);
+		assert_eq!(ready(), vec![old, dependent]);
+		assert_eq!(pool.status().future, 0);
+
+		// when
+		let new = block_on(pool.replace(&BlockId::Number(0), TransactionSource::Local, old, transfer(4, 0))).unwrap();
+
+		// then
+		assert_eq!(ready(), vec![new, dependent]);
+		assert_eq!(pool.ready_transaction(&new).unwrap().source, TransactionSource::Local);
+	}
+
+	#[test]
+	fn should_not_replace_if_limits_would_be_exceeded() {
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 2,
+				total_bytes: 100_000,
+			},
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |account, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(0), transfer(2, 0))).unwrap();
+
+		// when
+		let err = block_on(pool.replace(&BlockId::Number(0), TransactionSource::External, 999, transfer(3, 0)))
+			.unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::ImmediatelyDropped { current: 2, limit: 2, .. });
+		let ready = pool.ready().map(|tx| tx.hash).collect::<HashSet<_>>();
+		assert_eq!(ready, vec![hash1, hash2].into_iter().collect());
+	}
+
+	#[test]
+	fn should_upgrade_external_transactions_resubmitted_locally() {
+		use futures::StreamExt;
+
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 2,
+				total_bytes: 100_000,
+			},
+			admission: AdmissionOptions { upgrade_local_resubmissions: true, ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |account, nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(account)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(1, 0))).unwrap();
+		let hash1 = *watcher.hash();
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(1), transfer(2, 1))).unwrap();
+
+		// when
+		let upgraded = block_on(
+			pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(1, 0))
+		).unwrap();
+
+		// then
+		assert_eq!(upgraded, hash1);
+		assert_eq!(pool.count_by_source(), SourceCounts { local: 1, external: 1, in_block: 0 });
+		let mut stream = Box::pin(watcher.into_stream());
+		assert_eq!(stream.next().now_or_never(), Some(Some(TransactionStatus::Ready)));
+		assert_eq!(stream.next().now_or_never(), None);
+		let err = block_on(
+			pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(1, 0))
+		).unwrap_err();
+		assert_matches!(err, error::Error::AlreadyImported(_));
+
+		// when
+		let hash3 = block_on(pool.submit_one(&BlockId::Number(2), transfer(3, 2))).unwrap();
+
+		// then
+		assert!(pool.validated_pool.rotator().is_banned(&hash2));
+		let ready = pool.ready().map(|tx| tx.hash).collect::<HashSet<_>>();
+		assert_eq!(ready, vec![hash1, hash3].into_iter().collect());
+	}
+
+	#[test]
+	fn should_batch_evictions_during_cooldown() {
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 4,
+				total_bytes: 100_000,
+			},
+			limits: LimitOptions { eviction_cooldown: std::time::Duration::from_secs(60 * 60), ..Default::default() },
+			..Default::default()
+		}, TestApi::default().into());
+		let submit = |nonce| block_on(pool.submit_one(&BlockId::Number(nonce), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(nonce)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		})));
+		for nonce in 0..5 {
+			let _ = submit(nonce);
+		}
+		assert_eq!(pool.status().ready, 4);
+
+		// when
+		let _ = submit(5);
+
+		// then
+		assert_eq!(pool.status().ready, 5);
+
+		// when
+		let _ = submit(6);
+
+		// then
+		// the hard limit is enforced during the cool-down
+		assert_eq!(pool.status().ready, 5);
+		assert!(pool.validated_pool.rotator().is_banned(&((1 << 5) + 1)));
+	}
+
+	#[test]
+	fn should_not_resubmit_with_the_same_idempotency_key() {
+		// given
+		let pool = pool();
+		let key = [1u8; 32];
+		let hash1 = block_on(pool.submit_one_idempotent(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
+		}), key)).unwrap();
+
+		// when
+		let hash2 = block_on(pool.submit_one_idempotent(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 6,
+			nonce: 0,
+		}), key)).unwrap();
+		let hash3 = block_on(pool.submit_one_idempotent(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(2)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		}), [2u8; 32])).unwrap();
+
+		// then
+		assert_eq!(hash1, hash2);
+		assert_ne!(hash1, hash3);
+		assert_eq!(pool.status().ready + pool.status().future, 2);
+	}
+
+	#[test]
+	fn should_release_idempotency_keys_of_failed_and_expired_submissions() {
+		// given
+		let api = TestApi::default();
+		api.invalidate.lock().insert(32);
+		let pool = Pool::new(Options {
+			admission: AdmissionOptions { idempotency_key_ttl: Duration::from_millis(10), ..Default::default() },
+			..Default::default()
+		}, api.into());
+		let key = [1u8; 32];
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		block_on(pool.submit_one_idempotent(&BlockId::Number(0), transfer(0), key)).unwrap_err();
+		let hash1 = block_on(pool.submit_one_idempotent(&BlockId::Number(0), transfer(1), key)).unwrap();
+		std::thread::sleep(Duration::from_millis(20));
+		let hash2 = block_on(pool.submit_one_idempotent(&BlockId::Number(0), transfer(2), key)).unwrap();
+
+		// then
+		assert_eq!(hash1, 33);
+		assert_eq!(hash2, 34);
+		assert_eq!(pool.status().future, 2);
+	}
+
+	#[test]
+	fn should_return_oldest_pending_transactions() {
+		// given
+		let pool = pool();
+		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(2)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 0,
 		}))).unwrap();
-		let hash3 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+		let hash2 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
 			amount: 5,
 			nonce: 3,
 		}))).unwrap();
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(pool.status().future, 1);
 
 		// when
-		pool.validated_pool.clear_stale(&BlockId::Number(5)).unwrap();
+		let oldest = pool.oldest_pending(10);
+		let limited = pool.oldest_pending(1);
 
 		// then
-		assert_eq!(pool.ready().count(), 0);
-		assert_eq!(pool.status().future, 0);
-		assert_eq!(pool.status().ready, 0);
-		// make sure they are temporarily banned as well
-		assert!(pool.validated_pool.rotator().is_banned(&hash1));
-		assert!(pool.validated_pool.rotator().is_banned(&hash2));
-		assert!(pool.validated_pool.rotator().is_banned(&hash3));
+		assert_eq!(oldest.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash1, hash2]);
+		assert_eq!(limited.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash1]);
 	}
 
 	#[test]
-	fn should_ban_mined_transactions() {
+	fn should_account_memory_overhead() {
 		// given
 		let pool = pool();
-		let hash1 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+		assert_eq!(pool.memory_usage(), 0);
+		let xt = uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
 			amount: 5,
 			nonce: 0,
-		}))).unwrap();
+		});
+		let bytes = xt.encode().len();
 
 		// when
-		block_on(pool.prune_tags(&BlockId::Number(1), vec![vec![0]], vec![hash1.clone()])).unwrap();
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), xt)).unwrap();
 
 		// then
-		assert!(pool.validated_pool.rotator().is_banned(&hash1));
+		let status = pool.status();
+		assert!(status.ready_bytes > bytes);
+		assert!(pool.memory_usage() > status.ready_bytes);
+		drop(watcher);
 	}
 
 	#[test]
 	fn should_limit_futures() {
 		// given
+		let single_transaction_memory = {
+			let pool = pool();
+			block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}))).unwrap();
+			pool.status().future_bytes
+		};
 		let limit = Limit {
 			count: 100,
-			total_bytes: 200,
+			total_bytes: single_transaction_memory * 3 / 2,
 		};
 		let pool = Pool::new(Options {
 			ready: limit.clone(),
@@ -751,7 +4178,7 @@ mod tests {
 		}, TestApi::default().into());
 
 		// when
-		block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+		let err = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
 			from: AccountId::from_h256(H256::from_low_u64_be(1)),
 			to: AccountId::from_h256(H256::from_low_u64_be(2)),
 			amount: 5,
@@ -759,6 +4186,16 @@ mod tests {
 		}))).unwrap_err();
 
 		// then
+		assert_matches!(
+			err,
+			error::Error::ImmediatelyDropped {
+				exceeded: error::ExceededLimit::Future,
+				current: 1,
+				limit: 100,
+				current_bytes,
+				limit_bytes: 10,
+			} if current_bytes > 10
+		);
 		assert_eq!(pool.status().ready, 0);
 		assert_eq!(pool.status().future, 0);
 	}
@@ -778,8 +4215,412 @@ mod tests {
 
 		// then
 		assert_eq!(pool.status().ready, 0);
-		assert_eq!(pool.status().future, 0);
-		assert_matches!(err, error::Error::NoTagsProvided);
+		assert_eq!(pool.status().future, 0);
+		assert_matches!(err, error::Error::NoTagsProvided);
+	}
+
+	#[test]
+	fn should_order_ready_transactions_by_normalized_priority() {
+		#[derive(Debug)]
+		struct ByTag;
+		impl PriorityNormalizer for ByTag {
+			fn normalize(&self, tx: &ValidTransaction) -> u64 {
+				tx.provides[0][0] as u64
+			}
+		}
+
+		fn submit(pool: &Pool<TestApi>) -> Vec<u64> {
+			let hash1 = block_on(pool.submit_one(&BlockId::Number(1), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}))).unwrap();
+			let hash2 = block_on(pool.submit_one(&BlockId::Number(2), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(2)),
+				to: AccountId::from_h256(H256::from_low_u64_be(1)),
+				amount: 5,
+				nonce: 2,
+			}))).unwrap();
+			assert_eq!((hash1, hash2), (33, 66));
+			pool.ready().map(|tx| tx.hash).collect()
+		}
+
+		// given
+		let default_pool = pool();
+		let normalized_pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			priority_normalizer: Arc::new(ByTag),
+			..Default::default()
+		}, TestApi::default().into());
+
+		// when
+		let default_order = submit(&default_pool);
+		let normalized_order = submit(&normalized_pool);
+
+		// then
+		// with equal priorities the transaction with shorter longevity goes first
+		assert_eq!(default_order, vec![33, 66]);
+		assert_eq!(normalized_order, vec![66, 33]);
+	}
+
+	#[test]
+	fn should_reorder_ready_transactions_after_normalizer_change() {
+		#[derive(Debug, Default)]
+		struct Oracle(std::sync::atomic::AtomicBool);
+		impl PriorityNormalizer for Oracle {
+			fn normalize(&self, tx: &ValidTransaction) -> u64 {
+				let tag = u64::from(tx.provides[0][0]);
+				if self.0.load(std::sync::atomic::Ordering::SeqCst) {
+					100 - tag
+				} else {
+					tag
+				}
+			}
+		}
+
+		// given
+		let oracle = Arc::new(Oracle::default());
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			priority_normalizer: oracle.clone(),
+			..Default::default()
+		}, TestApi::default().into());
+		block_on(pool.submit_one(&BlockId::Number(1), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		}))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(2), uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(2)),
+			to: AccountId::from_h256(H256::from_low_u64_be(1)),
+			amount: 5,
+			nonce: 2,
+		}))).unwrap();
+		assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![66, 33]);
+
+		// when
+		oracle.0.store(true, std::sync::atomic::Ordering::SeqCst);
+		pool.reorder_ready();
+
+		// then
+		assert_eq!(pool.ready().map(|tx| (tx.hash, tx.priority)).collect::<Vec<_>>(), vec![(33, 99), (66, 98)]);
+		assert_eq!(pool.ready().map(|tx| tx.runtime_priority).collect::<Vec<_>>(), vec![4, 4]);
+	}
+
+	#[test]
+	fn should_pass_validation_context_to_the_api() {
+		#[derive(Debug)]
+		struct LocalOnly;
+		impl ValidationContextProvider for LocalOnly {
+			fn context(&self, source: TransactionSource, encoded: &[u8]) -> Option<Vec<u8>> {
+				if source == TransactionSource::Local {
+					Some(vec![encoded.len() as u8])
+				} else {
+					None
+				}
+			}
+		}
+
+		// given
+		let api = TestApi::default();
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			context_provider: Some(Arc::new(LocalOnly)),
+			..Default::default()
+		}, api.clone().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let encoded_len = transfer(0).encode().len();
+
+		// when
+		block_on(pool.submit_one_with_source(&BlockId::Number(0), TransactionSource::Local, transfer(0))).unwrap();
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap();
+
+		// then
+		assert_eq!(pool.status().ready, 2);
+		assert_eq!(*api.contexts.lock(), vec![vec![encoded_len as u8]]);
+
+		// when
+		let validity = block_on(pool.compute_validity(&BlockId::Number(0), TransactionSource::Local, transfer(2)));
+
+		// then
+		assert_matches!(validity, Ok(Ok(_)));
+		assert_eq!(*api.contexts.lock(), vec![vec![encoded_len as u8]; 2]);
+		assert_eq!(pool.status().ready, 2);
+	}
+
+	#[test]
+	fn should_reject_filtered_transactions_before_validation() {
+		#[derive(Debug)]
+		struct DenyList(Vec<Vec<u8>>);
+		impl PreValidationFilter for DenyList {
+			fn allow(&self, encoded: &[u8]) -> bool {
+				!self.0.iter().any(|denied| denied[..] == *encoded)
+			}
+		}
+
+		// given
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			pre_validation_filter: Some(Arc::new(DenyList(vec![transfer(1).encode()]))),
+			..Default::default()
+		}, TestApi::default().into());
+
+		// when
+		block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+		let err = block_on(pool.submit_one(&BlockId::Number(0), transfer(1))).unwrap_err();
+
+		// then
+		assert_matches!(err, error::Error::Filtered);
+		assert_eq!(pool.status().ready, 1);
+		assert!(!pool.validated_pool.rotator().is_banned(&pool.hash_of(&transfer(1))));
+		assert_eq!(pool.rejection_stats().get(&RejectionReason::Filtered), Some(&1));
+	}
+
+	#[derive(Debug)]
+	struct FromBlockTwo(Vec<Vec<u8>>);
+	impl ValidFromHint for FromBlockTwo {
+		fn valid_from(&self, encoded: &[u8]) -> Option<u64> {
+			if self.0.iter().any(|gated| gated[..] == *encoded) { Some(2) } else { None }
+		}
+	}
+
+	#[test]
+	fn should_validate_dedupe_and_bound_time_gated_transactions() {
+		// given
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let api = TestApi::default();
+		api.invalidate.lock().insert(36);
+		let pool = Pool::with_extensions(Options {
+			future: Limit { count: 2, total_bytes: 1_000_000 },
+			..Default::default()
+		}, PoolExtensions {
+			valid_from_hint: Some(Arc::new(FromBlockTwo((2..6).map(|nonce| transfer(nonce).encode()).collect()))),
+			..Default::default()
+		}, api.into());
+
+		// when
+		let results = block_on(pool.submit_at(&BlockId::Number(0), vec![
+			transfer(2), transfer(0), transfer(2), transfer(4), transfer(3), transfer(5),
+		], false)).unwrap();
+
+		// then
+		assert_matches!(results[0], Ok(34));
+		assert_matches!(results[1], Ok(32));
+		assert_matches!(results[2], Err(error::Error::AlreadyImported(_)));
+		assert_matches!(results[3], Err(error::Error::InvalidTransaction(_)));
+		assert_matches!(results[4], Ok(35));
+		assert_matches!(results[5], Err(error::Error::ImmediatelyDropped { current: 2, limit: 2, .. }));
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(pool.time_gated_count(), 2);
+		assert!(pool.validated_pool.rotator().is_banned(&36));
+
+		// when
+		let results = block_on(pool.submit_time_gated(&BlockId::Number(2)));
+
+		// then
+		assert_matches!(results[..], [Ok(34), Ok(35)]);
+		assert_eq!(pool.time_gated_count(), 0);
+	}
+
+	#[test]
+	fn should_watch_time_gated_transactions() {
+		// given
+		let transfer = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 2,
+		});
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			valid_from_hint: Some(Arc::new(FromBlockTwo(vec![transfer.encode()]))),
+			..Default::default()
+		}, TestApi::default().into());
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer)).unwrap();
+		assert_eq!(pool.status().future, 0);
+		assert_eq!(pool.time_gated_count(), 1);
+
+		// when
+		let results = block_on(pool.submit_time_gated(&BlockId::Number(2)));
+
+		// then
+		assert_matches!(results[..], [Ok(34)]);
+		assert_eq!(pool.status().ready, 1);
+		let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+		assert_eq!(stream.next(), Some(TransactionStatus::Future));
+		assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	}
+
+	#[test]
+	fn should_keep_aside_time_gated_transactions_on_every_submission_path() {
+		// given
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+		let api = TestApi::default();
+		api.invalidate.lock().insert(36);
+		let pool = Pool::with_extensions(Default::default(), PoolExtensions {
+			valid_from_hint: Some(Arc::new(FromBlockTwo(vec![transfer(2).encode(), transfer(3).encode()]))),
+			..Default::default()
+		}, api.into());
+
+		// when
+		let result = block_on(pool.submit_one_detailed(&BlockId::Number(0), transfer(2))).unwrap();
+
+		// then
+		assert_matches!(result, SubmitResult { hash: 34, replaced: None });
+		assert_eq!(pool.status().ready, 0);
+		assert_eq!(pool.status().future, 0);
+		assert_eq!(pool.time_gated_count(), 1);
+
+		// when
+		let result = block_on(pool.submit_batch_atomic(&BlockId::Number(0), vec![transfer(3), transfer(4)])).unwrap();
+
+		// then
+		assert_matches!(result, Err((1, error::Error::InvalidTransaction(_))));
+		assert_eq!(pool.time_gated_count(), 1);
+
+		// when
+		let results = block_on(pool.submit_time_gated(&BlockId::Number(2)));
+
+		// then
+		assert_matches!(results[..], [Ok(34)]);
+		assert_eq!(pool.time_gated_count(), 0);
+	}
+
+	#[test]
+	fn should_submit_atomic_batch() {
+		// given
+		let pool = pool();
+
+		// when
+		let hashes = block_on(pool.submit_batch_atomic(&BlockId::Number(0), vec![
+			uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}),
+			uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}),
+		])).unwrap().unwrap();
+
+		// then
+		assert_eq!(hashes, vec![32, 33]);
+		assert_eq!(pool.status().ready, 2);
+	}
+
+	#[test]
+	fn should_stop_at_first_failing_transaction() {
+		// given
+		let pool = pool();
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let results = block_on(pool.submit_at_stop_on_error(&BlockId::Number(1), vec![
+			transfer(1),
+			// stale
+			transfer(0),
+			transfer(2),
+		])).unwrap();
+
+		// then
+		assert_eq!(results.len(), 2);
+		assert_eq!(results[0].as_ref().unwrap(), &33);
+		assert_matches!(results[1], Err(error::Error::InvalidTransaction(_)));
+		assert_eq!(pool.status().ready, 1);
+		assert_eq!(pool.status().future, 0);
+	}
+
+	#[test]
+	fn should_not_evict_members_of_atomic_batch() {
+		// given
+		let pool = Pool::new(Options {
+			ready: Limit {
+				count: 2,
+				total_bytes: 100_000,
+			},
+			..Default::default()
+		}, TestApi::default().into());
+		let transfer = |nonce| uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce,
+		});
+
+		// when
+		let hashes = block_on(pool.submit_batch_atomic(
+			&BlockId::Number(0),
+			vec![transfer(0), transfer(1), transfer(2)],
+		)).unwrap().unwrap();
+
+		// then
+		assert_eq!(hashes, vec![32, 33, 34]);
+		assert_eq!(pool.status().ready, 3);
+	}
+
+	#[test]
+	fn should_not_import_anything_if_atomic_batch_fails() {
+		// given
+		let pool = pool();
+		let future = uxt(Transfer {
+			from: AccountId::from_h256(H256::from_low_u64_be(1)),
+			to: AccountId::from_h256(H256::from_low_u64_be(2)),
+			amount: 5,
+			nonce: 1,
+		});
+		let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), future.clone())).unwrap();
+		assert_eq!(pool.status().future, 1);
+
+		// when
+		let (index, err) = block_on(pool.submit_batch_atomic(&BlockId::Number(0), vec![
+			// promotes the future transaction
+			uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}),
+			// already in the pool
+			future,
+		])).unwrap().unwrap_err();
+
+		// then
+		assert_eq!(index, 1);
+		assert_matches!(err, error::Error::AlreadyImported(_));
+		assert_eq!(pool.status().ready, 0);
+		assert_eq!(pool.status().future, 1);
+		drop(pool);
+		assert_eq!(
+			futures::executor::block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+			vec![TransactionStatus::Future],
+		);
 	}
 
 	mod listener {
@@ -835,6 +4676,75 @@ mod tests {
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_trigger_usurped_when_conflicting_transaction_is_included() {
+			// given
+			let pool = pool();
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			// provides the same tag, but is not in the pool
+			let included = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(2)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 6,
+				nonce: 0,
+			});
+
+			// when
+			block_on(pool.prune(&BlockId::Number(1), &BlockId::Number(0), &[included])).unwrap();
+
+			// then
+			assert_eq!(pool.status().ready, 0);
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+			assert_eq!(stream.next(), Some(TransactionStatus::Usurped(64)));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_close_watcher_after_too_many_events() {
+			// given
+			let api = TestApi::default();
+			let pool = Pool::new(Options {
+				watch: WatchOptions { max_watch_events: Some(5), ..Default::default() },
+				..Default::default()
+			}, api.clone().into());
+			let xt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let hash = pool.hash_of(&xt);
+			// requires a tag that is never provided
+			api.add_requirements.lock().insert(hash);
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), xt)).unwrap();
+			assert_eq!(pool.status().future, 1);
+
+			// when
+			for _ in 0..3 {
+				pool.force_promote(&hash).unwrap();
+				assert_eq!(pool.status().ready, 1);
+				block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+				assert_eq!(pool.status().future, 1);
+			}
+
+			// then
+			let stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.collect::<Vec<_>>(), vec![
+				TransactionStatus::Future,
+				TransactionStatus::Ready,
+				TransactionStatus::Future,
+				TransactionStatus::Ready,
+				TransactionStatus::Invalid,
+			]);
+			assert_eq!(pool.watchers_count(), 0);
+		}
+
 		#[test]
 		fn should_trigger_future_and_ready_after_promoted() {
 			// given
@@ -863,6 +4773,29 @@ mod tests {
 			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
 		}
 
+		#[test]
+		fn should_trigger_placement_first_when_pruned_in_the_same_block() {
+			// given
+			let pool = pool();
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}))).unwrap();
+			assert_eq!(pool.status().future, 1);
+
+			// when
+			let hash = *watcher.hash();
+			pool.prune_known(&BlockId::Number(2), &[hash]).unwrap();
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Future));
+			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), None);
+		}
+
 		#[test]
 		fn should_trigger_invalid_and_ban() {
 			// given
@@ -887,6 +4820,281 @@ mod tests {
 			assert_eq!(stream.next(), None);
 		}
 
+		#[test]
+		fn should_demote_dependents_of_invalid_transactions() {
+			// given
+			let pool = pool();
+			let transfer = |nonce| uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce,
+			});
+			let hash0 = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+			let watcher1 = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(1))).unwrap();
+			let watcher2 = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(2))).unwrap();
+			assert_eq!(pool.status().ready, 3);
+
+			// when
+			let removed = pool.validated_pool.remove_invalid(&[hash0]);
+
+			// then
+			assert_eq!(removed.iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash0]);
+			assert_eq!(pool.status().ready, 0);
+			assert_eq!(pool.status().future, 2);
+			for watcher in vec![watcher1, watcher2] {
+				let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+				assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+				assert_eq!(stream.next(), Some(TransactionStatus::Future));
+			}
+		}
+
+		#[test]
+		fn should_drop_dependents_of_invalid_transactions_if_configured() {
+			// given
+			let pool = Pool::new(Options {
+				maintenance: MaintenanceOptions { drop_invalid_dependents: true, ..Default::default() },
+				..Default::default()
+			}, TestApi::default().into());
+			let transfer = |nonce| uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce,
+			});
+			let hash0 = block_on(pool.submit_one(&BlockId::Number(0), transfer(0))).unwrap();
+			let watcher1 = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(1))).unwrap();
+			let watcher2 = block_on(pool.submit_and_watch(&BlockId::Number(0), transfer(2))).unwrap();
+			assert_eq!(pool.status().ready, 3);
+
+			// when
+			let removed = pool.validated_pool.remove_invalid(&[hash0]);
+
+			// then
+			assert_eq!(removed.len(), 3);
+			assert_eq!(pool.status().ready, 0);
+			assert_eq!(pool.status().future, 0);
+			for watcher in vec![watcher1, watcher2] {
+				assert_eq!(
+					futures::executor::block_on_stream(watcher.into_stream()).collect::<Vec<_>>(),
+					vec![TransactionStatus::Ready, TransactionStatus::Invalid],
+				);
+			}
+		}
+
+		#[test]
+		fn should_trigger_queued_position_updates() {
+			// given
+			let pool = pool();
+			let hash0 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			let watcher = block_on(pool.submit_and_watch_with_position(&BlockId::Number(1), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 1,
+			}))).unwrap();
+			assert_eq!(pool.status().ready, 2);
+
+			// when
+			pool.validated_pool.remove_invalid(&[hash0]);
+			pool.notify_positions();
+			pool.validated_pool.remove_invalid(&[*watcher.hash()]);
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+			assert_eq!(stream.next(), Some(TransactionStatus::Queued { position: 2, total: 2 }));
+			assert_eq!(stream.next(), Some(TransactionStatus::Queued { position: 1, total: 1 }));
+			assert_eq!(stream.next(), Some(TransactionStatus::Invalid));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_only_trigger_terminal_status_if_requested() {
+			// given
+			let pool = pool();
+			let watcher = block_on(pool.submit_and_watch_terminal_only(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			assert_eq!(pool.status().ready, 1);
+
+			// when
+			pool.validated_pool.on_broadcasted(vec![(*watcher.hash(), vec!["a".into()])].into_iter().collect());
+			block_on(pool.prune_tags(&BlockId::Number(2), vec![vec![0u8]], vec![])).unwrap();
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::InBlock(H256::from_low_u64_be(2).into())));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_not_trigger_queued_without_opt_in() {
+			// given
+			let pool = pool();
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+
+			// when
+			pool.notify_positions();
+			pool.validated_pool.remove_invalid(&[*watcher.hash()]);
+
+			// then
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+			assert_eq!(stream.next(), Some(TransactionStatus::Invalid));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_trigger_invalid_once_for_duplicated_hashes() {
+			// given
+			let pool = pool();
+			let uxt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt)).unwrap();
+			let hash = *watcher.hash();
+
+			// when
+			let removed = pool.validated_pool.remove_invalid(&[hash, hash]);
+
+			// then
+			assert_eq!(removed.len(), 1);
+			assert_eq!(removed[0].hash, hash);
+			let mut stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+			assert_eq!(stream.next(), Some(TransactionStatus::Invalid));
+			assert_eq!(stream.next(), None);
+		}
+
+		#[test]
+		fn should_stream_status_events_of_all_transactions() {
+			// given
+			let pool = pool();
+			let stream = pool.all_status_stream();
+			let hash0 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			}))).unwrap();
+			let hash2 = block_on(pool.submit_one(&BlockId::Number(0), uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 2,
+			}))).unwrap();
+
+			// when
+			let mut map = HashMap::new();
+			map.insert(hash0, vec!["a".into()]);
+			pool.on_broadcasted(map);
+			pool.validated_pool.remove_invalid(&[hash0, hash2]);
+			drop(pool);
+
+			// then
+			let events = futures::executor::block_on_stream(stream).collect::<Vec<_>>();
+			assert_eq!(events, vec![
+				(hash0, TransactionStatus::Ready),
+				(hash2, TransactionStatus::Future),
+				(hash0, TransactionStatus::Broadcast(vec!["a".into()])),
+				(hash0, TransactionStatus::Invalid),
+				(hash2, TransactionStatus::Invalid),
+			]);
+		}
+
+		#[test]
+		fn should_limit_watchers_per_source() {
+			// given
+			let pool = Pool::new(Options {
+				watch: WatchOptions { max_watchers_per_source: Some(2), ..Default::default() },
+				..Default::default()
+			}, TestApi::default().into());
+			let submit = |source_id, nonce| block_on(pool.submit_and_watch_from(
+				&BlockId::Number(nonce),
+				TransactionSource::External,
+				source_id,
+				uxt(Transfer {
+					from: AccountId::from_h256(H256::from_low_u64_be(1)),
+					to: AccountId::from_h256(H256::from_low_u64_be(2)),
+					amount: 5,
+					nonce,
+				}),
+			));
+
+			// when
+			let watcher0 = submit("a", 0).unwrap();
+			let _watcher1 = submit("a", 1).unwrap();
+			let err = submit("a", 2).unwrap_err();
+
+			// then
+			assert_matches!(err, error::Error::TooManyWatchersForSource { current: 2, limit: 2 });
+			assert_eq!(pool.status().ready, 2);
+			assert!(submit("b", 2).is_ok());
+
+			// when
+			drop(watcher0.into_stream());
+
+			// then
+			assert!(submit("a", 3).is_ok());
+		}
+
+		#[test]
+		fn should_reclaim_slots_of_dropped_watchers() {
+			// given
+			let pool = Pool::new(Options {
+				watch: WatchOptions { max_watchers_per_source: Some(1), ..Default::default() },
+				..Default::default()
+			}, TestApi::default().into());
+			let submit = |nonce| block_on(pool.submit_and_watch_from(
+				&BlockId::Number(nonce),
+				TransactionSource::External,
+				"a",
+				uxt(Transfer {
+					from: AccountId::from_h256(H256::from_low_u64_be(1)),
+					to: AccountId::from_h256(H256::from_low_u64_be(2)),
+					amount: 5,
+					nonce,
+				}),
+			));
+			let watcher = submit(0).unwrap();
+			assert_eq!(pool.watchers_count(), 1);
+
+			// when
+			drop(watcher.into_stream());
+			let _watcher = submit(1).unwrap();
+
+			// then
+			assert_eq!(pool.status().ready, 2);
+			// closed subscribers are only forgotten in `clear_stale`
+			assert_eq!(pool.watchers_count(), 2);
+			pool.validated_pool.clear_stale(&BlockId::Number(0)).unwrap();
+			assert_eq!(pool.watchers_count(), 1);
+
+			// when
+			drop(_watcher);
+			pool.validated_pool.clear_stale(&BlockId::Number(0)).unwrap();
+
+			// then
+			assert_eq!(pool.watchers_count(), 0);
+		}
+
 		#[test]
 		fn should_trigger_broadcasted() {
 			// given
@@ -913,6 +5121,40 @@ mod tests {
 			assert_eq!(stream.next(), Some(TransactionStatus::Broadcast(peers)));
 		}
 
+		#[test]
+		fn should_not_repeat_broadcast_to_the_same_peers() {
+			// given
+			let pool = pool();
+			let uxt = uxt(Transfer {
+				from: AccountId::from_h256(H256::from_low_u64_be(1)),
+				to: AccountId::from_h256(H256::from_low_u64_be(2)),
+				amount: 5,
+				nonce: 0,
+			});
+			let watcher = block_on(pool.submit_and_watch(&BlockId::Number(0), uxt)).unwrap();
+			let hash = *watcher.hash();
+			let broadcast = |peers: Vec<String>| {
+				let mut map = HashMap::new();
+				map.insert(hash, peers);
+				pool.on_broadcasted(map);
+			};
+
+			// when
+			broadcast(vec!["a".into(), "b".into()]);
+			broadcast(vec!["a".into(), "b".into()]);
+			broadcast(vec!["b".into(), "c".into()]);
+			pool.validated_pool.remove_invalid(&[hash]);
+
+			// then
+			let stream = futures::executor::block_on_stream(watcher.into_stream());
+			assert_eq!(stream.collect::<Vec<_>>(), vec![
+				TransactionStatus::Ready,
+				TransactionStatus::Broadcast(vec!["a".into(), "b".into()]),
+				TransactionStatus::Broadcast(vec!["c".into()]),
+				TransactionStatus::Invalid,
+			]);
+		}
+
 		#[test]
 		fn should_trigger_dropped() {
 			// given
@@ -1039,7 +5281,7 @@ mod tests {
 		pool.validated_pool.api().invalidate.lock().insert(hash3);
 		pool.validated_pool.api().clear_requirements.lock().insert(hash1);
 		pool.validated_pool.api().add_requirements.lock().insert(hash0);
-		block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
+		let outcome = block_on(pool.revalidate_ready(&BlockId::Number(0), None)).unwrap();
 
 		// then
 		// hash0 now has unsatisfied requirements => it is moved to the future queue
@@ -1050,6 +5292,7 @@ mod tests {
 		//
 		// events for hash3 are: Ready, Invalid
 		// events for hash4 are: Ready, Invalid
+		assert_eq!(outcome, RevalidationOutcome { checked: 5, kept: 4, removed: 1 });
 		assert_eq!(pool.status().ready, 2);
 		assert_eq!(
 			futures::executor::block_on_stream(watcher3.into_stream()).collect::<Vec<_>>(),
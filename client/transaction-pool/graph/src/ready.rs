@@ -15,9 +15,9 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::{HashMap, HashSet, BTreeSet},
+	collections::{HashMap, HashSet, BTreeMap, BTreeSet},
 	cmp,
-	hash,
+	hash::{self, Hasher},
 	sync::Arc,
 };
 
@@ -27,11 +27,12 @@ use parking_lot::RwLock;
 use sp_runtime::traits::Member;
 use sp_runtime::transaction_validity::{
 	TransactionTag as Tag,
+	TransactionPriority as Priority,
 };
-use sp_transaction_pool::error;
+use sp_transaction_pool::{error, SourceCounts, TransactionSource, TransactionWeight as Weight};
 
 use crate::future::WaitingTransaction;
-use crate::base_pool::Transaction;
+use crate::base_pool::{SenderIndex, Transaction, TypeCounts};
 
 /// An in-pool transaction reference.
 ///
@@ -74,6 +75,60 @@ impl<Hash, Ex> PartialEq for TransactionRef<Hash, Ex> {
 }
 impl<Hash, Ex> Eq for TransactionRef<Hash, Ex> {}
 
+impl<Hash, Ex> AsRef<TransactionRef<Hash, Ex>> for TransactionRef<Hash, Ex> {
+	fn as_ref(&self) -> &TransactionRef<Hash, Ex> {
+		self
+	}
+}
+
+/// An in-pool transaction reference ordered by priority per encoded byte.
+///
+/// Transactions with the same density are ordered the same way as `TransactionRef`.
+#[derive(Debug)]
+struct FeeDensityRef<Hash, Ex>(TransactionRef<Hash, Ex>);
+
+impl<Hash, Ex> Clone for FeeDensityRef<Hash, Ex> {
+	fn clone(&self) -> Self {
+		FeeDensityRef(self.0.clone())
+	}
+}
+
+impl<Hash, Ex> From<TransactionRef<Hash, Ex>> for FeeDensityRef<Hash, Ex> {
+	fn from(tx_ref: TransactionRef<Hash, Ex>) -> Self {
+		FeeDensityRef(tx_ref)
+	}
+}
+
+impl<Hash, Ex> AsRef<TransactionRef<Hash, Ex>> for FeeDensityRef<Hash, Ex> {
+	fn as_ref(&self) -> &TransactionRef<Hash, Ex> {
+		&self.0
+	}
+}
+
+impl<Hash, Ex> Ord for FeeDensityRef<Hash, Ex> {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		// compares `priority / bytes` without losing precision
+		let scaled = |tx: &TransactionRef<Hash, Ex>, by: &TransactionRef<Hash, Ex>| {
+			u128::from(tx.transaction.priority) * by.transaction.bytes.max(1) as u128
+		};
+		scaled(&self.0, &other.0).cmp(&scaled(&other.0, &self.0))
+			.then_with(|| self.0.cmp(&other.0))
+	}
+}
+
+impl<Hash, Ex> PartialOrd for FeeDensityRef<Hash, Ex> {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<Hash, Ex> PartialEq for FeeDensityRef<Hash, Ex> {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == cmp::Ordering::Equal
+	}
+}
+impl<Hash, Ex> Eq for FeeDensityRef<Hash, Ex> {}
+
 #[derive(Debug)]
 pub struct ReadyTx<Hash, Ex> {
 	/// A reference to a transaction
@@ -114,6 +169,20 @@ pub struct ReadyTransactions<Hash: hash::Hash + Eq, Ex> {
 	ready: Arc<RwLock<HashMap<Hash, ReadyTx<Hash, Ex>>>>,
 	/// Best transactions that are ready to be included to the block without any other previous transaction.
 	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	/// Number of transactions per source.
+	counts: SourceCounts,
+	/// Number of transactions per sender.
+	senders: SenderIndex,
+	/// Number of transactions per type.
+	types: TypeCounts,
+	/// Approximate memory occupied by all transactions.
+	bytes: usize,
+	/// Total weight of all transactions.
+	weight: Weight,
+	/// Log target.
+	log_target: &'static str,
+	/// Whether a transaction replaces the ones providing the same tags with equal priority.
+	replace_equal_priority: bool,
 }
 
 impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
@@ -123,11 +192,34 @@ impl<Hash: hash::Hash + Eq, Ex> Default for ReadyTransactions<Hash, Ex> {
 			provided_tags: Default::default(),
 			ready: Default::default(),
 			best: Default::default(),
+			counts: Default::default(),
+			senders: Default::default(),
+			types: Default::default(),
+			bytes: 0,
+			weight: 0,
+			log_target: crate::DEFAULT_LOG_TARGET,
+			replace_equal_priority: false,
 		}
 	}
 }
 
 impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
+	/// Create new empty queue logging to given target.
+	pub fn with_log_target(log_target: &'static str) -> Self {
+		ReadyTransactions {
+			log_target,
+			..Default::default()
+		}
+	}
+
+	/// Sets whether a transaction providing the same tags as already ready transactions
+	/// with equal (collective) priority replaces them.
+	///
+	/// By default the already ready transactions are kept and the new one is rejected.
+	pub fn set_replace_equal_priority(&mut self, replace_equal_priority: bool) {
+		self.replace_equal_priority = replace_equal_priority;
+	}
+
 	/// Borrows a map of tags that are provided by transactions in this queue.
 	pub fn provided_tags(&self) -> &HashMap<Tag, Hash> {
 		&self.provided_tags
@@ -145,11 +237,76 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	/// 4. Lastly we sort by the time in the queue
 	/// - transactions that are longer in the queue go first
 	pub fn get(&self) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
+		self.get_excluding(Default::default())
+	}
+
+	/// Returns an iterator of ready transactions skipping given ones.
+	///
+	/// Transactions that depend on skipped ones are not returned either. The set is shared
+	/// with the iterator, so it's not copied.
+	pub fn get_excluding(&self, excluded: Arc<HashSet<Hash>>) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
 		BestIterator {
 			all: self.ready.clone(),
 			best: self.best.clone(),
 			awaiting: Default::default(),
+			excluded,
+		}
+	}
+
+	/// Returns an iterator of ready transactions ordered by priority per encoded byte.
+	///
+	/// Same as `get_excluding`, but transactions with all requirements satisfied are
+	/// ordered by fee density instead of absolute priority.
+	pub fn get_by_fee_density(&self, excluded: Arc<HashSet<Hash>>) -> impl Iterator<Item=Arc<Transaction<Hash, Ex>>> {
+		BestIterator {
+			all: self.ready.clone(),
+			best: self.best.iter().cloned().map(FeeDensityRef).collect::<BTreeSet<_>>(),
+			awaiting: Default::default(),
+			excluded,
+		}
+	}
+
+	/// Returns ready transactions shuffled deterministically by given seed.
+	///
+	/// Transactions are still returned after the ones they depend on, but otherwise
+	/// the order only depends on the seed and the transaction hashes. Transactions
+	/// depending on the skipped ones are not returned either.
+	pub fn get_shuffled(&self, excluded: Arc<HashSet<Hash>>, seed: [u8; 32]) -> Vec<Arc<Transaction<Hash, Ex>>> {
+		let included = self.get_excluding(excluded).collect::<Vec<_>>();
+		let ready = self.ready.read();
+
+		// number of requirements satisfied so far, see `BestIterator`
+		let mut awaiting = HashMap::new();
+		let mut available = BTreeMap::new();
+		for tx in included {
+			let ready_tx = ready.get(&tx.hash).expect(HASH_READY);
+			let satisfied = ready_tx.requires_offset;
+			if satisfied >= tx.requires.len() {
+				available.insert((shuffle_key(&seed, &tx.hash), ready_tx.transaction.insertion_id), tx);
+			} else {
+				awaiting.insert(tx.hash.clone(), (satisfied, ready_tx.transaction.insertion_id, tx));
+			}
 		}
+
+		let mut shuffled = Vec::with_capacity(available.len() + awaiting.len());
+		while let Some(key) = available.keys().next().cloned() {
+			let tx = available.remove(&key).expect("the key has just been taken from the map; qed");
+			for hash in &ready.get(&tx.hash).expect(HASH_READY).unlocks {
+				let is_satisfied = match awaiting.get_mut(hash) {
+					Some((satisfied, _, tx)) => {
+						*satisfied += 1;
+						*satisfied >= tx.requires.len()
+					},
+					None => false,
+				};
+				if is_satisfied {
+					let (_, insertion_id, tx) = awaiting.remove(hash).expect("the hash is awaiting; qed");
+					available.insert((shuffle_key(&seed, &tx.hash), insertion_id), tx);
+				}
+			}
+			shuffled.push(tx);
+		}
+		shuffled
 	}
 
 	/// Imports transactions to the pool of ready transactions.
@@ -208,6 +365,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		}
 
 		// insert to Ready
+		self.counts.add(transaction.transaction.source);
+		self.senders.add(&transaction.transaction);
+		self.types.add(&transaction.transaction);
+		self.bytes += transaction.transaction.memory_footprint();
+		self.weight = self.weight.saturating_add(transaction.transaction.weight);
 		ready.insert(hash, ReadyTx {
 			transaction,
 			unlocks,
@@ -272,6 +434,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			if let Some(mut tx) = ready.remove(&hash) {
+				self.counts.remove(tx.transaction.transaction.source);
+				self.senders.remove(&tx.transaction.transaction);
+				self.types.remove(&tx.transaction.transaction);
+				self.bytes -= tx.transaction.transaction.memory_footprint();
+				self.weight = self.weight.saturating_sub(tx.transaction.transaction.weight);
 				let invalidated = tx.transaction.transaction.provides
 					.iter()
 					.filter(|tag| provides_tag_filter
@@ -305,7 +472,7 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 				}
 
 				// add to removed
-				trace!(target: "txpool", "[{:?}] Removed as part of the subtree.", hash);
+				trace!(target: self.log_target, "[{:?}] Removed as part of the subtree.", hash);
 				removed.push(tx.transaction.transaction);
 			}
 		}
@@ -330,6 +497,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 					.and_then(|hash| self.ready.write().remove(&hash));
 
 			if let Some(tx) = res {
+				self.counts.remove(tx.transaction.transaction.source);
+				self.senders.remove(&tx.transaction.transaction);
+				self.types.remove(&tx.transaction.transaction);
+				self.bytes -= tx.transaction.transaction.memory_footprint();
+				self.weight = self.weight.saturating_sub(tx.transaction.transaction.weight);
 				let unlocks = tx.unlocks;
 				let tx = tx.transaction.transaction;
 
@@ -433,7 +605,12 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 			};
 
 			// bail - the transaction has too low priority to replace the old ones
-			if old_priority >= tx.priority {
+			let too_low = if self.replace_equal_priority {
+				old_priority > tx.priority
+			} else {
+				old_priority >= tx.priority
+			};
+			if too_low {
 				return Err(error::Error::TooLowPriority { old: old_priority, new: tx.priority })
 			}
 
@@ -469,26 +646,119 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 		self.ready.read().len()
 	}
 
-	/// Returns sum of encoding lengths of all transactions in this queue.
+	/// Returns number of transactions in this queue per source.
+	pub fn count_by_source(&self) -> SourceCounts {
+		self.counts
+	}
+
+	/// Returns number of transactions of given type in this queue.
+	pub fn type_count(&self, index: usize) -> usize {
+		self.types.get(index)
+	}
+
+	/// Returns number of transactions per sender in this queue.
+	pub(crate) fn senders(&self) -> &SenderIndex {
+		&self.senders
+	}
+
+	/// Returns approximate memory occupied by all transactions in this queue.
 	pub fn bytes(&self) -> usize {
-		self.ready.read().values().fold(0, |acc, tx| acc + tx.transaction.transaction.bytes)
+		self.bytes
+	}
+
+	/// Returns total weight of all transactions in this queue.
+	pub fn weight(&self) -> Weight {
+		self.weight
+	}
+}
+
+impl<Hash: hash::Hash + Member + Serialize, Ex: Clone> ReadyTransactions<Hash, Ex> {
+	/// Updates priorities of all transactions in the queue.
+	///
+	/// `priority` returns new priority of given transaction, the order of the queue
+	/// reflects the new priorities afterwards.
+	pub fn update_priorities(&mut self, mut priority: impl FnMut(&Transaction<Hash, Ex>) -> Priority) {
+		let best = std::mem::take(&mut self.best)
+			.into_iter()
+			.map(|tx| tx.transaction.hash.clone())
+			.collect::<HashSet<_>>();
+		let mut ready = self.ready.write();
+		for (hash, tx) in ready.iter_mut() {
+			let new_priority = priority(&tx.transaction.transaction);
+			if new_priority != tx.transaction.transaction.priority {
+				let mut updated = tx.transaction.transaction.duplicate();
+				updated.priority = new_priority;
+				tx.transaction.transaction = Arc::new(updated);
+			}
+			if best.contains(hash) {
+				self.best.insert(tx.transaction.clone());
+			}
+		}
+	}
+
+	/// Changes source of given transaction.
+	///
+	/// Returns `false` if the transaction is not in the queue or already has given source.
+	pub fn set_source(&mut self, hash: &Hash, source: TransactionSource) -> bool {
+		let mut ready = self.ready.write();
+		let tx = match ready.get_mut(hash) {
+			Some(tx) if tx.transaction.transaction.source != source => tx,
+			_ => return false,
+		};
+		let mut updated = tx.transaction.transaction.duplicate();
+		updated.source = source;
+		self.counts.remove(tx.transaction.transaction.source);
+		self.counts.add(source);
+		let is_best = self.best.remove(&tx.transaction);
+		tx.transaction.transaction = Arc::new(updated);
+		if is_best {
+			self.best.insert(tx.transaction.clone());
+		}
+		true
+	}
+}
+
+/// Returns the key ordering transactions shuffled with given seed.
+fn shuffle_key<Hash: hash::Hash>(seed: &[u8; 32], hash: &Hash) -> [u8; 32] {
+	let mut hasher = BytesHasher(seed.to_vec());
+	hash.hash(&mut hasher);
+	sp_core::blake2_256(&hasher.0)
+}
+
+/// Collects all the bytes that are hashed, so that they can be hashed by a
+/// hasher that's not randomized.
+struct BytesHasher(Vec<u8>);
+
+impl Hasher for BytesHasher {
+	fn finish(&self) -> u64 {
+		0
+	}
+
+	fn write(&mut self, bytes: &[u8]) {
+		self.0.extend_from_slice(bytes)
 	}
 }
 
 /// Iterator of ready transactions ordered by priority.
-pub struct BestIterator<Hash, Ex> {
+///
+/// Transactions with all requirements satisfied are ordered by `R`.
+pub struct BestIterator<Hash, Ex, R = TransactionRef<Hash, Ex>> {
 	all: Arc<RwLock<HashMap<Hash, ReadyTx<Hash, Ex>>>>,
 	awaiting: HashMap<Hash, (usize, TransactionRef<Hash, Ex>)>,
-	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	best: BTreeSet<R>,
+	excluded: Arc<HashSet<Hash>>,
 }
 
-impl<Hash: hash::Hash + Member, Ex> BestIterator<Hash, Ex> {
+impl<Hash, Ex, R> BestIterator<Hash, Ex, R> where
+	Hash: hash::Hash + Member,
+	R: Ord + From<TransactionRef<Hash, Ex>>,
+{
 	/// Depending on number of satisfied requirements insert given ref
 	/// either to awaiting set or to best set.
 	fn best_or_awaiting(&mut self, satisfied: usize, tx_ref: TransactionRef<Hash, Ex>) {
 		if satisfied >= tx_ref.transaction.requires.len() {
 			// If we have satisfied all deps insert to best
-			self.best.insert(tx_ref);
+			self.best.insert(tx_ref.into());
 
 		} else {
 			// otherwise we're still awaiting for some deps
@@ -497,13 +767,16 @@ impl<Hash: hash::Hash + Member, Ex> BestIterator<Hash, Ex> {
 	}
 }
 
-impl<Hash: hash::Hash + Member, Ex> Iterator for BestIterator<Hash, Ex> {
+impl<Hash, Ex, R> Iterator for BestIterator<Hash, Ex, R> where
+	Hash: hash::Hash + Member,
+	R: Ord + Clone + From<TransactionRef<Hash, Ex>> + AsRef<TransactionRef<Hash, Ex>>,
+{
 	type Item = Arc<Transaction<Hash, Ex>>;
 
 	fn next(&mut self) -> Option<Self::Item> {
 		loop {
 			let best = self.best.iter().next_back()?.clone();
-			let best = self.best.take(&best)?;
+			let best = self.best.take(&best)?.as_ref().clone();
 
 			let next = self.all.read().get(&best.transaction.hash).cloned();
 			let ready = match next {
@@ -512,6 +785,11 @@ impl<Hash: hash::Hash + Member, Ex> Iterator for BestIterator<Hash, Ex> {
 				None => continue,
 			};
 
+			// Skip the transaction together with everything it unlocks.
+			if self.excluded.contains(&best.transaction.hash) {
+				continue;
+			}
+
 			// Insert transactions that just got unlocked.
 			for hash in &ready.unlocks {
 				// first check local awaiting transactions
@@ -548,14 +826,12 @@ mod tests {
 
 	fn tx(id: u8) -> Transaction<u64, Vec<u8>> {
 		Transaction {
-			data: vec![id],
-			bytes: 1,
-			hash: id as u64,
 			priority: 1,
+			runtime_priority: 1,
 			valid_till: 2,
 			requires: vec![vec![1], vec![2]],
 			provides: vec![vec![3], vec![4]],
-			propagate: true,
+			..crate::base_pool::tx(id as u64)
 		}
 	}
 
@@ -632,6 +908,32 @@ mod tests {
 		assert_eq!(ready.get().count(), 3);
 	}
 
+	#[test]
+	fn should_keep_track_of_bytes_and_weight() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.weight = 5;
+		let mut tx2 = tx(2);
+		tx2.requires = vec![vec![3]];
+		tx2.provides = vec![vec![5]];
+		tx2.weight = 7;
+		let bytes = tx1.memory_footprint() + tx2.memory_footprint();
+		import(&mut ready, tx1).unwrap();
+		import(&mut ready, tx2).unwrap();
+		assert_eq!(ready.bytes(), bytes);
+		assert_eq!(ready.weight(), 12);
+
+		// when
+		ready.remove_subtree(&[1]);
+
+		// then
+		assert_eq!(ready.len(), 0);
+		assert_eq!(ready.bytes(), 0);
+		assert_eq!(ready.weight(), 0);
+	}
+
 	#[test]
 	fn should_return_best_transactions_in_correct_order() {
 		// given
@@ -648,14 +950,10 @@ mod tests {
 		tx4.requires = vec![tx1.provides[0].clone()];
 		tx4.provides = vec![];
 		let tx5 = Transaction {
-			data: vec![5],
-			bytes: 1,
-			hash: 5,
-			priority: 1,
 			valid_till: u64::max_value(),	// use the max_value() here for testing.
 			requires: vec![tx1.provides[0].clone()],
 			provides: vec![],
-			propagate: true,
+			..tx(5)
 		};
 
 		// when
@@ -676,6 +974,73 @@ mod tests {
 		assert_eq!(it.next(), None);
 	}
 
+	#[test]
+	fn should_order_transactions_with_equal_priority_by_fee_density() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		let mut tx1 = tx(1);
+		tx1.requires.clear();
+		tx1.provides = vec![vec![1]];
+		tx1.bytes = 200;
+		let mut tx2 = tx(2);
+		tx2.requires.clear();
+		tx2.provides = vec![vec![2]];
+		tx2.bytes = 100;
+		let mut tx3 = tx(3);
+		tx3.requires = vec![vec![1]];
+		tx3.provides = vec![vec![3]];
+		tx3.bytes = 10;
+
+		// when
+		for tx in vec![tx1, tx2, tx3] {
+			import(&mut ready, tx).unwrap();
+		}
+
+		// then
+		assert_eq!(ready.get().map(|tx| tx.data[0]).collect::<Vec<_>>(), vec![1, 2, 3]);
+		assert_eq!(
+			ready.get_by_fee_density(Default::default()).map(|tx| tx.data[0]).collect::<Vec<_>>(),
+			vec![2, 1, 3],
+		);
+	}
+
+	#[test]
+	fn should_shuffle_transactions_deterministically() {
+		// given
+		let mut ready = ReadyTransactions::default();
+		for id in 1..=8 {
+			let mut tx = tx(id);
+			tx.requires.clear();
+			tx.provides = vec![vec![id]];
+			import(&mut ready, tx).unwrap();
+		}
+		let mut dependent = tx(9);
+		dependent.requires = vec![vec![4], vec![7]];
+		dependent.provides = vec![vec![9]];
+		import(&mut ready, dependent).unwrap();
+		let shuffled = |seed| ready.get_shuffled(Default::default(), seed)
+			.into_iter()
+			.map(|tx| tx.data[0])
+			.collect::<Vec<_>>();
+
+		// when
+		let order1 = shuffled([1u8; 32]);
+		let order2 = shuffled([2u8; 32]);
+
+		// then
+		assert_eq!(order1, shuffled([1u8; 32]));
+		assert_ne!(order1, order2);
+		for order in vec![order1, order2] {
+			assert_eq!(order.len(), 9);
+			let position = |id| order.iter().position(|x| *x == id).unwrap();
+			assert!(position(9) > position(4));
+			assert!(position(9) > position(7));
+		}
+		// excluded transactions and their dependents are skipped
+		let excluded = Arc::new(vec![4u64].into_iter().collect());
+		assert_eq!(ready.get_shuffled(excluded, [1u8; 32]).len(), 7);
+	}
+
 	#[test]
 	fn should_order_refs() {
 		let mut id = 1;
@@ -101,7 +101,7 @@ mod tests {
 	use super::*;
 
 	type Hash = u64;
-	type Ex = ();
+	type Ex = Vec<u8>;
 
 	fn rotator() -> PoolRotator<Hash> {
 		PoolRotator {
@@ -113,14 +113,8 @@ mod tests {
 	fn tx() -> (Hash, Transaction<Hash, Ex>) {
 		let hash = 5u64;
 		let tx = Transaction {
-			data: (),
-			bytes: 1,
-			hash: hash.clone(),
-			priority: 5,
 			valid_till: 1,
-			requires: vec![],
-			provides: vec![],
-			propagate: true,
+			..crate::base_pool::tx(hash)
 		};
 
 		(hash, tx)
@@ -177,16 +171,10 @@ mod tests {
 	fn should_garbage_collect() {
 		// given
 		fn tx_with(i: u64, valid_till: u64) -> Transaction<Hash, Ex> {
-			let hash = i;
 			Transaction {
-				data: (),
 				bytes: 2,
-				hash,
-				priority: 5,
 				valid_till,
-				requires: vec![],
-				provides: vec![],
-				propagate: true,
+				..crate::base_pool::tx(i)
 			}
 		}
 
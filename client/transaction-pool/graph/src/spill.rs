@@ -0,0 +1,348 @@
+// Copyright 2018-2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Disk-backed spillover of the future queue.
+//!
+//! Future transactions evicted because of the pool limits are appended to a file
+//! (SCALE-encoded) instead of being dropped. Only their requirements are kept in memory,
+//! so that they can be read back as soon as any of the tags they require gets provided.
+//! Transactions are forgotten once their longevity ends, the file is compacted when
+//! most of it is occupied by records that are no longer referenced.
+
+use std::{
+	collections::{HashMap, HashSet},
+	fs::{File, OpenOptions},
+	hash,
+	io::{self, Read, Seek, SeekFrom, Write},
+	path::Path,
+};
+
+use codec::{Decode, Encode};
+use sp_runtime::transaction_validity::TransactionTag as Tag;
+use sp_transaction_pool::TransactionSource;
+
+/// Maximal number of transactions kept in the spill.
+///
+/// Evicted future transactions are dropped as usual once the spill is full.
+pub const MAX_SPILLED_TRANSACTIONS: usize = 16 * 1024;
+
+/// Size of unreferenced records in the file, below which the file is never compacted.
+const MIN_COMPACTION_BYTES: u64 = 1024 * 1024;
+
+/// Position of a spilled transaction in the file, tags it requires and its longevity.
+struct SpilledEntry {
+	offset: u64,
+	len: u64,
+	requires: Vec<Tag>,
+	valid_till: u64,
+}
+
+/// Spilled future transactions.
+pub struct FutureSpill<Hash> {
+	file: File,
+	/// End of the last record in the file.
+	end: u64,
+	/// Total length of records referenced by entries.
+	live: u64,
+	entries: HashMap<Hash, SpilledEntry>,
+	/// Tags required by spilled transactions.
+	wanted_tags: HashMap<Tag, HashSet<Hash>>,
+	/// Transactions which requirements got satisfied, waiting to be read back.
+	reloadable: Vec<Hash>,
+}
+
+fn encode_source(source: TransactionSource) -> u8 {
+	match source {
+		TransactionSource::InBlock => 0,
+		TransactionSource::Local => 1,
+		TransactionSource::External => 2,
+	}
+}
+
+fn decode_source(source: u8) -> Option<TransactionSource> {
+	match source {
+		0 => Some(TransactionSource::InBlock),
+		1 => Some(TransactionSource::Local),
+		2 => Some(TransactionSource::External),
+		_ => None,
+	}
+}
+
+impl<Hash: hash::Hash + Eq + Clone> FutureSpill<Hash> {
+	/// Creates a spill backed by the file at given path.
+	///
+	/// The file is truncated, transactions spilled by previous runs are not restored.
+	pub fn open(path: &Path) -> io::Result<Self> {
+		let file = OpenOptions::new()
+			.read(true)
+			.write(true)
+			.create(true)
+			.truncate(true)
+			.open(path)?;
+		Ok(FutureSpill {
+			file,
+			end: 0,
+			live: 0,
+			entries: Default::default(),
+			wanted_tags: Default::default(),
+			reloadable: Default::default(),
+		})
+	}
+
+	/// Returns number of spilled transactions.
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	/// Writes the encoded transaction to the spill.
+	///
+	/// The transaction is forgotten after block `valid_till` (see `remove_expired`).
+	/// Returns `false` if the spill is full or the transaction is already spilled.
+	pub fn spill(
+		&mut self,
+		hash: Hash,
+		requires: Vec<Tag>,
+		valid_till: u64,
+		source: TransactionSource,
+		encoded: Vec<u8>,
+	) -> io::Result<bool> {
+		if self.entries.len() >= MAX_SPILLED_TRANSACTIONS || self.entries.contains_key(&hash) {
+			return Ok(false);
+		}
+
+		let record = (encode_source(source), encoded).encode();
+		self.file.seek(SeekFrom::Start(self.end))?;
+		self.file.write_all(&record)?;
+
+		for tag in &requires {
+			self.wanted_tags.entry(tag.clone()).or_insert_with(HashSet::new).insert(hash.clone());
+		}
+		self.entries.insert(hash, SpilledEntry {
+			offset: self.end,
+			len: record.len() as u64,
+			requires,
+			valid_till,
+		});
+		self.end += record.len() as u64;
+		self.live += record.len() as u64;
+		Ok(true)
+	}
+
+	/// Marks spilled transactions requiring any of given tags to be read back.
+	pub fn satisfy_tags<T: AsRef<Tag>>(&mut self, tags: impl IntoIterator<Item=T>) {
+		for tag in tags {
+			if let Some(hashes) = self.wanted_tags.remove(tag.as_ref()) {
+				self.reloadable.extend(hashes);
+			}
+		}
+	}
+
+	/// Reads back and forgets all transactions which requirements got satisfied.
+	///
+	/// Records that can't be read or decoded are skipped.
+	pub fn take_reloadable(&mut self) -> Vec<(TransactionSource, Vec<u8>)> {
+		let mut reloaded = Vec::new();
+		for hash in std::mem::replace(&mut self.reloadable, Vec::new()) {
+			let entry = match self.forget(&hash) {
+				Some(entry) => entry,
+				// already reloaded because of another tag
+				None => continue,
+			};
+			if let Some(record) = self.read(&entry) {
+				reloaded.push(record);
+			}
+		}
+		self.compact();
+
+		reloaded
+	}
+
+	/// Forgets all transactions which longevity ended at or before given block.
+	///
+	/// Returns hashes of the forgotten transactions.
+	pub fn remove_expired(&mut self, block_number: u64) -> Vec<Hash> {
+		let expired = self.entries.iter()
+			.filter(|(_, entry)| entry.valid_till <= block_number)
+			.map(|(hash, _)| hash.clone())
+			.collect::<Vec<_>>();
+		for hash in &expired {
+			self.forget(hash);
+		}
+		self.compact();
+		expired
+	}
+
+	/// Removes the entry of given transaction together with its requirements.
+	fn forget(&mut self, hash: &Hash) -> Option<SpilledEntry> {
+		let entry = self.entries.remove(hash)?;
+		for tag in &entry.requires {
+			let is_empty = self.wanted_tags.get_mut(tag)
+				.map(|hashes| {
+					hashes.remove(hash);
+					hashes.is_empty()
+				})
+				.unwrap_or(false);
+			if is_empty {
+				self.wanted_tags.remove(tag);
+			}
+		}
+		self.live -= entry.len;
+		Some(entry)
+	}
+
+	/// Reclaims space of records that are no longer referenced.
+	///
+	/// The file is truncated once it's not referenced at all, or rewritten with referenced
+	/// records only once unreferenced ones take more space than the referenced ones.
+	/// Records that can't be read back are forgotten.
+	fn compact(&mut self) {
+		// nothing refers to the file content anymore, so it can be reused
+		if self.entries.is_empty() {
+			if self.end > 0 && self.file.set_len(0).is_ok() {
+				self.end = 0;
+			}
+			return;
+		}
+
+		let garbage = self.end - self.live;
+		if garbage < MIN_COMPACTION_BYTES.max(self.live) {
+			return;
+		}
+
+		let mut hashes = self.entries.iter().map(|(hash, entry)| (entry.offset, hash.clone())).collect::<Vec<_>>();
+		hashes.sort_by_key(|(offset, _)| *offset);
+		let mut records = Vec::with_capacity(hashes.len());
+		for (_, hash) in hashes {
+			let record = self.entries.get(&hash).and_then(|entry| self.read_raw(entry));
+			match record {
+				Some(record) => records.push((hash, record)),
+				None => {
+					self.forget(&hash);
+				},
+			}
+		}
+
+		let mut end = 0;
+		for (hash, record) in records {
+			let written = self.file.seek(SeekFrom::Start(end))
+				.and_then(|_| self.file.write_all(&record));
+			match (written, self.entries.get_mut(&hash)) {
+				(Ok(()), Some(entry)) => {
+					entry.offset = end;
+					end += record.len() as u64;
+				},
+				// the previous copy might have been overwritten already
+				_ => {
+					self.forget(&hash);
+				},
+			}
+		}
+		if self.file.set_len(end).is_ok() {
+			self.end = end;
+		}
+	}
+
+	fn read(&mut self, entry: &SpilledEntry) -> Option<(TransactionSource, Vec<u8>)> {
+		let buffer = self.read_raw(entry)?;
+		let (source, encoded) = <(u8, Vec<u8>)>::decode(&mut &buffer[..]).ok()?;
+		Some((decode_source(source)?, encoded))
+	}
+
+	fn read_raw(&self, entry: &SpilledEntry) -> Option<Vec<u8>> {
+		let mut buffer = vec![0; entry.len as usize];
+		let mut file = &self.file;
+		file.seek(SeekFrom::Start(entry.offset)).ok()?;
+		file.read_exact(&mut buffer).ok()?;
+		Some(buffer)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn path(name: &str) -> std::path::PathBuf {
+		std::env::temp_dir().join(format!("txpool-spill-{}-{}", name, std::process::id()))
+	}
+
+	#[test]
+	fn should_read_back_transactions_when_required_tag_is_satisfied() {
+		// given
+		let mut spill = FutureSpill::open(&path("satisfy")).unwrap();
+		assert!(spill.spill(1, vec![vec![1]], 10, TransactionSource::External, vec![1, 2, 3]).unwrap());
+		assert!(spill.spill(2, vec![vec![2], vec![3]], 10, TransactionSource::Local, vec![4]).unwrap());
+		assert!(!spill.spill(2, vec![vec![2]], 10, TransactionSource::Local, vec![4]).unwrap());
+		assert_eq!(spill.len(), 2);
+
+		// when
+		spill.satisfy_tags(&[vec![2], vec![3]]);
+
+		// then
+		assert_eq!(spill.take_reloadable(), vec![(TransactionSource::Local, vec![4])]);
+		assert!(!spill.entries.contains_key(&2));
+		assert!(spill.entries.contains_key(&1));
+		assert!(spill.take_reloadable().is_empty());
+
+		// when
+		spill.satisfy_tags(&[vec![1]]);
+
+		// then
+		assert_eq!(spill.take_reloadable(), vec![(TransactionSource::External, vec![1, 2, 3])]);
+		assert_eq!(spill.len(), 0);
+		assert_eq!(spill.end, 0);
+		std::fs::remove_file(path("satisfy")).ok();
+	}
+
+	#[test]
+	fn should_forget_expired_transactions() {
+		// given
+		let mut spill = FutureSpill::open(&path("expire")).unwrap();
+		assert!(spill.spill(1, vec![vec![1]], 5, TransactionSource::External, vec![1]).unwrap());
+		assert!(spill.spill(2, vec![vec![1]], 10, TransactionSource::External, vec![2]).unwrap());
+
+		// when
+		let expired = spill.remove_expired(5);
+
+		// then
+		assert_eq!(expired, vec![1]);
+		assert_eq!(spill.len(), 1);
+		spill.satisfy_tags(&[vec![1]]);
+		assert_eq!(spill.take_reloadable(), vec![(TransactionSource::External, vec![2])]);
+		std::fs::remove_file(path("expire")).ok();
+	}
+
+	#[test]
+	fn should_compact_the_file_under_churn() {
+		// given
+		let mut spill = FutureSpill::open(&path("compact")).unwrap();
+		let large = vec![0u8; MIN_COMPACTION_BYTES as usize];
+		assert!(spill.spill(1, vec![vec![1]], 10, TransactionSource::External, large.clone()).unwrap());
+		assert!(spill.spill(2, vec![vec![2]], 10, TransactionSource::External, large.clone()).unwrap());
+		assert!(spill.spill(3, vec![vec![3]], 10, TransactionSource::Local, vec![3]).unwrap());
+		let end = spill.end;
+
+		// when
+		spill.satisfy_tags(&[vec![1], vec![2]]);
+		assert_eq!(spill.take_reloadable().len(), 2);
+
+		// then
+		assert!(spill.end < end);
+		assert_eq!(spill.end, spill.live);
+		spill.satisfy_tags(&[vec![3]]);
+		assert_eq!(spill.take_reloadable(), vec![(TransactionSource::Local, vec![3])]);
+		std::fs::remove_file(path("compact")).ok();
+	}
+}
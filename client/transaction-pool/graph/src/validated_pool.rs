@@ -15,31 +15,47 @@
 // along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::{
-	collections::{HashSet, HashMap},
+	collections::{HashSet, HashMap, VecDeque},
 	fmt,
 	hash,
-	sync::Arc,
+	sync::{Arc, atomic::{AtomicU64, Ordering}},
 	time,
 };
 
 use crate::base_pool as base;
+use crate::bloom::RecentlySeen;
+use crate::bounded_stream::{self, BoundedSink, BoundedStream};
 use crate::listener::Listener;
 use crate::rotator::PoolRotator;
+use crate::spill::FutureSpill;
 use crate::watcher::Watcher;
 use serde::Serialize;
 use log::{debug, warn};
+use codec::{Decode, Encode};
 
 use futures::channel::mpsc;
-use parking_lot::{Mutex, RwLock};
+use parking_lot::{Mutex, RwLock, RwLockWriteGuard};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{self, SaturatedConversion},
-	transaction_validity::TransactionTag as Tag,
+	transaction_validity::{TransactionTag as Tag, TransactionLongevity, ValidTransaction},
+};
+use sp_transaction_pool::{
+	error, AllStatusStream, FutureStatus, PoolStatus, SourceCounts, TransactionSource, TransactionWeight as Weight,
 };
-use sp_transaction_pool::{error, PoolStatus};
 
 use crate::base_pool::PruneStatus;
-use crate::pool::{EventStream, Options, ChainApi, BlockHash, ExHash, ExtrinsicFor, TransactionFor};
+use crate::pool::{
+	DeadletterStream, EventStream, FuturePromotionStats, Options, ExtensionsFor, ChainApi, BlockHash, ExHash, ExtrinsicFor,
+	RejectionReason, SubmitResult, TransactionFor,
+};
+
+/// Maximal number of transactions kept in the quarantine.
+const QUARANTINE_SIZE: usize = 512;
+/// How long transactions are kept in the quarantine.
+const QUARANTINE_TTL: time::Duration = time::Duration::from_secs(60 * 10);
+/// Share of the queue limits above which the pool is considered to be near its capacity.
+const NEAR_CAPACITY: f32 = 0.9;
 
 /// Pre-validated transaction. Validated pool only accepts transactions wrapped in this enum.
 #[derive(Debug)]
@@ -61,33 +77,107 @@ pub type ValidatedTransactionFor<B> = ValidatedTransaction<
 	<B as ChainApi>::Error,
 >;
 
+/// Events a newly created watcher is interested in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WatchMode {
+	/// All status events.
+	All,
+	/// All status events and queue position updates.
+	WithPosition,
+	/// The terminal status only.
+	TerminalOnly,
+}
+
 /// Pool that deals with validated transactions.
 pub(crate) struct ValidatedPool<B: ChainApi> {
-	api: Arc<B>,
+	api: RwLock<Arc<B>>,
 	options: Options,
+	extensions: ExtensionsFor<B>,
 	listener: RwLock<Listener<ExHash<B>, BlockHash<B>>>,
 	pool: RwLock<base::BasePool<
 		ExHash<B>,
 		ExtrinsicFor<B>,
 	>>,
 	import_notification_sinks: Mutex<Vec<mpsc::UnboundedSender<ExHash<B>>>>,
+	bounded_import_notification_sinks: Mutex<Vec<BoundedSink<ExHash<B>>>>,
+	deadletter_sinks: Mutex<Vec<mpsc::UnboundedSender<(ExHash<B>, RejectionReason)>>>,
 	rotator: PoolRotator<ExHash<B>>,
+	/// Hashes of recently submitted, pruned or banned transactions.
+	recently_seen: Mutex<RecentlySeen<ExHash<B>>>,
+	/// Transactions invalidated during revalidation, with the error and the time of invalidation.
+	quarantine: Mutex<VecDeque<(TransactionFor<B>, String, time::Instant)>>,
+	/// Watcher slots per source, occupied slots are shared with live watchers.
+	watcher_slots: Mutex<HashMap<String, Arc<()>>>,
+	/// Time of the last eviction pass that removed any transactions.
+	last_eviction: Mutex<Option<time::Instant>>,
+	/// Number of structural changes of the pool.
+	generation: AtomicU64,
+	/// Future transactions evicted to disk (see `LimitOptions::future_spill_path`).
+	future_spill: Mutex<Option<FutureSpill<ExHash<B>>>>,
+	/// Number of future transactions that became ready.
+	promoted_futures: AtomicU64,
+	/// Number of future transactions removed because their longevity ended.
+	expired_futures: AtomicU64,
 }
 
 impl<B: ChainApi> ValidatedPool<B> {
 	/// Create a new transaction pool.
-	pub fn new(options: Options, api: Arc<B>) -> Self {
-		let base_pool = base::BasePool::new(options.reject_future_transactions);
+	pub fn new(options: Options, extensions: ExtensionsFor<B>, api: Arc<B>) -> Self {
+		let base_pool = base::BasePool::with_log_target(
+			options.reject_future_transactions,
+			options.log_target,
+		)
+			.with_max_future_chain_depth(options.admission.max_future_chain_depth)
+			.with_reject_dependency_cycles(options.admission.reject_dependency_cycles)
+			.with_replace_equal_priority(options.admission.replace_equal_priority)
+			.with_type_limits(options.limits.type_limits.clone());
+		let future_spill = options.limits.future_spill_path.as_ref().and_then(|path| match FutureSpill::open(path) {
+			Ok(spill) => Some(spill),
+			Err(e) => {
+				warn!(target: options.log_target, "Failed to open future spill at {:?}: {:?}", path, e);
+				None
+			},
+		});
 		ValidatedPool {
-			api,
+			api: RwLock::new(api),
+			listener: RwLock::new(
+				Listener::new(options.log_target)
+					.with_max_watch_events(options.watch.max_watch_events)
+					.with_finality_timeout(options.watch.finality_timeout_blocks)
+			),
 			options,
-			listener: Default::default(),
+			extensions,
 			pool: RwLock::new(base_pool),
 			import_notification_sinks: Default::default(),
+			bounded_import_notification_sinks: Default::default(),
+			deadletter_sinks: Default::default(),
 			rotator: Default::default(),
+			recently_seen: Default::default(),
+			quarantine: Default::default(),
+			watcher_slots: Default::default(),
+			last_eviction: Default::default(),
+			generation: AtomicU64::new(0),
+			future_spill: Mutex::new(future_spill),
+			promoted_futures: AtomicU64::new(0),
+			expired_futures: AtomicU64::new(0),
 		}
 	}
 
+	/// Locks the pool for a structural change.
+	fn pool_mut(&self) -> RwLockWriteGuard<base::BasePool<ExHash<B>, ExtrinsicFor<B>>> {
+		let pool = self.pool.write();
+		self.generation.fetch_add(1, Ordering::SeqCst);
+		pool
+	}
+
+	/// Returns a counter increased on every structural change of the pool.
+	///
+	/// The counter is bumped on every import, removal, pruning and revalidation,
+	/// also the ones that end up leaving the pool intact.
+	pub fn generation(&self) -> u64 {
+		self.generation.load(Ordering::SeqCst)
+	}
+
 	/// Bans given set of hashes.
 	pub fn ban(&self, now: &std::time::Instant, hashes: impl IntoIterator<Item=ExHash<B>>) {
 		self.rotator.ban(now, hashes)
@@ -98,75 +188,295 @@ impl<B: ChainApi> ValidatedPool<B> {
 		self.rotator.is_banned(hash)
 	}
 
+	/// Remembers given hash as recently seen.
+	pub fn note_seen(&self, hash: &ExHash<B>) {
+		self.recently_seen.lock().insert(hash)
+	}
+
+	/// Returns true if transaction with given hash might have been seen recently.
+	///
+	/// Covers submitted, pruned and banned transactions. False positives are possible,
+	/// but recently seen hashes are always reported.
+	pub fn maybe_seen(&self, hash: &ExHash<B>) -> bool {
+		self.rotator.is_banned(hash) || self.recently_seen.lock().contains(hash)
+	}
+
 	/// Imports a bunch of pre-validated transactions to the pool.
 	pub fn submit<T>(&self, txs: T) -> Vec<Result<ExHash<B>, B::Error>> where
 		T: IntoIterator<Item=ValidatedTransactionFor<B>>
 	{
 		let results = txs.into_iter()
-			.map(|validated_tx| self.submit_one(validated_tx))
+			.map(|validated_tx| self.submit_one(validated_tx).map(|result| result.hash))
 			.collect::<Vec<_>>();
 
 		// only enforce limits if there is at least one imported transaction
-		let removed = if results.iter().any(|res| res.is_ok()) {
+		let mut removed = if results.iter().any(|res| res.is_ok()) {
 			self.enforce_limits()
 		} else {
 			Default::default()
 		};
 
 		results.into_iter().map(|res| match res {
-			Ok(ref hash) if removed.contains(hash) => Err(error::Error::ImmediatelyDropped.into()),
+			Ok(hash) => match removed.remove(&hash) {
+				Some(error) => Err(error.into()),
+				None => Ok(hash),
+			},
 			other => other,
 		}).collect()
 	}
 
+	/// Imports single pre-validated transaction to the pool, reporting the transaction it replaced.
+	pub fn submit_detailed(&self, tx: ValidatedTransactionFor<B>) -> Result<SubmitResult<ExHash<B>>, B::Error> {
+		let result = self.submit_one(tx)?;
+		if let Some(error) = self.enforce_limits().remove(&result.hash) {
+			return Err(error.into());
+		}
+		Ok(result)
+	}
+
 	/// Submit single pre-validated transaction to the pool.
-	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<ExHash<B>, B::Error> {
+	fn submit_one(&self, tx: ValidatedTransactionFor<B>) -> Result<SubmitResult<ExHash<B>>, B::Error> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
-				let imported = self.pool.write().import(tx)?;
+				if self.options.admission.upgrade_local_resubmissions
+					&& tx.source == TransactionSource::Local
+					&& self.upgrade_to_local(&tx.hash)
+				{
+					return Ok(SubmitResult {
+						hash: tx.hash,
+						replaced: None,
+					});
+				}
+
+				let provides = tx.provides.clone();
+				let imported = self.pool_mut().import(tx)?;
+				// the replaced transaction provides the same tag, the other removed ones
+				// were pushed out by transactions promoted from the future queue
+				let replaced = match imported {
+					base::Imported::Ready { ref removed, .. } => removed.iter()
+						.find(|tx| tx.provides.iter().any(|tag| provides.contains(tag)))
+						.map(|tx| tx.hash.clone()),
+					base::Imported::Future { .. } => None,
+				};
 
 				if let base::Imported::Ready { ref hash, .. } = imported {
-					self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(hash.clone()).is_ok());
+					self.notify_imported(hash);
 				}
+				self.satisfy_spilled(&imported);
+				self.note_promoted(&imported, false);
 
 				let mut listener = self.listener.write();
 				fire_events(&mut *listener, &imported);
-				Ok(imported.hash().clone())
+				Ok(SubmitResult {
+					hash: imported.hash().clone(),
+					replaced,
+				})
 			}
 			ValidatedTransaction::Invalid(hash, err) => {
+				self.notify_rejected(&hash, &err);
 				self.rotator.ban(&std::time::Instant::now(), std::iter::once(hash));
 				Err(err.into())
 			},
 			ValidatedTransaction::Unknown(hash, err) => {
+				self.notify_rejected(&hash, &err);
 				self.listener.write().invalid(&hash, false);
 				Err(err.into())
 			}
 		}
 	}
 
-	fn enforce_limits(&self) -> HashSet<ExHash<B>> {
+	/// Replaces a transaction with a pre-validated one.
+	///
+	/// `old` is only removed if `new` enters the pool, otherwise the pool is left intact.
+	/// Ready transactions depending on `old` are imported back afterwards (see `BasePool::replace`).
+	pub fn replace(&self, old: &ExHash<B>, new: ValidatedTransactionFor<B>) -> Result<ExHash<B>, B::Error> {
+		let tx = match new {
+			ValidatedTransaction::Valid(tx) => tx,
+			ValidatedTransaction::Invalid(hash, err) => {
+				self.notify_rejected(&hash, &err);
+				self.rotator.ban(&std::time::Instant::now(), std::iter::once(hash));
+				return Err(err)
+			},
+			ValidatedTransaction::Unknown(hash, err) => {
+				self.notify_rejected(&hash, &err);
+				return Err(err)
+			},
+		};
+
+		let replaced = {
+			let mut pool = self.pool_mut();
+			// `old` would be gone already if `new` got evicted right after the swap
+			self.check_replacement_limits(&pool, old, &tx)?;
+			pool.replace(old, tx)?
+		};
+		let hash = replaced.imported.hash().clone();
+		if let base::Imported::Ready { .. } = replaced.imported {
+			self.notify_imported(&hash);
+		}
+		self.satisfy_spilled(&replaced.imported);
+		self.note_promoted(&replaced.imported, false);
+
+		{
+			let mut listener = self.listener.write();
+			for tx in &replaced.removed {
+				listener.dropped(&tx.hash, Some(&hash));
+			}
+			fire_events(&mut *listener, &replaced.imported);
+			for result in &replaced.restored {
+				fire_events(&mut *listener, result);
+			}
+		}
+
+		self.enforce_limits();
+		Ok(hash)
+	}
+
+	/// Fails if replacing `old` with `tx` would push the queue `tx` lands in over its limit.
+	///
+	/// `tx` is assumed to land in the queue of `old`, or in the ready queue if it has
+	/// no requirements and `old` is not in the pool.
+	fn check_replacement_limits(
+		&self,
+		pool: &base::BasePool<ExHash<B>, ExtrinsicFor<B>>,
+		old: &ExHash<B>,
+		tx: &base::Transaction<ExHash<B>, ExtrinsicFor<B>>,
+	) -> Result<(), error::Error> {
+		let status = pool.status();
+		let old_tx = pool.by_hashes(&[old.clone()]).pop().flatten();
+		let is_ready = match old_tx {
+			Some(_) => pool.ready_by_hash(old).is_some(),
+			None => tx.requires.is_empty(),
+		};
+		let (limit, count, bytes) = if is_ready {
+			(&self.options.ready, status.ready, status.ready_bytes)
+		} else {
+			(&self.options.future, status.future, status.future_bytes)
+		};
+		let (new_count, new_bytes) = match old_tx {
+			Some(old_tx) => (count, bytes.saturating_sub(old_tx.memory_footprint())),
+			None => (count + 1, bytes),
+		};
+		if limit.is_exceeded(new_count, new_bytes + tx.memory_footprint()) {
+			let exceeded = if is_ready { error::ExceededLimit::Ready } else { error::ExceededLimit::Future };
+			return Err(limit.exceeded(exceeded, count, bytes));
+		}
+		Ok(())
+	}
+
+	/// Changes source of a transaction already in the pool to local and pins it.
+	///
+	/// Returns `false` if the transaction is not in the pool or is local already.
+	fn upgrade_to_local(&self, hash: &ExHash<B>) -> bool {
+		let mut pool = self.pool_mut();
+		if !pool.set_source(hash, TransactionSource::Local) {
+			return false;
+		}
+		pool.pin(hash);
+		debug!(target: self.options.log_target, "[{:?}] Upgraded to local", hash);
+		true
+	}
+
+	/// Imports a batch of pre-validated transactions to the pool atomically.
+	///
+	/// The batch is imported only if all of the transactions are valid and can enter the pool.
+	/// Otherwise the pool is left intact, no events are fired and the index of the first
+	/// failing transaction is returned together with the error.
+	pub fn submit_atomic(
+		&self,
+		txs: Vec<ValidatedTransactionFor<B>>,
+	) -> Result<Vec<ExHash<B>>, (usize, B::Error)> {
+		let mut valid = Vec::with_capacity(txs.len());
+		for (index, tx) in txs.into_iter().enumerate() {
+			match tx {
+				ValidatedTransaction::Valid(tx) => valid.push(tx),
+				ValidatedTransaction::Invalid(hash, err) => {
+					self.notify_rejected(&hash, &err);
+					self.rotator.ban(&std::time::Instant::now(), std::iter::once(hash));
+					return Err((index, err))
+				},
+				ValidatedTransaction::Unknown(hash, err) => {
+					self.notify_rejected(&hash, &err);
+					return Err((index, err))
+				},
+			}
+		}
+
+		let imported = self.pool_mut().import_atomic(valid)
+			.map_err(|(index, err)| (index, err.into()))?;
+
+		for result in &imported {
+			if let base::Imported::Ready { hash, .. } = result {
+				self.notify_imported(hash);
+			}
+		}
+
+		{
+			let mut listener = self.listener.write();
+			for result in &imported {
+				self.note_promoted(result, false);
+				fire_events(&mut *listener, result);
+			}
+		}
+
+		// evicting a part of the batch would break its atomicity
+		let hashes = imported.iter().map(|result| result.hash().clone()).collect::<Vec<_>>();
+		self.enforce_limits_protecting(&hashes);
+		Ok(hashes)
+	}
+
+	/// Returns true if an eviction pass ran recently.
+	fn is_eviction_cooling_down(&self) -> bool {
+		let cooldown = self.options.limits.eviction_cooldown;
+		cooldown != time::Duration::default()
+			&& self.last_eviction.lock().map_or(false, |at| at.elapsed() < cooldown)
+	}
+
+	/// Evicts transactions exceeding the pool limits, returning the removed ones with the reason.
+	fn enforce_limits(&self) -> HashMap<ExHash<B>, error::Error> {
+		self.enforce_limits_protecting(&[])
+	}
+
+	/// Same as `enforce_limits`, but never evicts transactions in `protect` and the ones they depend on.
+	fn enforce_limits_protecting(&self, protect: &[ExHash<B>]) -> HashMap<ExHash<B>, error::Error> {
 		let status = self.pool.read().status();
-		let ready_limit = &self.options.ready;
-		let future_limit = &self.options.future;
+		// during the cool-down only the hard limits are enforced
+		let cooling_down = self.is_eviction_cooling_down();
+		let overflow = if cooling_down { self.options.limits.eviction_cooldown_overflow } else { 0.0 };
+		let ready_limit = &self.options.ready.extended(overflow);
+		let future_limit = &self.options.future.extended(overflow);
+		let max_weight = self.options.limits.max_weight
+			.map(|max| max.saturating_add((max as f64 * f64::from(overflow)) as Weight));
+		let quotas = &self.options.limits.source_quotas;
 
-		debug!(target: "txpool", "Pool Status: {:?}", status);
+		debug!(target: self.options.log_target, "Pool Status: {:?}", status);
 
 		if ready_limit.is_exceeded(status.ready, status.ready_bytes)
-			|| future_limit.is_exceeded(status.future, status.future_bytes) {
+			|| future_limit.is_exceeded(status.future, status.future_bytes)
+			|| quotas.is_partitioned()
+			|| max_weight.is_some() {
 			// clean up the pool
-			let removed = {
-				let mut pool = self.pool.write();
-				let removed = pool.enforce_limits(ready_limit, future_limit)
-					.into_iter().map(|x| x.hash.clone()).collect::<HashSet<_>>();
-				// ban all removed transactions
-				self.rotator.ban(&std::time::Instant::now(), removed.iter().map(|x| x.clone()));
-				removed
-			};
+			let evicted = self.pool_mut().enforce_limits(ready_limit, future_limit, quotas, max_weight, protect);
+			// spilled transactions are still part of the pool
+			let spilled = self.spill_evicted(&evicted);
+			let removed = evicted.into_iter()
+				.map(|(tx, error)| (tx.hash.clone(), error))
+				.filter(|(hash, _)| !spilled.contains(hash))
+				.collect::<HashMap<_, _>>();
+			// ban all removed transactions
+			self.rotator.ban(&std::time::Instant::now(), removed.keys().cloned());
+			// evictions of the hard limits don't extend the cool-down
+			if !removed.is_empty() && !cooling_down {
+				*self.last_eviction.lock() = Some(time::Instant::now());
+			}
+
 			// run notifications
 			let mut listener = self.listener.write();
-			for h in &removed {
+			for h in removed.keys() {
 				listener.dropped(h, None);
 			}
+			for h in &spilled {
+				listener.spilled(h);
+			}
 
 			removed
 		} else {
@@ -174,19 +484,130 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}
 	}
 
+	/// Writes transactions evicted because of the future queue limit to the spill.
+	///
+	/// Returns hashes of transactions that were spilled.
+	fn spill_evicted(&self, evicted: &[(TransactionFor<B>, error::Error)]) -> HashSet<ExHash<B>> {
+		let mut spill = self.future_spill.lock();
+		let spill = match spill.as_mut() {
+			Some(spill) => spill,
+			None => return Default::default(),
+		};
+		let log_target = self.options.log_target;
+		evicted.iter()
+			.filter(|(_, error)| match error {
+				error::Error::ImmediatelyDropped { exceeded: error::ExceededLimit::Future, .. } => true,
+				_ => false,
+			})
+			.map(|(tx, _)| tx)
+			.filter(|tx| match spill.spill(
+				tx.hash.clone(),
+				tx.requires.clone(),
+				tx.valid_till,
+				tx.source,
+				tx.data.encode(),
+			) {
+				Ok(spilled) => spilled,
+				Err(e) => {
+					warn!(target: log_target, "[{:?}] Failed to spill future transaction: {:?}", tx.hash, e);
+					false
+				},
+			})
+			.map(|tx| tx.hash.clone())
+			.collect()
+	}
+
+	/// Marks spilled transactions requiring tags provided by newly ready transactions as reloadable.
+	fn satisfy_spilled(&self, imported: &base::Imported<ExHash<B>, ExtrinsicFor<B>>) {
+		if self.future_spill.lock().is_none() {
+			return;
+		}
+		let tags = match *imported {
+			base::Imported::Ready { ref hash, ref promoted, .. } => {
+				let pool = self.pool.read();
+				std::iter::once(hash).chain(promoted)
+					.filter_map(|hash| pool.ready_by_hash(hash))
+					.flat_map(|tx| tx.provides.clone())
+					.collect::<Vec<_>>()
+			},
+			base::Imported::Future { .. } => return,
+		};
+		if let Some(spill) = self.future_spill.lock().as_mut() {
+			spill.satisfy_tags(tags);
+		}
+	}
+
+	/// Removes and returns spilled transactions which requirements got satisfied.
+	pub fn take_spilled(&self) -> Vec<(TransactionSource, ExtrinsicFor<B>)> {
+		let reloaded = match self.future_spill.lock().as_mut() {
+			Some(spill) => spill.take_reloadable(),
+			None => return Vec::new(),
+		};
+		reloaded.into_iter()
+			.filter_map(|(source, encoded)| match Decode::decode(&mut &encoded[..]) {
+				Ok(xt) => Some((source, xt)),
+				Err(e) => {
+					warn!(target: self.options.log_target, "Failed to decode spilled transaction: {:?}", e);
+					None
+				},
+			})
+			.collect()
+	}
+
+	/// Returns number of future transactions spilled to disk.
+	pub fn spilled_count(&self) -> usize {
+		self.future_spill.lock().as_ref().map(|spill| spill.len()).unwrap_or(0)
+	}
+
 	/// Import a single extrinsic and starts to watch their progress in the pool.
+	///
+	/// The watcher is registered before the import, so the first event it yields
+	/// is always the placement of the extrinsic (`Ready` or `Future`).
 	pub fn submit_and_watch(
 		&self,
 		tx: ValidatedTransactionFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(tx, WatchMode::All)
+	}
+
+	/// Import a single extrinsic and starts to watch their progress in the pool,
+	/// including the position of the extrinsic in the ready queue.
+	pub fn submit_and_watch_with_position(
+		&self,
+		tx: ValidatedTransactionFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(tx, WatchMode::WithPosition)
+	}
+
+	/// Import a single extrinsic and starts to watch its terminal status only.
+	pub fn submit_and_watch_terminal_only(
+		&self,
+		tx: ValidatedTransactionFor<B>,
+	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
+		self.submit_and_watch_with_mode(tx, WatchMode::TerminalOnly)
+	}
+
+	pub(crate) fn submit_and_watch_with_mode(
+		&self,
+		tx: ValidatedTransactionFor<B>,
+		mode: WatchMode,
 	) -> Result<Watcher<ExHash<B>, BlockHash<B>>, B::Error> {
 		match tx {
 			ValidatedTransaction::Valid(tx) => {
-				let hash = self.api.hash_and_length(&tx.data).0;
-				let watcher = self.listener.write().create_watcher(hash);
-				self.submit(std::iter::once(ValidatedTransaction::Valid(tx)))
+				let hash = tx.hash.clone();
+				let watcher = match mode {
+					WatchMode::All => self.listener.write().create_watcher(hash),
+					WatchMode::WithPosition => self.listener.write().create_watcher_with_position(hash),
+					WatchMode::TerminalOnly => self.listener.write().create_watcher_terminal_only(hash),
+				};
+				let result = self.submit(std::iter::once(ValidatedTransaction::Valid(tx)))
 					.pop()
 					.expect("One extrinsic passed; one result returned; qed")
-					.map(|_| watcher)
+					.map(|_| watcher);
+				if mode == WatchMode::WithPosition && result.is_ok() {
+					self.notify_positions();
+				}
+				result
 			},
 			ValidatedTransaction::Invalid(hash, err) => {
 				self.rotator.ban(&std::time::Instant::now(), std::iter::once(hash));
@@ -196,6 +617,53 @@ impl<B: ChainApi> ValidatedPool<B> {
 		}
 	}
 
+	/// Starts to watch a transaction kept aside until the block it becomes valid at.
+	///
+	/// The watcher yields `Future` right away and follows the transaction once it's imported.
+	pub(crate) fn watch_time_gated(&self, hash: ExHash<B>, mode: WatchMode) -> Watcher<ExHash<B>, BlockHash<B>> {
+		let mut listener = self.listener.write();
+		let watcher = match mode {
+			WatchMode::All => listener.create_watcher(hash.clone()),
+			WatchMode::WithPosition => listener.create_watcher_with_position(hash.clone()),
+			WatchMode::TerminalOnly => listener.create_watcher_terminal_only(hash.clone()),
+		};
+		listener.future(&hash);
+		watcher
+	}
+
+	/// Notifies watchers of a time-gated transaction that failed to enter the pool once valid.
+	pub fn time_gated_rejected(&self, hash: &ExHash<B>, error: &B::Error) {
+		match error::IntoPoolError::as_pool_error(error) {
+			// either watched in the pool already, or the watchers got notified on removal
+			Some(error::Error::AlreadyImported(_))
+				| Some(error::Error::ImmediatelyDropped { .. })
+				| Some(error::Error::WeightLimitExceeded { .. }) => {},
+			_ => self.listener.write().invalid(hash, false),
+		}
+	}
+
+	/// Reserves a watcher slot for given source.
+	///
+	/// Returns `None` if there is no limit of watchers per source configured.
+	/// The slot is released when the returned reference is dropped.
+	pub fn reserve_watcher_slot(&self, source_id: &str) -> Result<Option<Arc<()>>, B::Error> {
+		let max = match self.options.watch.max_watchers_per_source {
+			Some(max) => max,
+			None => return Ok(None),
+		};
+
+		// dropped watchers release their slots, sources without live watchers
+		// are forgotten in `clear_stale`
+		let mut slots = self.watcher_slots.lock();
+		let slot = slots.entry(source_id.to_owned()).or_insert_with(|| Arc::new(()));
+		let current = Arc::strong_count(slot) - 1;
+		if current >= max {
+			return Err(error::Error::TooManyWatchersForSource { current, limit: max }.into());
+		}
+
+		Ok(Some(slot.clone()))
+	}
+
 	/// Resubmits revalidated transactions back to the pool.
 	///
 	/// Removes and then submits passed transactions and all dependent transactions.
@@ -204,8 +672,10 @@ impl<B: ChainApi> ValidatedPool<B> {
 		#[derive(Debug, Clone, Copy, PartialEq)]
 		enum Status { Future, Ready, Failed, Dropped };
 
+		let mut invalidated = Vec::new();
+		let mut rejected = Vec::new();
 		let (mut initial_statuses, final_statuses) = {
-			let mut pool = self.pool.write();
+			let mut pool = self.pool_mut();
 
 			// remove all passed transactions from the ready/future queues
 			// (this may remove additional transactions as well)
@@ -226,7 +696,18 @@ impl<B: ChainApi> ValidatedPool<B> {
 				for removed_tx in removed {
 					let removed_hash = removed_tx.hash.clone();
 					let updated_transaction = updated_transactions.remove(&removed_hash);
-					let tx_to_resubmit = if let Some(updated_tx) = updated_transaction {
+					let tx_to_resubmit = if let Some(mut updated_tx) = updated_transaction {
+						match &mut updated_tx {
+							// keep the original submission time of revalidated transactions
+							ValidatedTransaction::Valid(tx) => tx.submitted_at = removed_tx.submitted_at,
+							ValidatedTransaction::Invalid(_, err) => {
+								if self.options.maintenance.quarantine_invalid {
+									invalidated.push((removed_tx.clone(), err.to_string()));
+								}
+								rejected.push((removed_hash.clone(), RejectionReason::from_error(&*err)));
+							},
+							_ => {},
+						}
 						updated_tx
 					} else {
 						// in most cases we'll end up in successful `try_unwrap`, but if not
@@ -277,7 +758,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 								// nor we do want to propagate this error, because it could tx unknown to caller
 								// => let's just notify listeners (and issue debug message)
 								warn!(
-									target: "txpool",
+									target: self.options.log_target,
 									"[{:?}] Removing invalid transaction from update: {}",
 									hash,
 									err,
@@ -316,6 +797,60 @@ impl<B: ChainApi> ValidatedPool<B> {
 				}
 			}
 		}
+
+		for (hash, reason) in rejected {
+			self.notify_deadletter(&hash, reason);
+		}
+
+		if !invalidated.is_empty() {
+			let now = time::Instant::now();
+			let mut quarantine = self.quarantine.lock();
+			for (tx, err) in invalidated {
+				if quarantine.len() >= QUARANTINE_SIZE {
+					quarantine.pop_front();
+				}
+				quarantine.push_back((tx, err, now));
+			}
+		}
+	}
+
+	/// Returns transactions invalidated during revalidation together with the reason.
+	///
+	/// Only populated if `MaintenanceOptions::quarantine_invalid` is set. Entries expire after `QUARANTINE_TTL`.
+	pub fn quarantined(&self) -> Vec<(TransactionFor<B>, String)> {
+		let now = time::Instant::now();
+		let mut quarantine = self.quarantine.lock();
+		quarantine.retain(|(_, _, at)| now.duration_since(*at) < QUARANTINE_TTL);
+		quarantine.iter().map(|(tx, err, _)| (tx.clone(), err.clone())).collect()
+	}
+
+	/// Re-applies the priority normalizer to all ready transactions and reorders the ready queue.
+	///
+	/// The normalizer is given validity reconstructed from the pooled transaction with
+	/// the priority originally reported by the runtime. The pool doesn't keep the original
+	/// longevity, so `longevity` is set to the maximal value.
+	pub fn reorder_ready(&self) {
+		let normalizer = &self.extensions.priority_normalizer;
+		self.pool_mut().update_ready_priorities(|tx| normalizer.normalize(&ValidTransaction {
+			priority: tx.runtime_priority,
+			requires: tx.requires.clone(),
+			provides: tx.provides.clone(),
+			longevity: TransactionLongevity::max_value(),
+			propagate: tx.propagate,
+		}));
+		self.notify_positions();
+	}
+
+	/// Notify watchers interested in queue positions about current positions of their transactions.
+	///
+	/// Positions are approximate, since the ready set changes with every block.
+	pub fn notify_positions(&self) {
+		if !self.listener.read().wants_positions() {
+			return;
+		}
+
+		let ready = self.pool.read().ready().map(|tx| tx.hash.clone()).collect::<Vec<_>>();
+		self.listener.write().queued(ready.iter(), ready.len());
 	}
 
 	/// For each extrinsic, returns tags that it provides (if known), or None (if it is unknown).
@@ -328,6 +863,11 @@ impl<B: ChainApi> ValidatedPool<B> {
 			.collect()
 	}
 
+	/// Get transaction by hash, either ready or future.
+	pub fn by_hash(&self, hash: &ExHash<B>) -> Option<TransactionFor<B>> {
+		self.pool.read().by_hashes(&[hash.clone()]).pop().flatten()
+	}
+
 	/// Get ready transaction by hash
 	pub fn ready_by_hash(&self, hash: &ExHash<B>) -> Option<TransactionFor<B>> {
 		self.pool.read().ready_by_hash(hash)
@@ -339,7 +879,15 @@ impl<B: ChainApi> ValidatedPool<B> {
 		tags: impl IntoIterator<Item=Tag>,
 	) -> Result<PruneStatus<ExHash<B>, ExtrinsicFor<B>>, B::Error> {
 		// Perform tag-based pruning in the base pool
-		let status = self.pool.write().prune_tags(tags);
+		let tags = tags.into_iter().collect::<Vec<_>>();
+		let status = self.pool_mut().prune_tags(tags.iter().cloned());
+		if let Some(spill) = self.future_spill.lock().as_mut() {
+			spill.satisfy_tags(&tags);
+		}
+		for promoted in &status.promoted {
+			self.satisfy_spilled(promoted);
+			self.note_promoted(promoted, true);
+		}
 		// Notify event listeners of all transactions
 		// that were promoted to `Ready` or were dropped.
 		{
@@ -362,6 +910,7 @@ impl<B: ChainApi> ValidatedPool<B> {
 		known_imported_hashes: impl IntoIterator<Item=ExHash<B>> + Clone,
 		pruned_hashes: Vec<ExHash<B>>,
 		pruned_xts: Vec<ValidatedTransactionFor<B>>,
+		conflicts: HashMap<ExHash<B>, ExHash<B>>,
 	) -> Result<(), B::Error> {
 		debug_assert_eq!(pruned_hashes.len(), pruned_xts.len());
 
@@ -375,10 +924,21 @@ impl<B: ChainApi> ValidatedPool<B> {
 			.filter_map(|(idx, r)| match r.map_err(error::IntoPoolError::into_pool_error) {
 				Err(Ok(error::Error::InvalidTransaction(_))) => Some(pruned_hashes[idx].clone()),
 				_ => None,
-			});
+			})
+			.collect::<Vec<_>>();
+
+		// Transactions conflicting with the imported ones were not included, they got replaced.
+		let (usurped, hashes): (Vec<_>, Vec<_>) = hashes.into_iter()
+			.partition(|hash| conflicts.contains_key(hash));
+		{
+			let mut listener = self.listener.write();
+			for hash in &usurped {
+				listener.dropped(hash, conflicts.get(hash));
+			}
+		}
 		// Fire `pruned` notifications for collected hashes and make sure to include
 		// `known_imported_hashes` since they were just imported as part of the block.
-		let hashes = hashes.chain(known_imported_hashes.into_iter());
+		let hashes = hashes.into_iter().chain(known_imported_hashes.into_iter());
 		self.fire_pruned(at, hashes)?;
 
 		// perform regular cleanup of old transactions in the pool
@@ -393,22 +953,39 @@ impl<B: ChainApi> ValidatedPool<B> {
 		at: &BlockId<B::Block>,
 		hashes: impl Iterator<Item=ExHash<B>>,
 	) -> Result<(), B::Error> {
-		let header_hash = self.api.block_id_to_hash(at)?
+		let header_hash = self.api().block_id_to_hash(at)?
 			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?;
+		let block_number = self.api().block_id_to_number(at)?
+			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?
+			.saturated_into::<u64>();
 		let mut listener = self.listener.write();
 		for h in hashes {
-			listener.pruned(header_hash, &h);
+			self.note_seen(&h);
+			listener.pruned(header_hash, block_number, &h);
 		}
 		Ok(())
 	}
 
+	/// Notify watchers of included transactions about a new best block.
+	///
+	/// Watchers of transactions which blocks haven't been finalized within
+	/// `WatchOptions::finality_timeout_blocks` receive `TransactionStatus::FinalityTimeout`.
+	pub fn on_best_block(&self, block_number: u64) {
+		self.listener.write().best_block(block_number)
+	}
+
+	/// Notify watchers of included transactions about finalization of given block.
+	pub fn on_block_finalized(&self, header_hash: &BlockHash<B>, block_number: u64) {
+		self.listener.write().finalized(header_hash, block_number)
+	}
+
 	/// Removes stale transactions from the pool.
 	///
 	/// Stale transactions are transaction beyond their longevity period.
 	/// Note this function does not remove transactions that are already included in the chain.
 	/// See `prune_tags` if you want this.
 	pub fn clear_stale(&self, at: &BlockId<B::Block>) -> Result<(), B::Error> {
-		let block_number = self.api.block_id_to_number(at)?
+		let block_number = self.api().block_id_to_number(at)?
 			.ok_or_else(|| error::Error::InvalidBlockId(format!("{:?}", at)).into())?
 			.saturated_into::<u64>();
 		let now = time::Instant::now();
@@ -428,11 +1005,28 @@ impl<B: ChainApi> ValidatedPool<B> {
 			}
 			hashes
 		};
+		let spilled_to_remove = match self.future_spill.lock().as_mut() {
+			Some(spill) => spill.remove_expired(block_number),
+			None => Vec::new(),
+		};
 		// removing old transactions
 		self.remove_invalid(&to_remove);
+		self.expired_futures.fetch_add(
+			(futures_to_remove.len() + spilled_to_remove.len()) as u64,
+			Ordering::Relaxed,
+		);
 		self.remove_invalid(&futures_to_remove);
+		if !spilled_to_remove.is_empty() {
+			self.rotator.ban(&now, spilled_to_remove.iter().cloned());
+			let mut listener = self.listener.write();
+			for hash in &spilled_to_remove {
+				listener.invalid(hash, false);
+			}
+		}
 		// clear banned transactions timeouts
 		self.rotator.clear_timeouts(&now);
+		self.listener.write().remove_closed_watchers();
+		self.watcher_slots.lock().retain(|_, slot| Arc::strong_count(slot) > 1);
 
 		Ok(())
 	}
@@ -443,9 +1037,24 @@ impl<B: ChainApi> ValidatedPool<B> {
 		&self.rotator
 	}
 
+	/// Get options reference.
+	pub fn options(&self) -> &Options {
+		&self.options
+	}
+
+	/// Get extension points reference.
+	pub fn extensions(&self) -> &ExtensionsFor<B> {
+		&self.extensions
+	}
+
 	/// Get api reference.
-	pub fn api(&self) -> &B {
-		&self.api
+	pub fn api(&self) -> Arc<B> {
+		self.api.read().clone()
+	}
+
+	/// Replaces the api used for all subsequent validations.
+	pub fn set_api(&self, api: Arc<B>) {
+		*self.api.write() = api;
 	}
 
 	/// Return an event stream of transactions imported to the pool.
@@ -455,8 +1064,56 @@ impl<B: ChainApi> ValidatedPool<B> {
 		stream
 	}
 
+	/// Return an event stream of transactions imported to the pool, buffering up to `capacity` hashes.
+	///
+	/// If the subscriber is too slow, the oldest notifications are dropped (see `BoundedStream::take_lagged`).
+	pub fn import_notification_stream_bounded(&self, capacity: usize) -> BoundedStream<ExHash<B>> {
+		let (sink, stream) = bounded_stream::bounded(capacity);
+		self.bounded_import_notification_sinks.lock().push(sink);
+		stream
+	}
+
+	/// Notifies import notification subscribers about a new ready transaction.
+	fn notify_imported(&self, hash: &ExHash<B>) {
+		self.import_notification_sinks.lock().retain(|sink| sink.unbounded_send(hash.clone()).is_ok());
+		self.bounded_import_notification_sinks.lock().retain(|sink| sink.send(hash.clone()));
+	}
+
+	/// Return an event stream of transactions that got permanently rejected or removed as invalid.
+	pub fn deadletter_stream(&self) -> DeadletterStream<ExHash<B>> {
+		let (sink, stream) = mpsc::unbounded();
+		self.deadletter_sinks.lock().push(sink);
+		stream
+	}
+
+	/// Notifies deadletter subscribers if the transaction got rejected permanently.
+	fn notify_rejected(&self, hash: &ExHash<B>, error: &B::Error) {
+		let reason = RejectionReason::from_error(error);
+		if reason.is_terminal() {
+			self.notify_deadletter(hash, reason);
+		}
+	}
+
+	/// Notifies deadletter subscribers about a permanently rejected transaction.
+	fn notify_deadletter(&self, hash: &ExHash<B>, reason: RejectionReason) {
+		self.deadletter_sinks.lock().retain(|sink| sink.unbounded_send((hash.clone(), reason)).is_ok());
+	}
+
+	/// Return an event stream of status changes of all transactions in the pool.
+	pub fn all_status_stream(&self) -> AllStatusStream<ExHash<B>, BlockHash<B>> {
+		self.listener.write().all_status_stream()
+	}
+
 	/// Invoked when extrinsics are broadcasted.
 	pub fn on_broadcasted(&self, propagated: HashMap<ExHash<B>, Vec<String>>) {
+		// the listener remembers peers of known transactions only, so that
+		// broadcasts of already removed ones don't accumulate
+		let propagated = {
+			let pool = self.pool.read();
+			propagated.into_iter()
+				.filter(|(hash, _)| pool.by_hashes(&[hash.clone()])[0].is_some())
+				.collect::<Vec<_>>()
+		};
 		let mut listener = self.listener.write();
 		for (hash, peers) in propagated.into_iter() {
 			listener.broadcasted(&hash, peers);
@@ -468,39 +1125,219 @@ impl<B: ChainApi> ValidatedPool<B> {
 	/// The transactions passed as an argument will be additionally banned
 	/// to prevent them from entering the pool right away.
 	/// Note this is not the case for the dependent transactions - those may
-	/// still be valid so we want to be able to re-import them.
+	/// still be valid, so they are moved back to the future queue, unless
+	/// `MaintenanceOptions::drop_invalid_dependents` is set, in which case they are removed as well.
+	/// Returns all removed transactions.
 	pub fn remove_invalid(&self, hashes: &[ExHash<B>]) -> Vec<TransactionFor<B>> {
 		// early exit in case there is no invalid transactions.
 		if hashes.is_empty() {
 			return vec![]
 		}
 
-		debug!(target: "txpool", "Removing invalid transactions: {:?}", hashes);
+		// the same hash might be passed multiple times, make sure we process it only once
+		let mut seen = HashSet::with_capacity(hashes.len());
+		let hashes = hashes.iter()
+			.filter(|hash| seen.insert(*hash))
+			.cloned()
+			.collect::<Vec<_>>();
+
+		debug!(target: self.options.log_target, "Removing invalid transactions: {:?}", hashes);
 
 		// temporarily ban invalid transactions
 		self.rotator.ban(&time::Instant::now(), hashes.iter().cloned());
 
-		let invalid = self.pool.write().remove_subtree(hashes);
+		let (invalid, demoted) = {
+			let mut pool = self.pool_mut();
+			let removed = pool.remove_subtree(&hashes);
+			if self.options.maintenance.drop_invalid_dependents {
+				(removed, Vec::new())
+			} else {
+				let (mut invalid, dependents) = removed.into_iter()
+					.partition::<Vec<_>, _>(|tx| seen.contains(&tx.hash));
+				let (demoted, failed) = pool.restore(dependents);
+				invalid.extend(failed);
+				(invalid, demoted)
+			}
+		};
 
-		debug!(target: "txpool", "Removed invalid transactions: {:?}", invalid);
+		debug!(target: self.options.log_target, "Removed invalid transactions: {:?}", invalid);
 
 		let mut listener = self.listener.write();
 		for tx in &invalid {
 			listener.invalid(&tx.hash, true);
 		}
+		for result in &demoted {
+			fire_events(&mut *listener, result);
+		}
+		drop(listener);
+		for tx in &invalid {
+			self.notify_deadletter(&tx.hash, RejectionReason::InvalidValidity);
+		}
 
 		invalid
 	}
 
+	/// Pins a transaction, so that it's never evicted because of the pool limits.
+	///
+	/// Returns `false` if the transaction is not in the pool.
+	pub fn pin(&self, hash: &ExHash<B>) -> bool {
+		self.pool.write().pin(hash)
+	}
+
+	/// Unpins a transaction.
+	///
+	/// Returns `false` if the transaction was not pinned.
+	pub fn unpin(&self, hash: &ExHash<B>) -> bool {
+		self.pool.write().unpin(hash)
+	}
+
+	/// Reserves up to `limit` best ready transactions (see `BasePool::reserve_ready`).
+	pub fn reserve_ready(&self, limit: usize) -> Vec<TransactionFor<B>> {
+		self.pool_mut().reserve_ready(limit)
+	}
+
+	/// Releases reserved transactions.
+	///
+	/// Returns number of transactions that were reserved.
+	pub fn release_reserved(&self, hashes: &[ExHash<B>]) -> usize {
+		self.pool_mut().release_reserved(hashes)
+	}
+
+	/// Moves a future transaction to the ready queue, ignoring its unsatisfied requirements.
+	///
+	/// NOTE this bypasses dependency checks and is meant to be used for testing only.
+	pub fn force_promote(&self, hash: &ExHash<B>) -> Result<(), B::Error> {
+		let imported = self.pool_mut().force_promote(hash)?;
+		self.note_promoted(&imported, true);
+		fire_events(&mut *self.listener.write(), &imported);
+		Ok(())
+	}
+
+	/// Counts future transactions promoted to ready by given import.
+	///
+	/// `from_future` tells whether the imported transaction itself was taken from the future queue.
+	fn note_promoted(&self, imported: &base::Imported<ExHash<B>, ExtrinsicFor<B>>, from_future: bool) {
+		if let base::Imported::Ready { ref promoted, .. } = *imported {
+			let count = promoted.len() + if from_future { 1 } else { 0 };
+			self.promoted_futures.fetch_add(count as u64, Ordering::Relaxed);
+		}
+	}
+
+	/// Returns number of future transactions that became ready or expired so far.
+	pub fn future_promotion_stats(&self) -> FuturePromotionStats {
+		FuturePromotionStats {
+			promoted: self.promoted_futures.load(Ordering::Relaxed),
+			expired: self.expired_futures.load(Ordering::Relaxed),
+		}
+	}
+
 	/// Get an iterator for ready transactions ordered by priority
 	pub fn ready(&self) -> impl Iterator<Item=TransactionFor<B>> {
 		self.pool.read().ready()
 	}
 
+	/// Returns all transactions in the future queue.
+	pub fn futures(&self) -> Vec<TransactionFor<B>> {
+		self.pool.read().futures_shared().cloned().collect()
+	}
+
+	/// Returns hashes of all transactions in the future queue.
+	pub fn future_hashes(&self) -> Vec<ExHash<B>> {
+		self.pool.read().futures().map(|tx| tx.hash.clone()).collect()
+	}
+
+	/// Returns total value committed by transactions providing a tag with given prefix.
+	pub fn committed_value_for(&self, provides_prefix: &[u8]) -> u128 {
+		let extractor = &self.extensions.value_extractor;
+		self.pool.read().all()
+			.filter(|tx| tx.provides.iter().any(|tag| tag.starts_with(provides_prefix)))
+			.fold(0u128, |total, tx| total.saturating_add(extractor.value_of(&*tx)))
+	}
+
+	/// Returns all transactions in the pool together with their required and provided tags.
+	pub fn tag_graph(&self) -> Vec<(ExHash<B>, Vec<Tag>, Vec<Tag>)> {
+		self.pool.read().tag_graph()
+	}
+
+	/// Get an iterator for ready transactions ordered by priority per encoded byte
+	pub fn ready_by_fee_density(&self) -> impl Iterator<Item=TransactionFor<B>> {
+		self.pool.read().ready_by_fee_density()
+	}
+
+	/// Get ready transactions shuffled deterministically by given seed
+	pub fn ready_shuffled(&self, seed: [u8; 32]) -> Vec<TransactionFor<B>> {
+		self.pool.read().ready_shuffled(seed)
+	}
+
+	/// Get an iterator for propagable ready transactions ordered by priority
+	pub fn ready_propagable(&self) -> impl Iterator<Item=TransactionFor<B>> {
+		self.pool.read().ready_propagable()
+	}
+
+	/// Get an iterator for the best ready transactions with total weight up to `max`
+	pub fn ready_within_weight(&self, max: Weight) -> impl Iterator<Item=TransactionFor<B>> {
+		self.pool.read().ready_within_weight(max)
+	}
+
+	/// Returns the best ready transactions within given size and weight budget.
+	pub fn next_block_candidates(&self, max_bytes: usize, max_weight: Weight) -> Vec<TransactionFor<B>> {
+		self.pool.read().next_block_candidates(max_bytes, max_weight)
+	}
+
+	/// Makes given transactions a bundle (see `BasePool::set_bundle`).
+	pub fn set_bundle(&self, hashes: &[ExHash<B>], id: base::BundleId) {
+		self.pool_mut().set_bundle(hashes, id)
+	}
+
+	/// Returns up to `limit` transactions that spent the longest time in the pool.
+	pub fn oldest_pending(&self, limit: usize) -> Vec<TransactionFor<B>> {
+		self.pool.read().oldest_pending(limit)
+	}
+
 	/// Returns pool status.
 	pub fn status(&self) -> PoolStatus {
 		self.pool.read().status()
 	}
+
+	/// Returns aggregate statistics of the future queue.
+	pub fn future_status(&self) -> FutureStatus {
+		self.pool.read().future_status()
+	}
+
+	/// Returns number of transactions per source.
+	pub fn count_by_source(&self) -> SourceCounts {
+		self.pool.read().count_by_source()
+	}
+
+	/// Returns number of distinct senders of transactions in the pool.
+	pub fn distinct_senders(&self) -> usize {
+		self.pool.read().distinct_senders()
+	}
+
+	/// Returns sender tags of transactions in the pool.
+	pub fn sender_tags(&self) -> Vec<Tag> {
+		self.pool.read().sender_tags()
+	}
+
+	/// Returns true if any of the queues is filled beyond `NEAR_CAPACITY` share of its limit.
+	pub fn is_near_capacity(&self) -> bool {
+		let status = self.status();
+		self.options.ready.share(NEAR_CAPACITY).is_exceeded(status.ready, status.ready_bytes)
+			|| self.options.future.share(NEAR_CAPACITY).is_exceeded(status.future, status.future_bytes)
+	}
+
+	/// Returns number of active transaction watchers.
+	pub fn watchers_count(&self) -> usize {
+		self.listener.read().watchers_count()
+	}
+
+	/// Returns approximate memory used by the pool.
+	///
+	/// Sum of memory footprints of all transactions in both queues and of all watchers.
+	pub fn memory_usage(&self) -> usize {
+		let status = self.status();
+		status.ready_bytes + status.future_bytes + self.listener.read().memory_usage()
+	}
 }
 
 fn fire_events<H, H2, Ex>(
@@ -16,8 +16,14 @@
 
 //! Extrinsics status updates.
 
+use std::{
+	pin::Pin,
+	sync::Arc,
+	task::{Context, Poll},
+};
+
 use futures::{
-	Stream,
+	Stream, StreamExt,
 	channel::mpsc,
 };
 use sp_transaction_pool::TransactionStatus;
@@ -29,6 +35,8 @@ use sp_transaction_pool::TransactionStatus;
 pub struct Watcher<H, H2> {
 	receiver: mpsc::UnboundedReceiver<TransactionStatus<H, H2>>,
 	hash: H,
+	/// Watcher slot of the source that is occupied as long as the watcher is alive.
+	slot: Option<Arc<()>>,
 }
 
 impl<H, H2> Watcher<H, H2> {
@@ -37,11 +45,35 @@ impl<H, H2> Watcher<H, H2> {
 		&self.hash
 	}
 
+	/// Occupies given watcher slot until the watcher (or its stream) is dropped.
+	pub(crate) fn with_slot(mut self, slot: Option<Arc<()>>) -> Self {
+		self.slot = slot;
+		self
+	}
+
 	/// Pipe the notifications to given sink.
 	///
 	/// Make sure to drive the future to completion.
 	pub fn into_stream(self) -> impl Stream<Item=TransactionStatus<H, H2>> {
-		self.receiver
+		WatcherStream {
+			receiver: self.receiver,
+			_slot: self.slot,
+		}
+	}
+}
+
+/// Stream of the status updates returned by `Watcher::into_stream`.
+struct WatcherStream<H, H2> {
+	receiver: mpsc::UnboundedReceiver<TransactionStatus<H, H2>>,
+	/// Watcher slot of the source that is occupied as long as the stream is alive.
+	_slot: Option<Arc<()>>,
+}
+
+impl<H, H2> Stream for WatcherStream<H, H2> {
+	type Item = TransactionStatus<H, H2>;
+
+	fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+		self.receiver.poll_next_unpin(cx)
 	}
 }
 
@@ -49,18 +81,53 @@ impl<H, H2> Watcher<H, H2> {
 #[derive(Debug)]
 pub struct Sender<H, H2> {
 	receivers: Vec<mpsc::UnboundedSender<TransactionStatus<H, H2>>>,
+	/// Receivers interested in queue position updates.
+	positioned: Vec<mpsc::UnboundedSender<TransactionStatus<H, H2>>>,
+	/// Receivers interested only in the terminal status.
+	terminal: Vec<mpsc::UnboundedSender<TransactionStatus<H, H2>>>,
 	finalized: bool,
+	/// Number of status events sent so far.
+	events: usize,
+	/// Maximal number of status events, including the final one.
+	max_events: Option<usize>,
+	/// Whether watching continues after inclusion until the block is finalized.
+	track_finality: bool,
 }
 
 impl<H, H2> Default for Sender<H, H2> {
 	fn default() -> Self {
 		Sender {
 			receivers: Default::default(),
+			positioned: Default::default(),
+			terminal: Default::default(),
 			finalized: false,
+			events: 0,
+			max_events: None,
+			track_finality: false,
 		}
 	}
 }
 
+impl<H, H2> Sender<H, H2> {
+	/// Creates a sender that emits at most `max_events` status events.
+	///
+	/// Once the limit is reached, `TransactionStatus::Invalid` is sent as the last
+	/// event instead and the watchers are closed. Queue position updates are not counted.
+	pub fn with_max_events(max_events: Option<usize>) -> Self {
+		Sender {
+			max_events,
+			..Default::default()
+		}
+	}
+
+	/// Keeps the watchers open after inclusion, until the block is finalized
+	/// (see `block_finalized` and `finality_timeout`).
+	pub fn tracking_finality(mut self, track_finality: bool) -> Self {
+		self.track_finality = track_finality;
+		self
+	}
+}
+
 impl<H: Clone, H2: Clone> Sender<H, H2> {
 	/// Add a new watcher to this sender object.
 	pub fn new_watcher(&mut self, hash: H) -> Watcher<H, H2> {
@@ -69,9 +136,44 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 		Watcher {
 			receiver,
 			hash,
+			slot: None,
 		}
 	}
 
+	/// Add a new watcher to this sender object, that also receives queue position updates.
+	pub fn new_watcher_with_position(&mut self, hash: H) -> Watcher<H, H2> {
+		let (tx, receiver) = mpsc::unbounded();
+		self.positioned.push(tx.clone());
+		self.receivers.push(tx);
+		Watcher {
+			receiver,
+			hash,
+			slot: None,
+		}
+	}
+
+	/// Add a new watcher to this sender object, that receives only the terminal status.
+	pub fn new_watcher_terminal_only(&mut self, hash: H) -> Watcher<H, H2> {
+		let (tx, receiver) = mpsc::unbounded();
+		self.terminal.push(tx);
+		Watcher {
+			receiver,
+			hash,
+			slot: None,
+		}
+	}
+
+	/// Returns true if any of the receivers is interested in queue position updates.
+	pub fn wants_position(&self) -> bool {
+		!self.positioned.is_empty()
+	}
+
+	/// Transaction position in the ready queue has been computed.
+	pub fn queued(&mut self, position: usize, total: usize) {
+		let status = TransactionStatus::Queued { position, total };
+		self.positioned.retain(|sender| sender.unbounded_send(status.clone()).is_ok())
+	}
+
 	/// Transaction became ready.
 	pub fn ready(&mut self) {
 		self.send(TransactionStatus::Ready)
@@ -82,6 +184,11 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 		self.send(TransactionStatus::Future)
 	}
 
+	/// Future transaction was moved to the disk-backed spill.
+	pub fn spilled(&mut self) {
+		self.send(TransactionStatus::Spilled)
+	}
+
 	/// Some state change (perhaps another extrinsic was included) rendered this extrinsic invalid.
 	pub fn usurped(&mut self, hash: H) {
 		self.send(TransactionStatus::Usurped(hash));
@@ -89,8 +196,24 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 	}
 
 	/// Extrinsic has been included in block with given hash.
+	///
+	/// Inclusion is the last event. Unless finality is tracked, the stream ends here
+	/// instead of waiting for the block to be finalized.
 	pub fn in_block(&mut self, hash: H2) {
 		self.send(TransactionStatus::InBlock(hash));
+		self.finalized = !self.track_finality;
+	}
+
+	/// The block the extrinsic has been included in has been finalized.
+	///
+	/// There are no more notifications, the stream just ends.
+	pub fn block_finalized(&mut self) {
+		self.finalized = true;
+	}
+
+	/// The block the extrinsic has been included in hasn't been finalized in time.
+	pub fn finality_timeout(&mut self, hash: H2) {
+		self.send(TransactionStatus::FinalityTimeout(hash));
 		self.finalized = true;
 	}
 
@@ -114,10 +237,34 @@ impl<H: Clone, H2: Clone> Sender<H, H2> {
 
 	/// Returns true if the are no more listeners for this extrinsic or it was finalized.
 	pub fn is_done(&self) -> bool {
-		self.finalized || self.receivers.is_empty()
+		self.finalized || (self.receivers.is_empty() && self.terminal.is_empty())
+	}
+
+	/// Forgets subscribers which dropped their receivers.
+	pub fn remove_closed(&mut self) {
+		self.receivers.retain(|sender| !sender.is_closed());
+		self.positioned.retain(|sender| !sender.is_closed());
+		self.terminal.retain(|sender| !sender.is_closed());
+	}
+
+	/// Returns number of subscribers of this sender.
+	pub fn receivers_count(&self) -> usize {
+		self.receivers.len() + self.terminal.len()
 	}
 
 	fn send(&mut self, status: TransactionStatus<H, H2>) {
+		let is_final = status.is_terminal();
+		let exhausted = self.max_events.map_or(false, |max| self.events + 1 >= max);
+		let status = if exhausted && !is_final {
+			self.finalized = true;
+			TransactionStatus::Invalid
+		} else {
+			status
+		};
+		self.events += 1;
+		if status.is_terminal() {
+			self.terminal.retain(|sender| sender.unbounded_send(status.clone()).is_ok())
+		}
 		self.receivers.retain(|sender| sender.unbounded_send(status.clone()).is_ok())
 	}
 }
@@ -41,6 +41,7 @@ use crate::error::{self, Error};
 pub struct FullChainApi<Client, Block> {
 	client: Arc<Client>,
 	pool: ThreadPool,
+	log_target: &'static str,
 	_marker: PhantomData<Block>,
 }
 
@@ -57,9 +58,16 @@ impl<Client, Block> FullChainApi<Client, Block> where
 				.name_prefix("txpool-verifier")
 				.create()
 				.expect("Failed to spawn verifier threads, that are critical for node operation."),
+			log_target: sc_transaction_graph::DEFAULT_LOG_TARGET,
 			_marker: Default::default()
 		}
 	}
+
+	/// Use given log target, it should match `Options::log_target` of the pool.
+	pub fn with_log_target(mut self, log_target: &'static str) -> Self {
+		self.log_target = log_target;
+		self
+	}
 }
 
 impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Block> where
@@ -86,12 +94,13 @@ impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Bloc
 		let (tx, rx) = oneshot::channel();
 		let client = self.client.clone();
 		let at = at.clone();
+		let log_target = self.log_target;
 
 		self.pool.spawn_ok(async move {
 			let res = client.runtime_api().validate_transaction(&at, uxt)
 				.map_err(|e| Error::RuntimeApi(format!("{:?}", e)));
 			if let Err(e) = tx.send(res) {
-				log::warn!("Unable to send a validate transaction result: {:?}", e);
+				log::warn!(target: log_target, "Unable to send a validate transaction result: {:?}", e);
 			}
 		});
 
@@ -128,6 +137,7 @@ impl<Client, Block> sc_transaction_graph::ChainApi for FullChainApi<Client, Bloc
 pub struct LightChainApi<Client, F, Block> {
 	client: Arc<Client>,
 	fetcher: Arc<F>,
+	log_target: &'static str,
 	_phantom: PhantomData<Block>,
 }
 
@@ -141,9 +151,16 @@ impl<Client, F, Block> LightChainApi<Client, F, Block> where
 		LightChainApi {
 			client,
 			fetcher,
+			log_target: sc_transaction_graph::DEFAULT_LOG_TARGET,
 			_phantom: Default::default(),
 		}
 	}
+
+	/// Use given log target, it should match `Options::log_target` of the pool.
+	pub fn with_log_target(mut self, log_target: &'static str) -> Self {
+		self.log_target = log_target;
+		self
+	}
 }
 
 impl<Client, F, Block> sc_transaction_graph::ChainApi for LightChainApi<Client, F, Block> where
@@ -211,12 +228,13 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for LightChainApi<Client,
 		let header = match header {
 			Ok(header) => header,
 			Err(err) => {
-				log::warn!(target: "txpool", "Failed to query header: {:?}", err);
+				log::warn!(target: self.log_target, "Failed to query header: {:?}", err);
 				return Box::pin(ready(Ok(None)));
 			}
 		};
 
 		let fetcher = self.fetcher.clone();
+		let log_target = self.log_target;
 		async move {
 			let transactions = fetcher.remote_body({
 					RemoteBodyRequest {
@@ -226,7 +244,7 @@ impl<Client, F, Block> sc_transaction_graph::ChainApi for LightChainApi<Client,
 				})
 				.await
 				.unwrap_or_else(|e| {
-					log::warn!(target: "txpool", "Failed to fetch block body: {:?}", e);
+					log::warn!(target: log_target, "Failed to fetch block body: {:?}", e);
 					Vec::new()
 				});
 
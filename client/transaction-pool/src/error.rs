@@ -34,6 +34,9 @@ pub enum Error {
 	/// Error while calling the runtime api.
 	#[from(ignore)]
 	RuntimeApi(String),
+	/// The snapshot can't be decoded.
+	#[display(fmt="Malformed snapshot")]
+	MalformedSnapshot,
 }
 
 impl std::error::Error for Error {
@@ -43,6 +46,7 @@ impl std::error::Error for Error {
 			Error::Blockchain(ref err) => Some(err),
 			Error::BlockIdConversion(_) => None,
 			Error::RuntimeApi(_) => None,
+			Error::MalformedSnapshot => None,
 		}
 	}
 }
@@ -54,4 +58,11 @@ impl sp_transaction_pool::error::IntoPoolError for Error {
 			e => Err(e),
 		}
 	}
+
+	fn as_pool_error(&self) -> Option<&TxPoolError> {
+		match self {
+			Error::Pool(e) => Some(e),
+			_ => None,
+		}
+	}
 }
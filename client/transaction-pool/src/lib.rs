@@ -28,94 +28,1137 @@ pub mod testing;
 pub use sc_transaction_graph as txpool;
 pub use crate::api::{FullChainApi, LightChainApi};
 
-use std::{collections::HashMap, sync::Arc, pin::Pin, time::Instant};
-use futures::{Future, FutureExt, future::ready};
+use std::{
+	collections::{HashMap, HashSet, VecDeque, hash_map::RandomState},
+	hash::{BuildHasher, Hasher},
+	sync::{Arc, atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering}},
+	pin::Pin,
+	time::{Duration, Instant},
+};
+use futures::{Future, FutureExt, future::ready, channel::mpsc};
 use parking_lot::Mutex;
 
 use sp_runtime::{
 	generic::BlockId,
-	traits::{Block as BlockT, NumberFor, SimpleArithmetic, Extrinsic},
+	traits::{Block as BlockT, NumberFor, SimpleArithmetic, Extrinsic, Saturating},
+	transaction_validity::{TransactionValidity, TransactionTag as Tag},
 };
 use sp_transaction_pool::{
-	TransactionPool, PoolStatus, ImportNotificationStream,
+	TransactionPool, PoolStatus, FutureStatus, ImportNotificationStream,
 	TxHash, TransactionFor, TransactionStatusStreamFor, BlockHash,
-	MaintainedTransactionPool, PoolFuture,
+	MaintainedTransactionPool, PoolFuture, ReadyDiff, ReadyDiffStream, TransactionSource,
+	EmptinessStream, TransactionWeight, AllStatusStream, SourceCounts, PressureLevel, PressureStream,
 };
 
 type PoolResult<T> = PoolFuture<T, error::Error>;
 
+/// Maximal number of maintenance cycles the pruning of a block with unavailable body is retried.
+const MAX_PRUNE_RETRIES: usize = 16;
+
+/// Period after the last maintenance during which the pool is considered to be maintained recently.
+const MAINTENANCE_HEALTH_PERIOD: Duration = Duration::from_secs(120);
+
 /// Basic implementation of transaction pool that can be customized by providing PoolApi.
+///
+/// The pool is cheap to clone, all clones share the same underlying pool and observe
+/// the same transactions, subscriptions and maintenance state.
 pub struct BasicPool<PoolApi, Block>
 	where
 		Block: BlockT,
 		PoolApi: sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash>,
 {
 	pool: Arc<sc_transaction_graph::Pool<PoolApi>>,
-	api: Arc<PoolApi>,
 	revalidation_strategy: Arc<Mutex<RevalidationStrategy<NumberFor<Block>>>>,
+	paused_maintenance: Arc<Mutex<Option<PausedMaintenance<Block>>>>,
+	last_revalidation_outcome: Arc<Mutex<Option<sc_transaction_graph::RevalidationOutcome>>>,
+	pending_prune: Arc<Mutex<Vec<(BlockId<Block>, usize)>>>,
+	notifiers: Arc<Mutex<ChangeNotifiers<Block::Hash>>>,
+	last_maintained: Arc<Mutex<Option<Instant>>>,
+	revalidation_failures: Arc<AtomicU32>,
+	/// Retracted transactions that failed to be resubmitted, with the last block they are retried at.
+	reorg_pending: Arc<Mutex<Vec<(NumberFor<Block>, sc_transaction_graph::ExtrinsicFor<PoolApi>)>>>,
+	last_maintain_timings: Arc<Mutex<MaintainTimings>>,
+	/// Snapshots of the pool at recently maintained blocks (see `MaintenanceOptions::history_depth`).
+	history: Arc<Mutex<VecDeque<(Block::Hash, PoolSnapshot<sc_transaction_graph::ExHash<PoolApi>>)>>>,
+	/// Held by the running maintenance, so that only one runs at a time.
+	maintenance_lock: Arc<futures::lock::Mutex<()>>,
+	/// Number of `maintain` calls so far, used to detect superseded ones.
+	maintenance_calls: Arc<AtomicU64>,
+	/// Blocks retracted according to the superseded maintenance calls.
+	superseded_retracted: Arc<Mutex<Vec<Block::Hash>>>,
+	/// Whether transactions from retracted blocks are resubmitted (see `set_resubmission_enabled`).
+	resubmission_enabled: Arc<AtomicBool>,
+	/// Block at which future transactions were first seen in the future queue during maintenance.
+	future_first_seen: Arc<Mutex<HashMap<sc_transaction_graph::ExHash<PoolApi>, NumberFor<Block>>>>,
+	/// Number of future transactions stuck for `MaintenanceOptions::future_stuck_threshold` blocks.
+	stuck_futures: Arc<AtomicUsize>,
+	/// Hooks invoked after each completed maintenance.
+	maintain_hooks: Arc<Mutex<Vec<Arc<dyn OnMaintainComplete<Block>>>>>,
+	/// Destination of the pool metrics (see `set_metrics_sink`), metrics are not collected if unset.
+	metrics: Arc<Mutex<Option<Arc<dyn MetricsSink>>>>,
+	/// Predicate of local transactions and whether only those are resubmitted after reorgs.
+	local_key_predicate: Arc<Mutex<Option<(Arc<dyn LocalKeyPredicate<Block>>, bool)>>>,
+}
+
+impl<PoolApi, Block> Clone for BasicPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash>,
+{
+	fn clone(&self) -> Self {
+		BasicPool {
+			pool: self.pool.clone(),
+			revalidation_strategy: self.revalidation_strategy.clone(),
+			paused_maintenance: self.paused_maintenance.clone(),
+			last_revalidation_outcome: self.last_revalidation_outcome.clone(),
+			pending_prune: self.pending_prune.clone(),
+			notifiers: self.notifiers.clone(),
+			last_maintained: self.last_maintained.clone(),
+			revalidation_failures: self.revalidation_failures.clone(),
+			reorg_pending: self.reorg_pending.clone(),
+			last_maintain_timings: self.last_maintain_timings.clone(),
+			history: self.history.clone(),
+			maintenance_lock: self.maintenance_lock.clone(),
+			maintenance_calls: self.maintenance_calls.clone(),
+			superseded_retracted: self.superseded_retracted.clone(),
+			resubmission_enabled: self.resubmission_enabled.clone(),
+			future_first_seen: self.future_first_seen.clone(),
+			stuck_futures: self.stuck_futures.clone(),
+			maintain_hooks: self.maintain_hooks.clone(),
+			metrics: self.metrics.clone(),
+			local_key_predicate: self.local_key_predicate.clone(),
+		}
+	}
+}
+
+/// Tracks changes of the ready set for `ready_diff_stream` subscribers.
+struct ReadyDiffTracker<Hash> {
+	/// Ready set at the time of the previous notification.
+	last_ready: HashSet<Hash>,
+	sinks: Vec<mpsc::UnboundedSender<ReadyDiff<Hash>>>,
+}
+
+impl<Hash> Default for ReadyDiffTracker<Hash> {
+	fn default() -> Self {
+		ReadyDiffTracker {
+			last_ready: Default::default(),
+			sinks: Default::default(),
+		}
+	}
+}
+
+impl<Hash: std::hash::Hash + Eq + Clone> ReadyDiffTracker<Hash> {
+	/// Adds a new subscriber.
+	///
+	/// The ready set is not tracked without subscribers, so the first one resets the baseline.
+	fn subscribe(&mut self, ready: impl Iterator<Item=Hash>) -> ReadyDiffStream<Hash> {
+		if self.sinks.is_empty() {
+			self.last_ready = ready.collect();
+		}
+		let (sink, stream) = mpsc::unbounded();
+		self.sinks.push(sink);
+		stream
+	}
+
+	/// Compares current ready set with the previous one and notifies subscribers about changes.
+	fn notify(&mut self, ready: impl Iterator<Item=Hash>) {
+		let current = ready.collect::<HashSet<_>>();
+		let diff = ReadyDiff {
+			added: current.difference(&self.last_ready).cloned().collect(),
+			removed: self.last_ready.difference(&current).cloned().collect(),
+		};
+		self.last_ready = current;
+
+		if !diff.added.is_empty() || !diff.removed.is_empty() {
+			self.sinks.retain(|sink| sink.unbounded_send(diff.clone()).is_ok());
+		}
+	}
+}
+
+/// Tracks transitions between empty and non-empty pool for `emptiness_stream` subscribers.
+#[derive(Default)]
+struct EmptinessTracker {
+	/// Emptiness at the time of the previous notification.
+	was_empty: bool,
+	sinks: Vec<mpsc::UnboundedSender<bool>>,
+}
+
+impl EmptinessTracker {
+	/// Adds a new subscriber.
+	///
+	/// The state is not tracked without subscribers, so the first one resets the baseline.
+	fn subscribe(&mut self, is_empty: bool) -> mpsc::UnboundedReceiver<bool> {
+		if self.sinks.is_empty() {
+			self.was_empty = is_empty;
+		}
+		let (sink, stream) = mpsc::unbounded();
+		self.sinks.push(sink);
+		stream
+	}
+
+	/// Notifies subscribers if the pool emptiness changed since the previous notification.
+	fn notify(&mut self, is_empty: bool) {
+		if self.was_empty != is_empty {
+			self.was_empty = is_empty;
+			self.sinks.retain(|sink| sink.unbounded_send(is_empty).is_ok());
+		}
+	}
+}
+
+/// Tracks transitions between pool pressure levels for `pressure_stream` subscribers.
+struct PressureTracker {
+	/// Pressure level at the time of the previous notification.
+	last_level: PressureLevel,
+	sinks: Vec<mpsc::UnboundedSender<PressureLevel>>,
+}
+
+impl Default for PressureTracker {
+	fn default() -> Self {
+		PressureTracker {
+			last_level: PressureLevel::Low,
+			sinks: Default::default(),
+		}
+	}
 }
 
-/// Type of revalidation.
-pub enum RevalidationType {
-	/// Light revalidation type.
+impl PressureTracker {
+	/// Adds a new subscriber.
+	///
+	/// The level is not tracked without subscribers, so the first one resets the baseline.
+	fn subscribe(&mut self, level: PressureLevel) -> PressureStream {
+		if self.sinks.is_empty() {
+			self.last_level = level;
+		}
+		let (sink, stream) = mpsc::unbounded();
+		self.sinks.push(sink);
+		stream
+	}
+
+	/// Notifies subscribers if the pressure level changed since the previous notification.
+	fn notify(&mut self, level: PressureLevel) {
+		if self.last_level != level {
+			self.last_level = level;
+			self.sinks.retain(|sink| sink.unbounded_send(level).is_ok());
+		}
+	}
+}
+
+/// Subscribers to the pool changes.
+struct ChangeNotifiers<Hash> {
+	ready_diff: ReadyDiffTracker<Hash>,
+	emptiness: EmptinessTracker,
+	pressure: PressureTracker,
+}
+
+impl<Hash> Default for ChangeNotifiers<Hash> {
+	fn default() -> Self {
+		ChangeNotifiers {
+			ready_diff: Default::default(),
+			emptiness: Default::default(),
+			pressure: Default::default(),
+		}
+	}
+}
+
+/// Reports submitted transactions to the metrics sink, if there is one.
+fn note_submissions(metrics: Option<&Arc<dyn MetricsSink>>, submitted: usize, rejected: usize) {
+	if let Some(metrics) = metrics {
+		metrics.inc_counter(metric_names::SUBMITTED, submitted as u64);
+		if rejected > 0 {
+			metrics.inc_counter(metric_names::REJECTED, rejected as u64);
+		}
+	}
+}
+
+/// Notifies change subscribers (if any) about the current state of the pool.
+fn notify_changes<PoolApi: sc_transaction_graph::ChainApi>(
+	pool: &sc_transaction_graph::Pool<PoolApi>,
+	notifiers: &Mutex<ChangeNotifiers<sc_transaction_graph::ExHash<PoolApi>>>,
+) {
+	let mut notifiers = notifiers.lock();
+	if !notifiers.emptiness.sinks.is_empty() {
+		notifiers.emptiness.notify(pool.status().is_empty());
+	}
+	if !notifiers.pressure.sinks.is_empty() {
+		notifiers.pressure.notify(pool.pressure_level());
+	}
+	if !notifiers.ready_diff.sinks.is_empty() {
+		notifiers.ready_diff.notify(pool.ready().map(|tx| tx.hash.clone()));
+	}
+}
+
+/// Block body requested during maintenance.
+enum BlockBody<Ex> {
+	/// The body is known (it might be empty).
+	Known(Vec<Ex>),
+	/// The body is not available, pruning needs to be retried later.
+	Unavailable,
+}
+
+async fn fetch_block_body<PoolApi: sc_transaction_graph::ChainApi>(
+	api: &PoolApi,
+	id: &BlockId<PoolApi::Block>,
+	log_target: &str,
+) -> BlockBody<sc_transaction_graph::ExtrinsicFor<PoolApi>> {
+	match api.block_body(id).await {
+		Ok(Some(body)) => BlockBody::Known(body),
+		Ok(None) => {
+			log::debug!(target: log_target, "[{:?}] Block body is not available", id);
+			BlockBody::Unavailable
+		},
+		Err(e) => {
+			log::warn!(target: log_target, "Prune known transactions: error request {:?}!", e);
+			BlockBody::Unavailable
+		},
+	}
+}
+
+/// Maintenance requests received while the maintenance is paused.
+struct PausedMaintenance<Block: BlockT> {
+	/// The latest block the maintenance was requested for.
+	latest: Option<BlockId<Block>>,
+	/// All blocks retracted since the maintenance was paused.
+	retracted: Vec<Block::Hash>,
+}
+
+/// Summary of the pool health.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolHealth {
+	/// Whether the pool was maintained within `MAINTENANCE_HEALTH_PERIOD`.
+	pub maintained_recently: bool,
+	/// Number of failed revalidations since the pool was created.
+	pub revalidation_failures: u32,
+	/// Whether any of the queues is close to its limit.
+	pub near_capacity: bool,
+	/// Number of active transaction watchers.
+	pub watchers: usize,
+}
+
+/// Durations of the phases of the most recent maintenance.
+///
+/// Phases that were skipped during the maintenance have zero duration.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintainTimings {
+	/// Duration of the whole maintenance.
+	pub total: Duration,
+	/// Pruning of transactions included in the block.
+	pub prune: Duration,
+	/// Resubmission of transactions from retracted blocks.
+	pub resubmit: Duration,
+	/// Revalidation of ready transactions.
+	pub revalidate: Duration,
+}
+
+/// Summary of a completed maintenance, passed to `OnMaintainComplete` hooks.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MaintenanceReport {
+	/// Durations of the maintenance phases.
+	pub timings: MaintainTimings,
+	/// Outcome of the revalidation, if it ran during the maintenance and succeeded.
+	pub revalidation: Option<sc_transaction_graph::RevalidationOutcome>,
+	/// Number of ready transactions after the maintenance.
+	pub ready: usize,
+	/// Number of future transactions after the maintenance.
+	pub future: usize,
+}
+
+/// Callback invoked after each completed maintenance of a block.
+///
+/// Maintenance calls that got superseded (see `MaintenanceOptions::supersede_maintenance`) or postponed
+/// by `pause_maintenance` don't complete on their own and are not reported.
+pub trait OnMaintainComplete<Block: BlockT>: Send + Sync {
+	/// Called with the maintained block and the summary of the maintenance.
+	fn on_complete(&self, at: &BlockId<Block>, report: &MaintenanceReport);
+}
+
+/// Tells transactions originating from the node operator apart from the others.
+///
+/// Used during resubmission of transactions from retracted blocks (see
+/// `BasicPool::set_local_key_predicate`).
+pub trait LocalKeyPredicate<Block: BlockT>: Send + Sync {
+	/// Returns `true` if given transaction was signed by one of the local keys.
+	fn is_local(&self, xt: &Block::Extrinsic) -> bool;
+}
+
+/// Names of the metrics emitted by the pool (see `MetricsSink`).
+pub mod metric_names {
+	/// Counter of submitted transactions.
+	pub const SUBMITTED: &str = "txpool_submitted";
+	/// Counter of submitted transactions that were rejected.
+	pub const REJECTED: &str = "txpool_rejected";
+	/// Counter of completed maintenance runs.
+	pub const MAINTAINED: &str = "txpool_maintained";
+	/// Gauge of ready transactions after maintenance.
+	pub const READY: &str = "txpool_ready";
+	/// Gauge of future transactions after maintenance.
+	pub const FUTURE: &str = "txpool_future";
+	/// Histogram of maintenance durations, in seconds.
+	pub const MAINTAIN_DURATION: &str = "txpool_maintain_duration";
+}
+
+/// Destination of the pool metrics, allowing to plug in any metrics backend.
+///
+/// See `metric_names` for the metrics being emitted.
+pub trait MetricsSink: Send + Sync {
+	/// Increases counter of given name by `value`.
+	fn inc_counter(&self, name: &'static str, value: u64);
+	/// Sets gauge of given name to `value`.
+	fn set_gauge(&self, name: &'static str, value: u64);
+	/// Records an observation of `value` in histogram of given name.
+	fn observe_histogram(&self, name: &'static str, value: f64);
+}
+
+/// Metrics sink discarding all metrics.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NoopMetricsSink;
+
+impl MetricsSink for NoopMetricsSink {
+	fn inc_counter(&self, _name: &'static str, _value: u64) {}
+	fn set_gauge(&self, _name: &'static str, _value: u64) {}
+	fn observe_histogram(&self, _name: &'static str, _value: f64) {}
+}
+
+/// Content of the pool right after maintenance of some block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolSnapshot<Hash> {
+	/// Ready transactions in the order they would be included.
+	pub ready: Vec<Hash>,
+	/// Future transactions in no particular order.
+	pub future: Vec<Hash>,
+}
+
+/// Transaction of an exported snapshot (see `BasicPool::export_snapshot`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotTransaction {
+	/// Source the transaction was submitted from.
+	pub source: TransactionSource,
+	/// SCALE-encoded extrinsic.
+	pub encoded: Vec<u8>,
+}
+
+/// Transactions of the pool exported to be imported by another pool.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ExportedSnapshot {
+	/// Ready transactions in the order they would be included.
+	pub ready: Vec<SnapshotTransaction>,
+	/// Future transactions in no particular order.
+	pub future: Vec<SnapshotTransaction>,
+}
+
+/// Serialization format of exported snapshots.
+pub trait SnapshotCodec {
+	/// Encodes the snapshot.
+	fn encode(&self, snapshot: &ExportedSnapshot) -> Vec<u8>;
+	/// Decodes the snapshot, returns `None` if the data is malformed.
+	fn decode(&self, data: &[u8]) -> Option<ExportedSnapshot>;
+}
+
+/// Compact SCALE encoding of exported snapshots.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ScaleSnapshotCodec;
+
+impl ScaleSnapshotCodec {
+	fn encode_source(source: TransactionSource) -> u8 {
+		match source {
+			TransactionSource::InBlock => 0,
+			TransactionSource::Local => 1,
+			TransactionSource::External => 2,
+		}
+	}
+
+	fn decode_source(source: u8) -> Option<TransactionSource> {
+		match source {
+			0 => Some(TransactionSource::InBlock),
+			1 => Some(TransactionSource::Local),
+			2 => Some(TransactionSource::External),
+			_ => None,
+		}
+	}
+}
+
+impl SnapshotCodec for ScaleSnapshotCodec {
+	fn encode(&self, snapshot: &ExportedSnapshot) -> Vec<u8> {
+		let encode_all = |txs: &[SnapshotTransaction]| txs.iter()
+			.map(|tx| (Self::encode_source(tx.source), &tx.encoded))
+			.collect::<Vec<_>>();
+		codec::Encode::encode(&(encode_all(&snapshot.ready), encode_all(&snapshot.future)))
+	}
+
+	fn decode(&self, mut data: &[u8]) -> Option<ExportedSnapshot> {
+		let (ready, future): (Vec<(u8, Vec<u8>)>, Vec<(u8, Vec<u8>)>) = codec::Decode::decode(&mut data).ok()?;
+		if !data.is_empty() {
+			return None;
+		}
+		let decode_all = |txs: Vec<(u8, Vec<u8>)>| txs.into_iter()
+			.map(|(source, encoded)| Some(SnapshotTransaction { source: Self::decode_source(source)?, encoded }))
+			.collect::<Option<Vec<_>>>();
+		Some(ExportedSnapshot { ready: decode_all(ready)?, future: decode_all(future)? })
+	}
+}
+
+/// Type of revalidation.
+pub enum RevalidationType {
+	/// Light revalidation type.
+	///
+	/// During maintenance, transaction pool makes periodic revalidation
+	/// of all transactions depending on number of blocks or time passed.
+	/// Also this kind of revalidation does not resubmit transactions from
+	/// retracted blocks, since it is too expensive.
+	Light,
+
+	/// Full revalidation type.
+	///
+	/// During maintenance, transaction pool revalidates some fixed amount of
+	/// transactions from the pool of valid transactions.
+	Full,
+}
+
+/// Read-only snapshot of the revalidation strategy state.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RevalidationStateView<N> {
+	/// All transactions are revalidated during every maintenance (`RevalidationType::Full`).
+	Always,
+	/// Light revalidation is not scheduled yet.
+	NotScheduled,
+	/// Light revalidation is scheduled at given time or block, whichever comes first.
+	Scheduled {
+		/// Time of the revalidation.
+		at_time: Option<Instant>,
+		/// Block of the revalidation.
+		at_block: Option<N>,
+	},
+	/// Light revalidation is in progress.
+	InProgress,
+}
+
+impl<PoolApi, Block> BasicPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash>,
+{
+	/// Create new basic transaction pool with provided api.
+	pub fn new(
+		options: sc_transaction_graph::Options,
+		pool_api: PoolApi,
+	) -> Self {
+		Self::with_revalidation_type(options, pool_api, RevalidationType::Full)
+	}
+
+	/// Create new basic transaction pool with provided api and custom
+	/// extension points.
+	pub fn with_extensions(
+		options: sc_transaction_graph::Options,
+		pool_api: PoolApi,
+		extensions: sc_transaction_graph::ExtensionsFor<PoolApi>,
+	) -> Self {
+		Self::with_revalidation_type_and_extensions(options, pool_api, RevalidationType::Full, extensions)
+	}
+
+	/// Create new basic transaction pool with provided api and custom
+	/// revalidation type.
+	pub fn with_revalidation_type(
+		options: sc_transaction_graph::Options,
+		pool_api: PoolApi,
+		revalidation_type: RevalidationType,
+	) -> Self {
+		Self::with_revalidation_type_and_extensions(options, pool_api, revalidation_type, Default::default())
+	}
+
+	fn with_revalidation_type_and_extensions(
+		options: sc_transaction_graph::Options,
+		pool_api: PoolApi,
+		revalidation_type: RevalidationType,
+		extensions: sc_transaction_graph::ExtensionsFor<PoolApi>,
+	) -> Self {
+		let revalidation_jitter = options.maintenance.revalidation_jitter;
+		BasicPool {
+			pool: Arc::new(sc_transaction_graph::Pool::with_extensions(options, extensions, Arc::new(pool_api))),
+			revalidation_strategy: Arc::new(Mutex::new(
+				match revalidation_type {
+					RevalidationType::Light => RevalidationStrategy::Light(
+						RevalidationStatus::NotScheduled,
+						RevalidationJitter::new(revalidation_jitter),
+					),
+					RevalidationType::Full => RevalidationStrategy::Always,
+				}
+			)),
+			paused_maintenance: Arc::new(Mutex::new(None)),
+			last_revalidation_outcome: Arc::new(Mutex::new(None)),
+			pending_prune: Arc::new(Mutex::new(Vec::new())),
+			notifiers: Arc::new(Mutex::new(Default::default())),
+			last_maintained: Arc::new(Mutex::new(None)),
+			revalidation_failures: Arc::new(AtomicU32::new(0)),
+			reorg_pending: Arc::new(Mutex::new(Vec::new())),
+			last_maintain_timings: Arc::new(Mutex::new(Default::default())),
+			history: Arc::new(Mutex::new(VecDeque::new())),
+			maintenance_lock: Arc::new(futures::lock::Mutex::new(())),
+			maintenance_calls: Arc::new(AtomicU64::new(0)),
+			superseded_retracted: Arc::new(Mutex::new(Vec::new())),
+			resubmission_enabled: Arc::new(AtomicBool::new(true)),
+			future_first_seen: Arc::new(Mutex::new(HashMap::new())),
+			stuck_futures: Arc::new(AtomicUsize::new(0)),
+			maintain_hooks: Arc::new(Mutex::new(Vec::new())),
+			metrics: Arc::new(Mutex::new(None)),
+			local_key_predicate: Arc::new(Mutex::new(None)),
+		}
+
+	}
+
+	/// Gets shared reference to the underlying pool.
+	pub fn pool(&self) -> &Arc<sc_transaction_graph::Pool<PoolApi>> {
+		&self.pool
+	}
+
+	/// Returns approximate memory used by the pool.
+	///
+	/// Includes encoded transactions, pool metadata and indexes, and watchers.
+	pub fn memory_usage(&self) -> usize {
+		self.pool.memory_usage()
+	}
+
+	/// Returns the outcome of the most recent ready transactions revalidation.
+	///
+	/// `None` if no revalidation has completed yet.
+	pub fn last_revalidation_outcome(&self) -> Option<sc_transaction_graph::RevalidationOutcome> {
+		*self.last_revalidation_outcome.lock()
+	}
+
+	/// Returns a snapshot of the revalidation strategy state.
+	pub fn revalidation_state(&self) -> RevalidationStateView<NumberFor<Block>> {
+		self.revalidation_strategy.lock().view()
+	}
+
+	/// Returns the number of future transactions that have been waiting in the future queue
+	/// for at least `MaintenanceOptions::future_stuck_threshold` blocks as of the last maintenance.
+	pub fn stuck_future_count(&self) -> usize {
+		self.stuck_futures.load(Ordering::Relaxed)
+	}
+
+	/// Returns a summary of the pool health.
+	///
+	/// Briefly takes the pool and listener read locks and walks the ready queue and
+	/// the watchers, so it's fine for periodic liveness probes, but not for hot paths.
+	pub fn health(&self) -> PoolHealth {
+		PoolHealth {
+			maintained_recently: self.last_maintained.lock()
+				.map(|at| at.elapsed() < MAINTENANCE_HEALTH_PERIOD)
+				.unwrap_or(false),
+			revalidation_failures: self.revalidation_failures.load(Ordering::Relaxed),
+			near_capacity: self.pool.is_near_capacity(),
+			watchers: self.pool.watchers_count(),
+		}
+	}
+
+	/// Returns a stream of transactions imported to the pool, buffering up to `capacity` hashes.
+	///
+	/// Oldest notifications are dropped if the subscriber is too slow, which can be
+	/// observed with `BoundedStream::take_lagged`.
+	pub fn import_notification_stream_bounded(
+		&self,
+		capacity: usize,
+	) -> sc_transaction_graph::BoundedStream<sc_transaction_graph::ExHash<PoolApi>> {
+		self.pool.import_notification_stream_bounded(capacity)
+	}
+
+	/// Returns a stream of transactions that got permanently rejected or removed as invalid.
+	///
+	/// Unlike per-submission errors, the stream collects terminal negative outcomes
+	/// (invalid or filtered transactions) in a single place.
+	pub fn deadletter_stream(
+		&self,
+	) -> sc_transaction_graph::DeadletterStream<sc_transaction_graph::ExHash<PoolApi>> {
+		self.pool.deadletter_stream()
+	}
+
+	/// Returns durations of the phases of the most recent maintenance.
+	pub fn last_maintain_timings(&self) -> MaintainTimings {
+		self.last_maintain_timings.lock().clone()
+	}
+
+	/// Returns snapshot of the pool taken right after maintenance of given block.
+	///
+	/// Only snapshots of the `MaintenanceOptions::history_depth` most recently maintained blocks are kept.
+	pub fn snapshot_at(&self, block: &Block::Hash) -> Option<PoolSnapshot<sc_transaction_graph::ExHash<PoolApi>>> {
+		self.history.lock().iter()
+			.find(|(hash, _)| hash == block)
+			.map(|(_, snapshot)| snapshot.clone())
+	}
+
+	/// Exports transactions currently in the pool using given codec.
+	///
+	/// Use `ScaleSnapshotCodec` for the compact default, other codecs make it possible
+	/// to inspect snapshots in a human-readable form. The snapshot can be imported
+	/// back with `import_snapshot` using the same codec.
+	pub fn export_snapshot<C: SnapshotCodec>(&self, codec: &C) -> Vec<u8> {
+		let export = |tx: &sc_transaction_graph::TransactionFor<PoolApi>| SnapshotTransaction {
+			source: tx.source,
+			encoded: codec::Encode::encode(&tx.data),
+		};
+		codec.encode(&ExportedSnapshot {
+			ready: self.pool.ready().map(|tx| export(&tx)).collect(),
+			future: self.pool.futures().iter().map(export).collect(),
+		})
+	}
+
+	/// Returns a future that imports transactions exported with `export_snapshot`.
+	///
+	/// Transactions are validated at given block the same way as submissions from their
+	/// original sources, results are returned in the order of the snapshot (ready transactions
+	/// first). Fails with `Error::MalformedSnapshot` if the snapshot can't be decoded.
+	pub fn import_snapshot<C: SnapshotCodec>(
+		&self,
+		at: &BlockId<Block>,
+		data: &[u8],
+		codec: &C,
+	) -> PoolResult<Vec<Result<TxHash<Self>, error::Error>>> {
+		let xts = codec.decode(data)
+			.and_then(|snapshot| snapshot.ready.into_iter()
+				.chain(snapshot.future)
+				.map(|tx| codec::Decode::decode(&mut &tx.encoded[..]).ok().map(|xt| (tx.source, xt)))
+				.collect::<Option<Vec<(TransactionSource, TransactionFor<Self>)>>>()
+			);
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let xts = xts.ok_or(error::Error::MalformedSnapshot)?;
+			let mut results = Vec::with_capacity(xts.len());
+			for (source, xt) in xts {
+				results.push(pool.submit_one_with_source(&at, source, xt).await);
+			}
+			notify_changes(&pool, &notifiers);
+			Ok(results)
+		}.boxed()
+	}
+
+	/// Returns number of submissions rejected since the pool was created, per reason.
+	pub fn rejection_stats(&self) -> HashMap<sc_transaction_graph::RejectionReason, u64> {
+		self.pool.rejection_stats()
+	}
+
+	/// Returns number of future transactions that became ready or expired since the pool was created.
+	///
+	/// Helps to tune `AdmissionOptions::max_future_distance` and the future queue limits.
+	pub fn future_promotion_stats(&self) -> sc_transaction_graph::FuturePromotionStats {
+		self.pool.future_promotion_stats()
+	}
+
+	/// Returns histogram of runtime validation times of transactions submitted so far.
+	///
+	/// Helps to spot expensive validation clogging the submission path.
+	pub fn validation_time_histogram(&self) -> sc_transaction_graph::ValidationTimeHistogram {
+		self.pool.validation_time_histogram()
+	}
+
+	/// Re-applies the priority normalizer to the ready transactions and reorders them.
+	///
+	/// Should be called whenever external data the normalizer depends on (e.g. a fee oracle)
+	/// gets updated.
+	pub fn reorder_ready(&self) {
+		self.pool.reorder_ready()
+	}
+
+	/// Marks all ready transactions for revalidation without blocking.
+	///
+	/// The transactions are revalidated by the regular (batched) revalidation during
+	/// subsequent maintenance, before any other ready transactions.
+	pub fn schedule_full_revalidation(&self) {
+		self.pool.schedule_full_revalidation()
+	}
+
+	/// Returns a counter increased on every structural change of the pool.
+	///
+	/// Imports, removals, pruning and revalidation all bump the counter, so comparing
+	/// snapshots taken before and after a series of reads tells if the pool
+	/// changed in the meantime. Might be bumped even if nothing actually changed.
+	pub fn generation(&self) -> u64 {
+		self.pool.generation()
+	}
+
+	/// Replaces the chain api used to validate transactions, e.g. after a runtime upgrade.
+	///
+	/// Validations (and maintenance) started after this call use the new api, while the
+	/// ones already in flight complete with the previous one. Transactions already in the
+	/// pool are not revalidated, use `set_api_and_revalidate` if the validity rules changed.
+	pub fn set_api(&self, api: Arc<PoolApi>) {
+		self.pool.set_api(api)
+	}
+
+	/// Starts or stops accepting new transactions, e.g. during a maintenance window.
+	///
+	/// While not accepting, submissions fail with `Error::NotAccepting`, but transactions
+	/// already in the pool are still maintained and can be included in blocks.
+	pub fn set_accepting(&self, accepting: bool) {
+		self.pool.set_accepting(accepting)
+	}
+
+	/// Returns `true` if the pool accepts new transactions.
+	pub fn is_accepting(&self) -> bool {
+		self.pool.is_accepting()
+	}
+
+	/// Registers a hook invoked after each completed maintenance.
+	///
+	/// Hooks are called from the maintenance future, so they should not block.
+	pub fn on_maintain_complete(&self, hook: Arc<dyn OnMaintainComplete<Block>>) {
+		self.maintain_hooks.lock().push(hook)
+	}
+
+	/// Sets the predicate of transactions signed by local keys.
+	///
+	/// Local transactions of retracted blocks are resubmitted first. If `exclusive` is set,
+	/// only local transactions are resubmitted, the others are left to be re-provided
+	/// by the network.
+	pub fn set_local_key_predicate(&self, predicate: Arc<dyn LocalKeyPredicate<Block>>, exclusive: bool) {
+		*self.local_key_predicate.lock() = Some((predicate, exclusive));
+	}
+
+	/// Replaces the destination of the pool metrics.
+	///
+	/// Metrics are not collected until a sink is set.
+	pub fn set_metrics_sink(&self, sink: Arc<dyn MetricsSink>) {
+		*self.metrics.lock() = Some(sink);
+	}
+
+	/// Returns the current destination of the pool metrics.
+	fn metrics_sink(&self) -> Option<Arc<dyn MetricsSink>> {
+		self.metrics.lock().clone()
+	}
+
+	/// Enables or disables resubmission of transactions from retracted blocks, e.g. during
+	/// a series of reorgs.
+	///
+	/// Pruning and revalidation keep running during maintenance. Transactions of blocks
+	/// retracted while disabled are not resubmitted once it's enabled again.
+	pub fn set_resubmission_enabled(&self, enabled: bool) {
+		self.resubmission_enabled.store(enabled, Ordering::Relaxed)
+	}
+
+	/// Returns `true` if transactions from retracted blocks are resubmitted.
+	pub fn is_resubmission_enabled(&self) -> bool {
+		self.resubmission_enabled.load(Ordering::Relaxed)
+	}
+
+	/// Get reference to the inner chain api, for tests only.
+	#[cfg(any(feature = "test-helpers", test))]
+	pub fn api(&self) -> Arc<PoolApi> {
+		self.pool.api()
+	}
+}
+
+impl<PoolApi, Block> BasicPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: 'static + sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash, Error=error::Error>,
+{
+	/// Moves a future transaction to the ready queue as if its requirements were satisfied.
+	///
+	/// Fires `Ready` on the transaction watchers. This bypasses dependency checks
+	/// and is meant to be used for testing only.
+	#[cfg(any(feature = "test-helpers", test))]
+	pub fn force_promote(&self, hash: &TxHash<Self>) -> error::Result<()> {
+		let result = self.pool.force_promote(hash);
+		notify_changes(&self.pool, &self.notifiers);
+		result
+	}
+
+	/// Returns number of distinct senders of transactions in the pool.
+	///
+	/// Senders are derived from the first `provides` tag of each transaction,
+	/// assuming that it encodes the sender (as `(AccountId, Index)` in standard runtimes).
+	pub fn distinct_senders(&self) -> usize {
+		self.pool.distinct_senders()
+	}
+
+	/// Returns sender tags of transactions in the pool (see `distinct_senders`).
+	pub fn sender_tags(&self) -> Vec<Tag> {
+		self.pool.sender_tags()
+	}
+
+	/// Pins a transaction, so that it's never evicted because of the pool limits.
+	///
+	/// Pinned transactions are still pruned when included and removed when invalid.
+	/// Returns `false` if the transaction is not in the pool.
+	pub fn pin(&self, hash: &TxHash<Self>) -> bool {
+		self.pool.pin(hash)
+	}
+
+	/// Unpins a transaction.
+	///
+	/// Returns `false` if the transaction was not pinned.
+	pub fn unpin(&self, hash: &TxHash<Self>) -> bool {
+		self.pool.unpin(hash)
+	}
+
+	/// Returns a future that imports a bunch of unverified transactions to the pool atomically.
+	///
+	/// Either all transactions are imported or none of them. In the latter case
+	/// the index of the first failing transaction is returned together with the error.
+	pub fn submit_batch_atomic(
+		&self,
+		at: &BlockId<Block>,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolResult<Result<Vec<TxHash<Self>>, (usize, error::Error)>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_batch_atomic(&at, xts).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Returns a future that imports a bunch of unverified transactions, stopping at the first failure.
+	///
+	/// Results are returned up to and including the first failing transaction, the rest
+	/// is not attempted. Unlike `submit_batch_atomic` transactions imported before
+	/// the failure stay in the pool.
+	pub fn submit_at_stop_on_error(
+		&self,
+		at: &BlockId<Block>,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolResult<Vec<Result<TxHash<Self>, error::Error>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_at_stop_on_error(&at, xts).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Returns a future that imports a bunch of unverified transactions coming from given source.
+	///
+	/// Regular `submit_*` methods treat all transactions as `TransactionSource::External`.
+	pub fn submit_at_with_source(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolResult<Vec<Result<TxHash<Self>, error::Error>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_at_with_source(&at, source, xts, false).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Returns a future that imports a bunch of trusted transactions, bypassing the temporary bans.
+	///
+	/// Forced submissions skip the checks of the pool state that resubmission of transactions
+	/// from retracted blocks skips as well: the temporary ban of recently invalid or removed
+	/// transactions and the `set_accepting` switch. Runtime validity, pool limits and all
+	/// other policies (`min_priority`, filters etc.) are still enforced.
+	///
+	/// Transactions are treated as `TransactionSource::Local`. Never use it for transactions
+	/// coming from the network or RPC, the ban protects the pool from repeated validation
+	/// of the same invalid transactions.
+	pub fn submit_at_forced(
+		&self,
+		at: &BlockId<Block>,
+		xts: Vec<TransactionFor<Self>>,
+	) -> PoolResult<Vec<Result<TxHash<Self>, error::Error>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_at_with_source(&at, TransactionSource::Local, xts, true).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Returns a future that imports one unverified transaction coming from given source.
+	pub fn submit_one_with_source(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<TxHash<Self>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_one_with_source(&at, source, xt).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Returns a future that imports a single transaction submitted by given source
+	/// and starts to watch its progress in the pool.
+	///
+	/// Number of concurrent watchers per `source_id` is limited by `WatchOptions::max_watchers_per_source`.
+	pub fn submit_and_watch_from(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		source_id: String,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<Box<TransactionStatusStreamFor<Self>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_and_watch_from(&at, source, &source_id, xt)
+				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
+				.await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	/// Imports one unverified transaction, blocking the current thread until it's done.
 	///
-	/// During maintenance, transaction pool makes periodic revalidation
-	/// of all transactions depending on number of blocks or time passed.
-	/// Also this kind of revalidation does not resubmit transactions from
-	/// retracted blocks, since it is too expensive.
-	Light,
+	/// Meant for synchronous callers (e.g. command line tools or FFI boundaries) that can't
+	/// drive the futures returned by `submit_one`. Must not be called from within an async
+	/// executor, since it would block the executor thread (and could deadlock if that
+	/// thread is also needed to drive the validation).
+	pub fn submit_one_sync(
+		&self,
+		at: &BlockId<Block>,
+		xt: TransactionFor<Self>,
+	) -> Result<TxHash<Self>, error::Error> {
+		futures::executor::block_on(self.submit_one(at, xt))
+	}
 
-	/// Full revalidation type.
+	/// Returns a future that imports one unverified transaction, reporting the transaction it replaced.
 	///
-	/// During maintenance, transaction pool revalidates some fixed amount of
-	/// transactions from the pool of valid transactions.
-	Full,
-}
+	/// Same as `submit_one`, but the result also carries the hash of the ready transaction
+	/// that got usurped by the submitted one (e.g. by a fee bump).
+	pub fn submit_one_detailed(
+		&self,
+		at: &BlockId<Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<txpool::SubmitResult<TxHash<Self>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_one_detailed(&at, xt).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
 
-impl<PoolApi, Block> BasicPool<PoolApi, Block>
-	where
-		Block: BlockT,
-		PoolApi: sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash>,
-{
-	/// Create new basic transaction pool with provided api.
-	pub fn new(
-		options: sc_transaction_graph::Options,
-		pool_api: PoolApi,
-	) -> Self {
-		Self::with_revalidation_type(options, pool_api, RevalidationType::Full)
+	/// Returns a future that replaces a transaction in the pool with a new one coming from given source.
+	///
+	/// `old` is only removed if the new transaction enters the pool, otherwise
+	/// the pool is left intact. Meant for correcting transactions that became invalid.
+	pub fn replace(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		old: TxHash<Self>,
+		new: TransactionFor<Self>,
+	) -> PoolResult<TxHash<Self>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.replace(&at, source, old, new).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
 	}
 
-	/// Create new basic transaction pool with provided api and custom
-	/// revalidation type.
-	pub fn with_revalidation_type(
-		options: sc_transaction_graph::Options,
-		pool_api: PoolApi,
-		revalidation_type: RevalidationType,
-	) -> Self {
-		let api = Arc::new(pool_api);
-		let cloned_api = api.clone();
-		BasicPool {
-			api: cloned_api,
-			pool: Arc::new(sc_transaction_graph::Pool::new(options, api)),
-			revalidation_strategy: Arc::new(Mutex::new(
-				match revalidation_type {
-					RevalidationType::Light => RevalidationStrategy::Light(RevalidationStatus::NotScheduled),
-					RevalidationType::Full => RevalidationStrategy::Always,
-				}
-			)),
-		}
+	/// Returns a future that imports one unverified transaction tagged with an idempotency key.
+	///
+	/// Repeated submissions with a recently seen key return the hash of the originally
+	/// submitted transaction instead of importing anything.
+	pub fn submit_one_idempotent(
+		&self,
+		at: &BlockId<Block>,
+		xt: TransactionFor<Self>,
+		key: [u8; 32],
+	) -> PoolResult<TxHash<Self>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.submit_one_idempotent(&at, xt, key).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
 
+	/// Returns a future that prunes all transactions providing any of given tags.
+	///
+	/// Useful when it's known which tags got satisfied on-chain, but not which exact
+	/// transactions were included. Transactions depending on pruned ones get promoted.
+	pub fn prune_tags(&self, at: &BlockId<Block>, tags: Vec<Tag>) -> PoolResult<()> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let at = *at;
+		async move {
+			let result = pool.prune_tags(&at, tags, Vec::new()).await;
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
 	}
 
-	/// Gets shared reference to the underlying pool.
-	pub fn pool(&self) -> &Arc<sc_transaction_graph::Pool<PoolApi>> {
-		&self.pool
+	/// Prunes given transactions included in given block, e.g. when `MaintenanceOptions::auto_prune`
+	/// is disabled.
+	///
+	/// Transactions providing the same tags are pruned as well and the ones depending on
+	/// pruned transactions get promoted. Hashes not known to the pool are ignored.
+	pub fn prune_transactions(&self, at: &BlockId<Block>, hashes: &[TxHash<Self>]) -> error::Result<()> {
+		let result = self.pool.prune_known(at, hashes);
+		notify_changes(&self.pool, &self.notifiers);
+		result
 	}
 
-	/// Get reference to the inner chain api, for tests only.
-	#[cfg(any(feature = "test-helpers", test))]
-	pub fn api(&self) -> &Arc<PoolApi> {
-		&self.api
+	/// Replaces the chain api and returns a future that revalidates all ready transactions at given block.
+	///
+	/// The api is swapped immediately (see `set_api`), transactions submitted while the
+	/// revalidation is running are validated with the new api already.
+	pub fn set_api_and_revalidate(
+		&self,
+		api: Arc<PoolApi>,
+		at: &BlockId<Block>,
+	) -> PoolResult<sc_transaction_graph::RevalidationOutcome> {
+		self.set_api(api);
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let last_revalidation_outcome = self.last_revalidation_outcome.clone();
+		let at = *at;
+		async move {
+			let outcome = pool.revalidate_ready(&at, None).await?;
+			*last_revalidation_outcome.lock() = Some(outcome);
+			notify_changes(&pool, &notifiers);
+			Ok(outcome)
+		}.boxed()
+	}
+
+	/// Pauses the pool maintenance.
+	///
+	/// While paused, `maintain` does nothing besides remembering the latest block
+	/// and all retracted blocks. Submissions keep working as usual.
+	pub fn pause_maintenance(&self) {
+		let mut paused = self.paused_maintenance.lock();
+		if paused.is_none() {
+			*paused = Some(PausedMaintenance {
+				latest: None,
+				retracted: Vec::new(),
+			});
+		}
+	}
+
+	/// Resumes the pool maintenance.
+	///
+	/// Returns a future that performs a single catch-up maintenance for the latest block
+	/// requested while the maintenance was paused.
+	pub fn resume_maintenance(&self) -> Pin<Box<dyn Future<Output=()> + Send>> {
+		let paused = self.paused_maintenance.lock().take();
+		match paused {
+			Some(PausedMaintenance { latest: Some(id), retracted }) => self.maintain(&id, &retracted),
+			_ => Box::pin(ready(())),
+		}
 	}
 }
 
@@ -135,9 +1178,19 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		xts: Vec<TransactionFor<Self>>,
 	) -> PoolResult<Vec<Result<TxHash<Self>, Self::Error>>> {
 		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
 		let at = *at;
 		async move {
-			pool.submit_at(&at, xts, false).await
+			let submitted = xts.len();
+			let result = pool.submit_at(&at, xts, false).await;
+			let rejected = match result {
+				Ok(ref results) => results.iter().filter(|result| result.is_err()).count(),
+				Err(_) => 0,
+			};
+			note_submissions(metrics.as_ref(), submitted, rejected);
+			notify_changes(&pool, &notifiers);
+			result
 		}.boxed()
 	}
 
@@ -147,9 +1200,14 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 		xt: TransactionFor<Self>,
 	) -> PoolResult<TxHash<Self>> {
 		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
 		let at = *at;
 		async move {
-			pool.submit_one(&at, xt).await
+			let result = pool.submit_one(&at, xt).await;
+			note_submissions(metrics.as_ref(), 1, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
 		}.boxed()
 	}
 
@@ -160,30 +1218,214 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 	) -> PoolResult<Box<TransactionStatusStreamFor<Self>>> {
 		let at = *at;
 		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
+
+		async move {
+			let result = pool.submit_and_watch(&at, xt)
+				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
+				.await;
+			note_submissions(metrics.as_ref(), 1, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	fn submit_and_watch_from_source(
+		&self,
+		at: &BlockId<Self::Block>,
+		source_id: String,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<Box<TransactionStatusStreamFor<Self>>> {
+		let at = *at;
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
+
+		async move {
+			let result = pool.submit_and_watch_from(&at, TransactionSource::External, &source_id, xt)
+				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
+				.await;
+			note_submissions(metrics.as_ref(), 1, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	fn submit_and_watch_with_position(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<Box<TransactionStatusStreamFor<Self>>> {
+		let at = *at;
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
+
+		async move {
+			let result = pool.submit_and_watch_with_position(&at, xt)
+				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
+				.await;
+			note_submissions(metrics.as_ref(), 1, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	fn submit_and_watch_terminal_only(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<Box<TransactionStatusStreamFor<Self>>> {
+		let at = *at;
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
 
 		async move {
-			pool.submit_and_watch(&at, xt)
+			let result = pool.submit_and_watch_terminal_only(&at, xt)
 				.map(|result| result.map(|watcher| Box::new(watcher.into_stream()) as _))
-				.await
+				.await;
+			note_submissions(metrics.as_ref(), 1, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	fn submit_bundle(
+		&self,
+		at: &BlockId<Self::Block>,
+		xts: Vec<TransactionFor<Self>>,
+		bundle_id: [u8; 32],
+	) -> PoolResult<Vec<TxHash<Self>>> {
+		let pool = self.pool.clone();
+		let notifiers = self.notifiers.clone();
+		let metrics = self.metrics_sink();
+		let at = *at;
+		async move {
+			let submitted = xts.len();
+			let result = pool.submit_bundle(&at, xts, bundle_id).await;
+			note_submissions(metrics.as_ref(), submitted, result.is_err() as usize);
+			notify_changes(&pool, &notifiers);
+			result
+		}.boxed()
+	}
+
+	fn compute_validity(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolResult<TransactionValidity> {
+		let at = *at;
+		let pool = self.pool.clone();
+
+		async move {
+			pool.compute_validity(&at, source, xt).await
 		}.boxed()
 	}
 
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>> {
-		self.pool.remove_invalid(hashes)
+		let removed = self.pool.remove_invalid(hashes);
+		notify_changes(&self.pool, &self.notifiers);
+		removed
 	}
 
 	fn status(&self) -> PoolStatus {
 		self.pool.status()
 	}
 
+	fn future_status(&self) -> FutureStatus {
+		self.pool.future_status()
+	}
+
+	fn count_by_source(&self) -> SourceCounts {
+		self.pool.count_by_source()
+	}
+
+	fn oldest_pending(&self, limit: usize) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.oldest_pending(limit)
+	}
+
+	fn quarantined(&self) -> Vec<(Arc<Self::InPoolTransaction>, String)> {
+		self.pool.quarantined()
+	}
+
 	fn ready(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
 		Box::new(self.pool.ready())
 	}
 
+	fn ready_by_fee_density(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.pool.ready_by_fee_density())
+	}
+
+	fn ready_shuffled(&self, seed: [u8; 32]) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.ready_shuffled(seed)
+	}
+
+	fn ready_page(
+		&self,
+		cursor: Option<TxHash<Self>>,
+		limit: usize,
+	) -> (Vec<Arc<Self::InPoolTransaction>>, Option<TxHash<Self>>) {
+		self.pool.ready_page(cursor.as_ref(), limit)
+	}
+
+	fn ready_propagable(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.pool.ready_propagable())
+	}
+
+	fn ready_limited(&self, max: usize) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.pool.ready_limited(max))
+	}
+
+	fn ready_within_weight(
+		&self,
+		max: TransactionWeight,
+	) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.pool.ready_within_weight(max))
+	}
+
+	fn next_block_candidates(
+		&self,
+		max_bytes: usize,
+		max_weight: TransactionWeight,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		self.pool.next_block_candidates(max_bytes, max_weight)
+	}
+
+	fn reserve_ready(&self, limit: usize) -> Vec<Arc<Self::InPoolTransaction>> {
+		let reserved = self.pool.reserve_ready(limit);
+		notify_changes(&self.pool, &self.notifiers);
+		reserved
+	}
+
+	fn release_reserved(&self, hashes: &[TxHash<Self>]) -> usize {
+		let released = self.pool.release_reserved(hashes);
+		notify_changes(&self.pool, &self.notifiers);
+		released
+	}
+
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>> {
 		self.pool.import_notification_stream()
 	}
 
+	fn emptiness_stream(&self) -> EmptinessStream {
+		self.notifiers.lock().emptiness.subscribe(self.pool.status().is_empty())
+	}
+
+	fn pressure_stream(&self) -> PressureStream {
+		self.notifiers.lock().pressure.subscribe(self.pool.pressure_level())
+	}
+
+	fn all_status_stream(&self) -> AllStatusStream<TxHash<Self>, BlockHash<Self>> {
+		self.pool.all_status_stream()
+	}
+
+	fn ready_diff_stream(&self) -> ReadyDiffStream<TxHash<Self>> {
+		self.notifiers.lock().ready_diff.subscribe(self.pool.ready().map(|tx| tx.hash.clone()))
+	}
+
 	fn hash_of(&self, xt: &TransactionFor<Self>) -> TxHash<Self> {
 		self.pool.hash_of(xt)
 	}
@@ -195,6 +1437,26 @@ impl<PoolApi, Block> TransactionPool for BasicPool<PoolApi, Block>
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>> {
 		self.pool.ready_transaction(hash)
 	}
+
+	fn encoded_of(&self, hash: &TxHash<Self>) -> Option<Vec<u8>> {
+		self.pool.encoded_of(hash)
+	}
+
+	fn encoded_size_of(&self, hash: &TxHash<Self>) -> Option<usize> {
+		self.pool.encoded_size_of(hash)
+	}
+
+	fn maybe_seen(&self, hash: &TxHash<Self>) -> bool {
+		self.pool.maybe_seen(hash)
+	}
+
+	fn committed_value_for(&self, provides_prefix: &[u8]) -> u128 {
+		self.pool.committed_value_for(provides_prefix)
+	}
+
+	fn tag_graph(&self) -> Vec<(TxHash<Self>, Vec<Tag>, Vec<Tag>)> {
+		self.pool.tag_graph()
+	}
 }
 
 #[cfg_attr(test, derive(Debug))]
@@ -203,13 +1465,54 @@ enum RevalidationStatus<N> {
 	NotScheduled,
 	/// The revalidation is scheduled.
 	Scheduled(Option<std::time::Instant>, Option<N>),
-	/// The revalidation is in progress.
-	InProgress,
+	/// The revalidation is in progress since given time.
+	InProgress(std::time::Instant),
 }
 
 enum RevalidationStrategy<N> {
 	Always,
-	Light(RevalidationStatus<N>)
+	Light(RevalidationStatus<N>, RevalidationJitter)
+}
+
+/// Pseudo-random delay added to scheduled revalidation.
+///
+/// Nodes started together would otherwise revalidate at the same moments.
+struct RevalidationJitter {
+	max: Duration,
+	state: u64,
+}
+
+impl RevalidationJitter {
+	/// Creates a jitter source with a random per-node seed.
+	fn new(max: Duration) -> Self {
+		Self::with_seed(max, RandomState::new().build_hasher().finish())
+	}
+
+	/// Creates a jitter source with given seed.
+	///
+	/// The same seed always yields the same sequence of delays.
+	fn with_seed(max: Duration, seed: u64) -> Self {
+		RevalidationJitter {
+			max,
+			// xorshift state must not be zero
+			state: seed | 1,
+		}
+	}
+
+	/// Returns the next delay, not greater than `max`.
+	fn next(&mut self) -> Duration {
+		let max_millis = self.max.as_millis() as u64;
+		if max_millis == 0 {
+			return Duration::from_millis(0);
+		}
+
+		let mut x = self.state;
+		x ^= x << 13;
+		x ^= x >> 7;
+		x ^= x << 17;
+		self.state = x;
+		Duration::from_millis(x % (max_millis + 1))
+	}
 }
 
 struct RevalidationAction {
@@ -220,23 +1523,40 @@ struct RevalidationAction {
 
 impl<N: Clone + Copy + SimpleArithmetic> RevalidationStrategy<N> {
 	pub fn clear(&mut self) {
-		if let Self::Light(status) = self {
+		if let Self::Light(status, _) = self {
 			status.clear()
 		}
 	}
 
+	pub fn view(&self) -> RevalidationStateView<N> {
+		match self {
+			Self::Always => RevalidationStateView::Always,
+			Self::Light(RevalidationStatus::NotScheduled, _) => RevalidationStateView::NotScheduled,
+			Self::Light(RevalidationStatus::Scheduled(at_time, at_block), _) => RevalidationStateView::Scheduled {
+				at_time: *at_time,
+				at_block: *at_block,
+			},
+			Self::Light(RevalidationStatus::InProgress(_), _) => RevalidationStateView::InProgress,
+		}
+	}
+
 	pub fn next(
 		&mut self,
 		block: N,
 		revalidate_time_period: Option<std::time::Duration>,
 		revalidate_block_period: Option<N>,
+		revalidation_timeout: Option<std::time::Duration>,
+		log_target: &str,
 	) -> RevalidationAction {
 		match self {
-			Self::Light(status) => RevalidationAction {
+			Self::Light(status, jitter) => RevalidationAction {
 				revalidate: status.next_required(
 					block,
 					revalidate_time_period,
-					revalidate_block_period
+					revalidate_block_period,
+					revalidation_timeout,
+					jitter,
+					log_target,
 				),
 				resubmit: false,
 				revalidate_amount: None,
@@ -262,24 +1582,65 @@ impl<N: Clone + Copy + SimpleArithmetic> RevalidationStatus<N> {
 		block: N,
 		revalidate_time_period: Option<std::time::Duration>,
 		revalidate_block_period: Option<N>,
+		revalidation_timeout: Option<std::time::Duration>,
+		jitter: &mut RevalidationJitter,
+		log_target: &str,
+	) -> bool {
+		self.next_required_at(
+			Instant::now(),
+			block,
+			revalidate_time_period,
+			revalidate_block_period,
+			revalidation_timeout,
+			jitter,
+			log_target,
+		)
+	}
+
+	/// Returns true if revalidation is required at given time.
+	///
+	/// Revalidation that is in progress for longer than `revalidation_timeout`
+	/// is considered stuck and the status is reset, so it gets scheduled again.
+	pub fn next_required_at(
+		&mut self,
+		now: Instant,
+		block: N,
+		revalidate_time_period: Option<std::time::Duration>,
+		revalidate_block_period: Option<N>,
+		revalidation_timeout: Option<std::time::Duration>,
+		jitter: &mut RevalidationJitter,
+		log_target: &str,
 	) -> bool {
 		match *self {
 			Self::NotScheduled => {
 				*self = Self::Scheduled(
-					revalidate_time_period.map(|period| Instant::now() + period),
+					revalidate_time_period.map(|period| now + period + jitter.next()),
 					revalidate_block_period.map(|period| block + period),
 				);
 				false
 			},
 			Self::Scheduled(revalidate_at_time, revalidate_at_block) => {
-				let is_required = revalidate_at_time.map(|at| Instant::now() >= at).unwrap_or(false)
+				let is_required = revalidate_at_time.map(|at| now >= at).unwrap_or(false)
 					|| revalidate_at_block.map(|at| block >= at).unwrap_or(false);
 				if is_required {
-					*self = Self::InProgress;
+					*self = Self::InProgress(now);
 				}
 				is_required
 			},
-			Self::InProgress => false,
+			Self::InProgress(started) => {
+				let is_stuck = revalidation_timeout
+					.map(|timeout| now >= started + timeout)
+					.unwrap_or(false);
+				if is_stuck {
+					log::warn!(
+						target: log_target,
+						"Revalidation has been in progress for {:?}, rescheduling.",
+						now - started,
+					);
+					*self = Self::NotScheduled;
+				}
+				false
+			},
 		}
 	}
 }
@@ -292,51 +1653,139 @@ where
 	fn maintain(&self, id: &BlockId<Self::Block>, retracted: &[BlockHash<Self>])
 		-> Pin<Box<dyn Future<Output=()> + Send>>
 	{
+		if let Some(paused) = self.paused_maintenance.lock().as_mut() {
+			log::trace!(target: self.pool.log_target(), "Maintenance is paused, postponing chain event {:?}", id);
+			paused.latest = Some(id.clone());
+			paused.retracted.extend(retracted.iter().cloned());
+			return Box::pin(ready(()));
+		}
+
 		let id = id.clone();
 		let pool = self.pool.clone();
-		let api = self.api.clone();
+		let api = pool.api();
+		let log_target = pool.log_target();
 
 		let block_number = match api.block_id_to_number(&id) {
 			Ok(Some(number)) => number,
 			_ => {
-				log::trace!(target: "txqueue", "Skipping chain event - no number for that block {:?}", id);
+				log::trace!(target: log_target, "Skipping chain event - no number for that block {:?}", id);
 				return Box::pin(ready(()));
 			}
 		};
 
-		let next_action = self.revalidation_strategy.lock().next(
-			block_number,
-			Some(std::time::Duration::from_secs(60)),
-			Some(20.into()),
-		);
+		let call = self.maintenance_calls.fetch_add(1, Ordering::SeqCst) + 1;
+		let maintenance_lock = self.maintenance_lock.clone();
+		let maintenance_calls = self.maintenance_calls.clone();
+		let superseded_retracted = self.superseded_retracted.clone();
 		let revalidation_strategy = self.revalidation_strategy.clone();
+		let last_revalidation_outcome = self.last_revalidation_outcome.clone();
+		let pending_prune = self.pending_prune.clone();
+		let notifiers = self.notifiers.clone();
+		let last_maintained = self.last_maintained.clone();
+		let reorg_pending = self.reorg_pending.clone();
+		let revalidation_failures = self.revalidation_failures.clone();
+		let last_maintain_timings = self.last_maintain_timings.clone();
+		let history = self.history.clone();
+		let resubmission_enabled = self.resubmission_enabled.clone();
+		let future_first_seen = self.future_first_seen.clone();
+		let stuck_futures = self.stuck_futures.clone();
+		let maintain_hooks = self.maintain_hooks.clone();
+		let metrics = self.metrics_sink();
+		let local_key_predicate = self.local_key_predicate.lock().clone();
 		let retracted = retracted.to_vec();
 
 		async move {
+			// maintenance runs are serialized, queued ones wait here for their turn
+			let _maintenance = maintenance_lock.lock().await;
+			if pool.supersede_maintenance() && maintenance_calls.load(Ordering::SeqCst) != call {
+				log::debug!(target: log_target, "[{:?}] Maintenance superseded by a newer one", id);
+				// the block and retracted transactions are taken care of by the next maintenance
+				pending_prune.lock().push((id, 0));
+				superseded_retracted.lock().extend(retracted);
+				return;
+			}
+			let retracted = {
+				let mut superseded = std::mem::replace(&mut *superseded_retracted.lock(), Vec::new());
+				superseded.extend(retracted);
+				superseded
+			};
+
+			let next_action = revalidation_strategy.lock().next(
+				block_number,
+				Some(std::time::Duration::from_secs(60)),
+				Some(20.into()),
+				pool.revalidation_timeout(),
+				log_target,
+			);
+			let started = Instant::now();
+			let mut timings = MaintainTimings::default();
+
+			// blocks which bodies were not available during previous maintenance go first
+			let mut to_prune = std::mem::replace(&mut *pending_prune.lock(), Vec::new());
+			to_prune.push((id.clone(), 0));
+
 			// We don't query block if we won't prune anything
-			if !pool.status().is_empty() {
-				let hashes = api.block_body(&id).await
-					.unwrap_or_else(|e| {
-						log::warn!("Prune known transactions: error request {:?}!", e);
-						None
-					})
-				.unwrap_or_default()
-				.into_iter()
-				.map(|tx| pool.hash_of(&tx))
-				.collect::<Vec<_>>();
+			if !pool.auto_prune() {
+				log::trace!(target: log_target, "[{:?}] Automatic pruning is disabled", id);
+			} else if !pool.status().is_empty() {
+				for (block, attempts) in to_prune {
+					let body = match fetch_block_body(&*api, &block, log_target).await {
+						BlockBody::Known(body) => body,
+						BlockBody::Unavailable if attempts < MAX_PRUNE_RETRIES => {
+							pending_prune.lock().push((block, attempts + 1));
+							continue;
+						},
+						BlockBody::Unavailable => {
+							log::warn!(target: log_target,
+								"[{:?}] Block body is still not available, giving up on pruning", block
+							);
+							continue;
+						},
+					};
+					let hashes = body.into_iter()
+						.filter_map(|tx| match pool.try_hash_and_length(&tx) {
+							Ok((hash, _)) => Some(hash),
+							Err(e) => {
+								log::error!(target: log_target, "[{:?}] Skipping transaction of the block: {}", block, e);
+								None
+							},
+						})
+						.collect::<Vec<_>>();
 
-				if let Err(e) = pool.prune_known(&id, &hashes) {
-					log::error!("Cannot prune known in the pool {:?}!", e);
+					if let Err(e) = pool.prune_known(&block, &hashes) {
+						log::error!(target: log_target, "Cannot prune known in the pool {:?}!", e);
+					}
 				}
 			}
+			timings.prune = started.elapsed();
+			pool.on_best_block(block_number);
+
+			let time_gated = pool.submit_time_gated(&id).await;
+			if !time_gated.is_empty() {
+				log::debug!(target: log_target,
+					"[{:?}] Imported {} of {} time-gated transactions",
+					id, time_gated.iter().filter(|result| result.is_ok()).count(), time_gated.len(),
+				);
+			}
 
-			if next_action.resubmit {
+			if next_action.resubmit && !resubmission_enabled.load(Ordering::Relaxed) {
+				log::debug!(target: log_target, "[{:?}] Resubmission is disabled, skipping retracted blocks", id);
+			} else if next_action.resubmit {
+				let resubmit_started = Instant::now();
 				let mut resubmit_transactions = Vec::new();
 
+				// resubmit in the original order of blocks, so that dependent transactions
+				// don't have to wait in the future queue for their predecessors
+				let mut retracted = retracted;
+				retracted.sort_by_key(|hash| api.block_id_to_number(&BlockId::hash(hash.clone()))
+					.ok()
+					.and_then(|number| number)
+				);
+
 				for retracted_hash in retracted {
 					let block_transactions = api.block_body(&BlockId::hash(retracted_hash.clone())).await
 						.unwrap_or_else(|e| {
-							log::warn!("Failed to fetch block body {:?}!", e);
+							log::warn!(target: log_target, "Failed to fetch block body {:?}!", e);
 							None
 						})
 						.unwrap_or_default()
@@ -345,20 +1794,158 @@ where
 
 					resubmit_transactions.extend(block_transactions);
 				}
-				if let Err(e) = pool.submit_at(&id, resubmit_transactions, true).await {
-					log::debug!(target: "txpool",
+
+				if let Some((predicate, exclusive)) = local_key_predicate {
+					if exclusive {
+						resubmit_transactions.retain(|xt| predicate.is_local(xt));
+					} else {
+						// the sort is stable, so the order of blocks is kept otherwise
+						resubmit_transactions.sort_by_key(|xt| !predicate.is_local(xt));
+					}
+				}
+
+				// transactions that failed resubmission during recent reorgs might be valid
+				// again, so they are retried until their grace period ends
+				let deadline = block_number.saturating_add(pool.reorg_grace_blocks().into());
+				let retried = std::mem::replace(&mut *reorg_pending.lock(), Vec::new())
+					.into_iter()
+					.filter(|(deadline, _)| *deadline >= block_number);
+				let mut seen = HashSet::new();
+				let resubmit = resubmit_transactions.into_iter()
+					.map(|xt| (deadline, xt))
+					.chain(retried)
+					.filter_map(|retry| match pool.try_hash_and_length(&retry.1) {
+						Ok((hash, _)) => Some((hash, retry)),
+						Err(e) => {
+							log::error!(target: log_target, "[{:?}] Skipping retracted transaction: {}", id, e);
+							None
+						},
+					})
+					.filter(|(hash, _)| seen.insert(hash.clone()))
+					.collect::<Vec<_>>();
+
+				match pool.submit_at_with_source(
+					&id,
+					TransactionSource::InBlock,
+					resubmit.iter().map(|(_, (_, xt))| xt.clone()).collect::<Vec<_>>(),
+					true,
+				).await {
+					Ok(results) => {
+						let imported = results.into_iter().filter_map(Result::ok).collect::<HashSet<_>>();
+						// transactions rejected as invalid got banned, other failures are not retried
+						let retry = resubmit.into_iter()
+							.filter(|(hash, (deadline, _))| {
+								*deadline > block_number && !imported.contains(hash) && pool.is_banned(hash)
+							})
+							.map(|(_, retry)| retry);
+						reorg_pending.lock().extend(retry);
+					},
+					Err(e) => log::debug!(target: log_target,
 						"[{:?}] Error re-submitting transactions: {:?}", id, e
-					)
+					),
 				}
+				timings.resubmit = resubmit_started.elapsed();
 			}
 
+			let mut revalidation = None;
 			if next_action.revalidate {
-				if let Err(e) = pool.revalidate_ready(&id, next_action.revalidate_amount).await {
-					log::warn!("Revalidate ready failed {:?}", e);
+				let revalidate_started = Instant::now();
+				match pool.revalidate_ready(&id, next_action.revalidate_amount).await {
+					Ok(outcome) => {
+						log::debug!(target: log_target,
+							"[{:?}] Revalidated {} transactions: {} kept, {} removed",
+							id, outcome.checked, outcome.kept, outcome.removed,
+						);
+						*last_revalidation_outcome.lock() = Some(outcome);
+						revalidation = Some(outcome);
+					},
+					Err(e) => {
+						log::warn!(target: log_target, "Revalidate ready failed {:?}", e);
+						revalidation_failures.fetch_add(1, Ordering::Relaxed);
+					},
 				}
+				// the status is left in progress if the revalidation never finishes,
+				// so that the watchdog (see `MaintenanceOptions::revalidation_timeout`) can reschedule it
+				revalidation_strategy.lock().clear();
+				timings.revalidate = revalidate_started.elapsed();
 			}
 
-			revalidation_strategy.lock().clear();
+			if let Some(threshold) = pool.future_stuck_threshold() {
+				let stuck = {
+					let future = pool.future_hashes();
+					let mut first_seen = future_first_seen.lock();
+					let in_future = future.iter().collect::<HashSet<_>>();
+					first_seen.retain(|hash, _| in_future.contains(hash));
+					for hash in future.iter() {
+						first_seen.entry(hash.clone()).or_insert(block_number);
+					}
+					first_seen.values()
+						.filter(|seen| block_number.saturating_sub(**seen) >= threshold.into())
+						.count()
+				};
+				if stuck > 0 {
+					log::warn!(target: log_target,
+						"[{:?}] {} transactions stuck in the future queue for at least {} blocks",
+						id, stuck, threshold,
+					);
+				}
+				stuck_futures.store(stuck, Ordering::Relaxed);
+			}
+
+			pool.notify_positions();
+			notify_changes(&pool, &notifiers);
+			timings.total = started.elapsed();
+			log::debug!(target: log_target, "[{:?}] Maintained in {:?}", id, timings);
+			*last_maintain_timings.lock() = timings.clone();
+			*last_maintained.lock() = Some(Instant::now());
+
+			let history_depth = pool.history_depth();
+			if history_depth > 0 {
+				if let Ok(Some(block_hash)) = api.block_id_to_hash(&id) {
+					let snapshot = PoolSnapshot {
+						ready: pool.ready().map(|tx| tx.hash.clone()).collect(),
+						future: pool.future_hashes(),
+					};
+					let mut history = history.lock();
+					history.retain(|(hash, _)| *hash != block_hash);
+					history.push_back((block_hash, snapshot));
+					while history.len() > history_depth {
+						history.pop_front();
+					}
+				}
+			}
+
+			let hooks = maintain_hooks.lock().clone();
+			if metrics.is_none() && hooks.is_empty() {
+				return;
+			}
+
+			let status = pool.status();
+			if let Some(metrics) = metrics {
+				metrics.inc_counter(metric_names::MAINTAINED, 1);
+				metrics.set_gauge(metric_names::READY, status.ready as u64);
+				metrics.set_gauge(metric_names::FUTURE, status.future as u64);
+				metrics.observe_histogram(metric_names::MAINTAIN_DURATION, timings.total.as_secs_f64());
+			}
+
+			if !hooks.is_empty() {
+				let report = MaintenanceReport {
+					timings,
+					revalidation,
+					ready: status.ready,
+					future: status.future,
+				};
+				for hook in hooks {
+					hook.on_complete(&id, &report);
+				}
+			}
 		}.boxed()
 	}
+
+	fn on_block_finalized(&self, block: BlockHash<Self>) -> Pin<Box<dyn Future<Output=()> + Send>> {
+		if let Err(e) = self.pool.on_block_finalized(&BlockId::hash(block)) {
+			log::debug!(target: self.pool.log_target(), "[{:?}] Failed to process finalized block: {:?}", block, e);
+		}
+		Box::pin(ready(()))
+	}
 }
@@ -35,8 +35,11 @@ struct ChainState {
 	pub block_by_number: HashMap<BlockNumber, Vec<Extrinsic>>,
 	pub block_by_hash: HashMap<Hash, Vec<Extrinsic>>,
 	pub header_by_number: HashMap<BlockNumber, Header>,
+	pub number_by_hash: HashMap<Hash, BlockNumber>,
 	pub nonces: HashMap<AccountId, u64>,
 	pub invalid_hashes: HashSet<Hash>,
+	pub unencodable_hashes: HashSet<Hash>,
+	pub stalled_hashes: HashSet<Hash>,
 }
 
 /// Test Api for transaction pool.
@@ -98,6 +101,15 @@ impl TestApi {
 		chain.block_by_hash.insert(block_hash, xts);
 	}
 
+	/// Push a block without a number, but with known height.
+	///
+	/// As a part of non-canonical chain.
+	pub fn push_fork_block_with_number(&self, block_hash: Hash, block_number: BlockNumber, xts: Vec<Extrinsic>) {
+		let mut chain = self.chain.write();
+		chain.block_by_hash.insert(block_hash, xts);
+		chain.number_by_hash.insert(block_hash, block_number);
+	}
+
 	fn hash_and_length_inner(ex: &Extrinsic) -> (Hash, usize) {
 		let encoded = ex.encode();
 		(BlakeTwo256::hash(&encoded), encoded.len())
@@ -113,6 +125,32 @@ impl TestApi {
 		);
 	}
 
+	/// Mark previously invalid transaction as valid again.
+	pub fn remove_invalid(&self, xts: &Extrinsic) {
+		self.chain.write().invalid_hashes.remove(
+			&Self::hash_and_length_inner(xts).0
+		);
+	}
+
+	/// Mark some transaction as failing to encode.
+	///
+	/// Next time the transaction pool will try to compute its hash, api will panic.
+	pub fn add_unencodable(&self, xts: &Extrinsic) {
+		self.chain.write().unencodable_hashes.insert(
+			Self::hash_and_length_inner(xts).0
+		);
+	}
+
+	/// Mark some transaction as stalling the validation.
+	///
+	/// Next time the transaction pool will try to validate the transaction,
+	/// the validation will never complete.
+	pub fn add_stalled(&self, xts: &Extrinsic) {
+		self.chain.write().stalled_hashes.insert(
+			Self::hash_and_length_inner(xts).0
+		);
+	}
+
 	/// Query validation requests received.
 	pub fn validation_requests(&self) -> Vec<Extrinsic> {
 		self.validation_requests.read().clone()
@@ -129,7 +167,10 @@ impl sc_transaction_graph::ChainApi for TestApi {
 	type Block = Block;
 	type Hash = Hash;
 	type Error = error::Error;
-	type ValidationFuture = futures::future::Ready<error::Result<TransactionValidity>>;
+	type ValidationFuture = futures::future::Either<
+		futures::future::Ready<error::Result<TransactionValidity>>,
+		futures::future::Pending<error::Result<TransactionValidity>>,
+	>;
 	type BodyFuture = futures::future::Ready<error::Result<Option<Vec<Extrinsic>>>>;
 
 	fn validate_transaction(
@@ -147,10 +188,15 @@ impl sc_transaction_graph::ChainApi for TestApi {
 		};
 		let provides = vec![vec![uxt.transfer().nonce as u8]];
 
-		if self.chain.read().invalid_hashes.contains(&self.hash_and_length(&uxt).0) {
-			return futures::future::ready(Ok(
+		let hash = self.hash_and_length(&uxt).0;
+		if self.chain.read().stalled_hashes.contains(&hash) {
+			return futures::future::Either::Right(futures::future::pending())
+		}
+
+		if self.chain.read().invalid_hashes.contains(&hash) {
+			return futures::future::Either::Left(futures::future::ready(Ok(
 				Err(TransactionValidityError::Invalid(InvalidTransaction::Custom(0)))
-			))
+			)))
 		}
 
 		let mut validity = ValidTransaction {
@@ -163,14 +209,19 @@ impl sc_transaction_graph::ChainApi for TestApi {
 
 		(self.valid_modifier.read())(&mut validity);
 
-		futures::future::ready(Ok(Ok(validity)))
+		futures::future::Either::Left(futures::future::ready(Ok(Ok(validity))))
 	}
 
 	fn block_id_to_number(
 		&self,
 		at: &BlockId<Self::Block>,
 	) -> error::Result<Option<sc_transaction_graph::NumberFor<Self>>> {
-		Ok(Some(number_of(at)))
+		Ok(Some(match at {
+			generic::BlockId::Hash(hash) => self.chain.read().number_by_hash.get(hash)
+				.map(|number| *number as u64)
+				.unwrap_or_else(|| number_of(at)),
+			_ => number_of(at),
+		}))
 	}
 
 	fn block_id_to_hash(
@@ -187,14 +238,17 @@ impl sc_transaction_graph::ChainApi for TestApi {
 		&self,
 		ex: &sc_transaction_graph::ExtrinsicFor<Self>,
 	) -> (Self::Hash, usize) {
-		Self::hash_and_length_inner(ex)
+		let (hash, len) = Self::hash_and_length_inner(ex);
+		if self.chain.read().unencodable_hashes.contains(&hash) {
+			panic!("Failed to encode transaction {:?}", hash);
+		}
+		(hash, len)
 	}
 
 	fn block_body(&self, id: &BlockId<Self::Block>) -> Self::BodyFuture {
-		futures::future::ready(Ok(if let BlockId::Number(num) = id {
-			self.chain.read().block_by_number.get(num).cloned()
-		} else {
-			None
+		futures::future::ready(Ok(match id {
+			BlockId::Number(num) => self.chain.read().block_by_number.get(num).cloned(),
+			BlockId::Hash(hash) => self.chain.read().block_by_hash.get(hash).cloned(),
 		}))
 	}
 }
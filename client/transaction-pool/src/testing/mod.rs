@@ -19,5 +19,8 @@
 #[cfg(any(feature = "test-helpers", test))]
 pub mod api;
 
+#[cfg(any(feature = "test-helpers", test))]
+pub mod recording;
+
 #[cfg(test)]
 mod pool;
\ No newline at end of file
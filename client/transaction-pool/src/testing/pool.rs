@@ -7,10 +7,12 @@ use sp_runtime::{
 	transaction_validity::ValidTransaction,
 };
 use substrate_test_runtime_client::{
-	runtime::{Block, Hash, Index},
+	runtime::{Block, Extrinsic, Hash, Index},
 	AccountKeyring::*,
 };
 use crate::testing::api::{TestApi, uxt};
+use codec::Encode;
+use sp_transaction_pool::TransactionStatus;
 
 fn pool() -> Pool<TestApi> {
 	Pool::new(Default::default(), TestApi::with_alice_nonce(209).into())
@@ -141,12 +143,33 @@ fn should_prune_old_during_maintenance() {
 	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
-	pool.api.push_block(1, vec![xt.clone()]);
+	pool.api().push_block(1, vec![xt.clone()]);
 
 	block_on(pool.maintain(&BlockId::number(1), &[]));
 	assert_eq!(pool.status().ready, 0);
 }
 
+#[test]
+fn should_skip_transactions_that_fail_to_encode_during_maintenance() {
+	let xt = uxt(Alice, 209);
+	let unencodable = uxt(Bob, 0);
+	let retracted_hash = Hash::random();
+
+	let pool = maintained_pool();
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+	pool.api().add_unencodable(&unencodable);
+
+	// when
+	pool.api().push_block(1, vec![unencodable.clone(), xt.clone()]);
+	pool.api().push_fork_block(retracted_hash, vec![unencodable.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+
+	// then
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.status().future, 0);
+}
+
 #[test]
 fn should_revalidate_during_maintenance() {
 	let xt1 = uxt(Alice, 209);
@@ -156,14 +179,19 @@ fn should_revalidate_during_maintenance() {
 	block_on(pool.submit_one(&BlockId::number(0), xt1.clone())).expect("1. Imported");
 	block_on(pool.submit_one(&BlockId::number(0), xt2.clone())).expect("2. Imported");
 	assert_eq!(pool.status().ready, 2);
-	assert_eq!(pool.api.validation_requests().len(), 2);
+	assert_eq!(pool.api().validation_requests().len(), 2);
+	assert_eq!(pool.last_revalidation_outcome(), None);
 
-	pool.api.push_block(1, vec![xt1.clone()]);
+	pool.api().push_block(1, vec![xt1.clone()]);
 
 	block_on(pool.maintain(&BlockId::number(1), &[]));
 	assert_eq!(pool.status().ready, 1);
 	// test that pool revalidated transaction that left ready and not included in the block
-	assert_eq!(pool.api.validation_requests().len(), 3);
+	assert_eq!(pool.api().validation_requests().len(), 3);
+	assert_eq!(
+		pool.last_revalidation_outcome(),
+		Some(sc_transaction_graph::RevalidationOutcome { checked: 1, kept: 1, removed: 0 }),
+	);
 }
 
 #[test]
@@ -176,13 +204,247 @@ fn should_resubmit_from_retracted_during_maintaince() {
 	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
-	pool.api.push_block(1, vec![]);
-	pool.api.push_fork_block(retracted_hash, vec![xt.clone()]);
+	pool.api().push_block(1, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![xt.clone()]);
+
+	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+	assert_eq!(pool.status().ready, 1);
+}
+
+#[test]
+fn should_skip_non_propagable_transactions_from_retracted_if_configured() {
+	let xt = uxt(Alice, 209);
+	let retracted_hash = Hash::random();
+	let api = TestApi::with_alice_nonce(209);
+	api.set_valid_modifier(Box::new(|v: &mut ValidTransaction| {
+		v.propagate = false;
+	}));
+	api.push_block(1, vec![]);
+	api.push_fork_block(retracted_hash, vec![xt.clone()]);
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { resubmit_non_propagable: false, ..Default::default() },
+		..Default::default()
+	}, api);
+
+	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_skip_resubmission_from_retracted_while_disabled() {
+	let xt = uxt(Alice, 209);
+	let pool = maintained_pool();
 
+	// when
+	pool.set_resubmission_enabled(false);
+	let retracted_hash = Hash::random();
+	pool.api().push_block(1, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![xt.clone()]);
 	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+
+	// then
+	assert!(!pool.is_resubmission_enabled());
+	assert_eq!(pool.status().ready, 0);
+
+	// when
+	pool.set_resubmission_enabled(true);
+	let retracted_hash = Hash::random();
+	pool.api().push_block(2, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![xt.clone()]);
+	block_on(pool.maintain(&BlockId::number(2), &[retracted_hash]));
+
+	// then
 	assert_eq!(pool.status().ready, 1);
 }
 
+#[test]
+fn should_resubmit_only_local_transactions_in_exclusive_mode() {
+	struct SignedByAlice;
+	impl LocalKeyPredicate<Block> for SignedByAlice {
+		fn is_local(&self, xt: &Extrinsic) -> bool {
+			xt.transfer().from == Alice.public()
+		}
+	}
+
+	let local = uxt(Alice, 209);
+	let remote = uxt(Bob, 0);
+	let pool = maintained_pool();
+	pool.set_local_key_predicate(Arc::new(SignedByAlice), true);
+
+	// when
+	let retracted_hash = Hash::random();
+	pool.api().push_block(1, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![local.clone(), remote.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+
+	// then
+	assert_eq!(pool.ready().map(|tx| tx.data.clone()).collect::<Vec<_>>(), vec![local.clone()]);
+
+	// when
+	pool.set_local_key_predicate(Arc::new(SignedByAlice), false);
+	let retracted_hash = Hash::random();
+	pool.api().push_block(2, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![remote.clone()]);
+	block_on(pool.maintain(&BlockId::number(2), &[retracted_hash]));
+
+	// then
+	assert_eq!(pool.status().ready, 2);
+}
+
+#[test]
+fn should_count_transactions_stuck_in_future_queue() {
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { future_stuck_threshold: Some(2), ..Default::default() },
+		..Default::default()
+	}, TestApi::with_alice_nonce(209));
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 211))).expect("1. Imported");
+	assert_eq!(pool.status().future, 1);
+
+	for block in 1..3 {
+		pool.api().push_block(block, vec![]);
+		block_on(pool.maintain(&BlockId::number(block), &[]));
+		assert_eq!(pool.stuck_future_count(), 0);
+	}
+
+	pool.api().push_block(3, vec![]);
+	block_on(pool.maintain(&BlockId::number(3), &[]));
+	assert_eq!(pool.stuck_future_count(), 1);
+
+	// filling the gap promotes the transaction
+	block_on(pool.submit_one(&BlockId::number(3), uxt(Alice, 209))).expect("2. Imported");
+	block_on(pool.submit_one(&BlockId::number(3), uxt(Alice, 210))).expect("3. Imported");
+	pool.api().push_block(4, vec![]);
+	block_on(pool.maintain(&BlockId::number(4), &[]));
+	assert_eq!(pool.status().future, 0);
+	assert_eq!(pool.stuck_future_count(), 0);
+}
+
+#[test]
+fn should_not_prune_automatically_if_disabled() {
+	let xt = uxt(Alice, 209);
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { auto_prune: false, ..Default::default() },
+		..Default::default()
+	}, TestApi::with_alice_nonce(209));
+	let hash = block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+
+	// when
+	pool.api().push_block(1, vec![xt.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+
+	// then
+	assert_eq!(pool.status().ready, 1);
+
+	// when
+	pool.prune_transactions(&BlockId::number(1), &[hash]).expect("Pruned");
+
+	// then
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_import_time_gated_transactions_once_valid() {
+	#[derive(Debug)]
+	struct FromBlockTwo;
+	impl sc_transaction_graph::ValidFromHint for FromBlockTwo {
+		fn valid_from(&self, _encoded: &[u8]) -> Option<u64> {
+			Some(2)
+		}
+	}
+
+	let extensions = sc_transaction_graph::PoolExtensions {
+		valid_from_hint: Some(Arc::new(FromBlockTwo)),
+		..Default::default()
+	};
+	let pool = BasicPool::with_extensions(Default::default(), TestApi::with_alice_nonce(209), extensions);
+	let hash = block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("Kept aside");
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.pool().time_gated_count(), 1);
+
+	// when
+	pool.api().push_block(1, vec![]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+
+	// then
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.pool().time_gated_count(), 1);
+
+	// when
+	pool.api().push_block(2, vec![]);
+	block_on(pool.maintain(&BlockId::number(2), &[]));
+
+	// then
+	assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+	assert_eq!(pool.pool().time_gated_count(), 0);
+}
+
+#[test]
+fn should_emit_metrics_to_registered_sink() {
+	#[derive(Default)]
+	struct Recorder(Mutex<Vec<(&'static str, u64)>>);
+	impl MetricsSink for Recorder {
+		fn inc_counter(&self, name: &'static str, value: u64) {
+			self.0.lock().push((name, value));
+		}
+		fn set_gauge(&self, name: &'static str, value: u64) {
+			self.0.lock().push((name, value));
+		}
+		fn observe_histogram(&self, _name: &'static str, _value: f64) {}
+	}
+
+	let recorder = Arc::new(Recorder::default());
+	let pool = maintained_pool();
+	pool.set_metrics_sink(recorder.clone());
+
+	// when
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	assert!(block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).is_err());
+	block_on(pool.submit_at(&BlockId::number(0), vec![uxt(Alice, 210)])).expect("2. Imported");
+	pool.api().push_block(1, vec![]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+
+	// then
+	assert_eq!(*recorder.0.lock(), vec![
+		(metric_names::SUBMITTED, 1),
+		(metric_names::SUBMITTED, 1),
+		(metric_names::REJECTED, 1),
+		(metric_names::SUBMITTED, 1),
+		(metric_names::MAINTAINED, 1),
+		(metric_names::READY, 2),
+		(metric_names::FUTURE, 0),
+	]);
+}
+
+#[test]
+fn should_report_completed_maintenance_to_hooks() {
+	struct Reports(Mutex<Vec<(BlockId<Block>, MaintenanceReport)>>);
+	impl OnMaintainComplete<Block> for Reports {
+		fn on_complete(&self, at: &BlockId<Block>, report: &MaintenanceReport) {
+			self.0.lock().push((at.clone(), report.clone()));
+		}
+	}
+
+	let reports = Arc::new(Reports(Mutex::new(Vec::new())));
+	let pool = maintained_pool();
+	pool.on_maintain_complete(reports.clone());
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 211))).expect("2. Imported");
+
+	// when
+	pool.api().push_block(1, vec![]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+
+	// then
+	let reports = reports.0.lock();
+	assert_eq!(reports.len(), 1);
+	let (at, report) = &reports[0];
+	assert_eq!(*at, BlockId::number(1));
+	assert_eq!(report.ready, 1);
+	assert_eq!(report.future, 1);
+	assert_eq!(report.timings, pool.last_maintain_timings());
+	assert_eq!(report.revalidation, pool.last_revalidation_outcome());
+}
+
 #[test]
 fn should_not_retain_invalid_hashes_from_retracted() {
 	let xt = uxt(Alice, 209);
@@ -193,10 +455,655 @@ fn should_not_retain_invalid_hashes_from_retracted() {
 	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
 	assert_eq!(pool.status().ready, 1);
 
-	pool.api.push_block(1, vec![]);
-	pool.api.push_fork_block(retracted_hash, vec![xt.clone()]);
-	pool.api.add_invalid(&xt);
+	pool.api().push_block(1, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![xt.clone()]);
+	pool.api().add_invalid(&xt);
 
 	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
 	assert_eq!(pool.status().ready, 0);
 }
+
+#[test]
+fn should_resubmit_retracted_transactions_in_block_order() {
+	let pool = maintained_pool();
+	let first_block = Hash::random();
+	let second_block = Hash::random();
+	pool.api().push_fork_block_with_number(first_block, 1, vec![uxt(Alice, 209)]);
+	pool.api().push_fork_block_with_number(second_block, 2, vec![uxt(Alice, 210)]);
+
+	let mut statuses = pool.all_status_stream();
+	block_on(pool.maintain(&BlockId::number(3), &[second_block, first_block]));
+
+	let pending: Vec<_> = pool.ready().map(|a| a.data.transfer().nonce).collect();
+	assert_eq!(pending, vec![209, 210]);
+	while let Ok(Some((_, status))) = statuses.try_next() {
+		assert_eq!(status, TransactionStatus::Ready);
+	}
+}
+
+#[test]
+fn should_retry_invalid_retracted_transactions_during_grace_period() {
+	let xt = uxt(Alice, 209);
+	let retracted_hash = Hash::random();
+
+	let options = sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { reorg_grace_blocks: 2, ..Default::default() },
+		..Default::default()
+	};
+	let pool = BasicPool::new(options, TestApi::with_alice_nonce(209));
+
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	pool.api().push_block(1, vec![]);
+	pool.api().push_fork_block(retracted_hash, vec![xt.clone()]);
+	pool.api().add_invalid(&xt);
+
+	block_on(pool.maintain(&BlockId::number(1), &[retracted_hash]));
+	assert_eq!(pool.status().ready, 0);
+
+	// the transaction becomes valid again shortly after the reorg
+	pool.api().remove_invalid(&xt);
+	pool.api().push_block(2, vec![]);
+
+	block_on(pool.maintain(&BlockId::number(2), &[]));
+	assert_eq!(pool.status().ready, 1);
+}
+
+#[test]
+fn should_not_prune_while_maintenance_is_paused() {
+	let xt = uxt(Alice, 209);
+
+	let pool = maintained_pool();
+	pool.pause_maintenance();
+
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	pool.api().push_block(1, vec![xt.clone()]);
+
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	assert_eq!(pool.status().ready, 1);
+
+	block_on(pool.resume_maintenance());
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_bypass_ban_with_forced_submission() {
+	let pool = maintained_pool();
+	let xt = uxt(Alice, 209);
+	let hash = block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	pool.remove_invalid(&[hash]);
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).unwrap_err();
+
+	let results = block_on(pool.submit_at_forced(&BlockId::number(0), vec![xt])).expect("2. Imported");
+
+	assert_eq!(results.len(), 1);
+	assert_eq!(*results[0].as_ref().unwrap(), hash);
+	assert_eq!(pool.status().ready, 1);
+}
+
+#[test]
+fn should_serialize_concurrent_maintenance() {
+	let alice = uxt(Alice, 209);
+	let bob = uxt(Bob, 0);
+
+	let pool = maintained_pool();
+
+	block_on(pool.submit_one(&BlockId::number(0), alice.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), bob.clone())).expect("2. Imported");
+	pool.api().push_block(1, vec![alice]);
+	pool.api().push_block(2, vec![bob]);
+
+	// pretend another maintenance is running
+	let running = block_on(pool.maintenance_lock.lock());
+	let mut first = pool.maintain(&BlockId::number(1), &[]);
+	let mut second = pool.maintain(&BlockId::number(2), &[]);
+	assert!(futures::FutureExt::now_or_never(&mut first).is_none());
+	assert!(futures::FutureExt::now_or_never(&mut second).is_none());
+	assert_eq!(pool.status().ready, 2);
+
+	drop(running);
+	block_on(first);
+	assert_eq!(pool.status().ready, 1);
+	block_on(second);
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_skip_superseded_maintenance_if_configured() {
+	let alice = uxt(Alice, 209);
+	let bob = uxt(Bob, 0);
+
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { supersede_maintenance: true, ..Default::default() },
+		..Default::default()
+	}, TestApi::with_alice_nonce(209));
+
+	block_on(pool.submit_one(&BlockId::number(0), alice.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), bob.clone())).expect("2. Imported");
+	pool.api().push_block(1, vec![alice]);
+	pool.api().push_block(2, vec![bob]);
+
+	let running = block_on(pool.maintenance_lock.lock());
+	let first = pool.maintain(&BlockId::number(1), &[]);
+	let second = pool.maintain(&BlockId::number(2), &[]);
+	drop(running);
+
+	// the first one got superseded, so it doesn't prune anything
+	block_on(first);
+	assert_eq!(pool.status().ready, 2);
+
+	// the second one prunes both blocks
+	block_on(second);
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_retry_pruning_if_block_body_is_not_available() {
+	let xt = uxt(Alice, 209);
+
+	let pool = maintained_pool();
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	// block 1 is known, but its body is not available yet
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	assert_eq!(pool.status().ready, 1);
+
+	pool.api().push_block(1, vec![xt.clone()]);
+	pool.api().push_block(2, vec![]);
+
+	block_on(pool.maintain(&BlockId::number(2), &[]));
+	assert_eq!(pool.status().ready, 0);
+}
+
+#[test]
+fn should_stream_ready_diffs() {
+	let xt1 = uxt(Alice, 209);
+	let xt2 = uxt(Alice, 210);
+
+	let pool = maintained_pool();
+	let hash1 = block_on(pool.submit_one(&BlockId::number(0), xt1.clone())).expect("1. Imported");
+	let mut stream = futures::executor::block_on_stream(pool.ready_diff_stream());
+
+	let hash2 = block_on(pool.submit_one(&BlockId::number(0), xt2.clone())).expect("2. Imported");
+	assert_eq!(stream.next(), Some(ReadyDiff { added: vec![hash2], removed: vec![] }));
+
+	pool.api().push_block(1, vec![xt1.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	assert_eq!(stream.next(), Some(ReadyDiff { added: vec![], removed: vec![hash1] }));
+}
+
+#[test]
+fn should_notify_about_emptiness_transitions() {
+	let xt1 = uxt(Alice, 209);
+	let xt2 = uxt(Alice, 210);
+
+	let pool = maintained_pool();
+	let mut stream = futures::executor::block_on_stream(pool.emptiness_stream());
+
+	block_on(pool.submit_one(&BlockId::number(0), xt1.clone())).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), xt2.clone())).expect("2. Imported");
+	assert_eq!(stream.next(), Some(false));
+
+	pool.api().push_block(1, vec![xt1.clone(), xt2.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(stream.next(), Some(true));
+
+	drop(pool);
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn should_notify_about_pressure_level_transitions() {
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		ready: sc_transaction_graph::base_pool::Limit {
+			count: 4,
+			total_bytes: 1_000_000,
+		},
+		..Default::default()
+	}, TestApi::with_alice_nonce(209));
+	let mut stream = futures::executor::block_on_stream(pool.pressure_stream());
+
+	let xts = (209..213).map(|nonce| uxt(Alice, nonce)).collect::<Vec<_>>();
+	for xt in &xts {
+		block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("Imported");
+	}
+	assert_eq!(stream.next(), Some(PressureLevel::Medium));
+	assert_eq!(stream.next(), Some(PressureLevel::Full));
+
+	pool.api().push_block(1, xts);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(stream.next(), Some(PressureLevel::Low));
+
+	drop(pool);
+	assert_eq!(stream.next(), None);
+}
+
+fn finality_tracking_pool() -> BasicPool<TestApi, Block> {
+	BasicPool::new(sc_transaction_graph::Options {
+		watch: sc_transaction_graph::WatchOptions { finality_timeout_blocks: Some(2), ..Default::default() },
+		..Default::default()
+	}, TestApi::with_alice_nonce(209))
+}
+
+#[test]
+fn should_emit_finality_timeout_if_block_is_never_finalized() {
+	let xt = uxt(Alice, 209);
+	let pool = finality_tracking_pool();
+	let watcher = block_on(pool.submit_and_watch(&BlockId::number(0), xt.clone())).expect("1. Imported");
+
+	// best block advances, but nothing gets finalized
+	pool.api().push_block(1, vec![xt.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	pool.api().push_block(2, vec![]);
+	block_on(pool.maintain(&BlockId::number(2), &[]));
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(pool.pool().watchers_count(), 1);
+	pool.api().push_block(3, vec![]);
+	block_on(pool.maintain(&BlockId::number(3), &[]));
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock(Default::default())));
+	assert_eq!(stream.next(), Some(TransactionStatus::FinalityTimeout(Default::default())));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn should_stop_watching_once_block_is_finalized() {
+	let xt = uxt(Alice, 209);
+	let pool = finality_tracking_pool();
+	let watcher = block_on(pool.submit_and_watch(&BlockId::number(0), xt.clone())).expect("1. Imported");
+
+	pool.api().push_block(1, vec![xt.clone()]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	block_on(pool.on_block_finalized(Default::default()));
+	pool.api().push_block(3, vec![]);
+	block_on(pool.maintain(&BlockId::number(3), &[]));
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+	assert_eq!(stream.next(), Some(TransactionStatus::InBlock(Default::default())));
+	assert_eq!(stream.next(), None);
+}
+
+#[test]
+fn should_force_promote_future_transaction() {
+	let xt = uxt(Alice, 210);
+	let pool = maintained_pool();
+	let watcher = block_on(pool.submit_and_watch(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	let ready_hash = block_on(pool.submit_one(&BlockId::number(0), uxt(Bob, 0))).expect("2. Imported");
+	assert_eq!(pool.status().ready, 1);
+	assert_eq!(pool.status().future, 1);
+
+	pool.force_promote(&pool.hash_of(&xt)).expect("Future transaction is promoted");
+	assert_eq!(pool.status().ready, 2);
+	assert_eq!(pool.status().future, 0);
+	assert!(pool.force_promote(&ready_hash).is_err());
+
+	let mut stream = futures::executor::block_on_stream(watcher);
+	assert_eq!(stream.next(), Some(TransactionStatus::Future));
+	assert_eq!(stream.next(), Some(TransactionStatus::Ready));
+}
+
+#[test]
+fn should_delay_scheduled_revalidation_by_deterministic_jitter() {
+	let period = std::time::Duration::from_secs(60);
+	let max = std::time::Duration::from_secs(10);
+	let mut jitter1 = RevalidationJitter::with_seed(max, 42);
+	let mut jitter2 = RevalidationJitter::with_seed(max, 42);
+	let delays = (0..16).map(|_| jitter1.next()).collect::<Vec<_>>();
+	assert_eq!(delays, (0..16).map(|_| jitter2.next()).collect::<Vec<_>>());
+	assert!(delays.iter().all(|delay| *delay <= max));
+	assert!(delays.iter().any(|delay| *delay != delays[0]));
+
+	let mut status = RevalidationStatus::<u64>::NotScheduled;
+	let before = std::time::Instant::now();
+	assert!(!status.next_required(0, Some(period), None, None, &mut RevalidationJitter::with_seed(max, 42), "txpool"));
+	let after = std::time::Instant::now();
+	match status {
+		RevalidationStatus::Scheduled(Some(at), None) => {
+			assert!(at >= before + period + delays[0]);
+			assert!(at <= after + period + delays[0]);
+		},
+		status => panic!("Unexpected status: {:?}", status),
+	}
+}
+
+#[test]
+fn should_reschedule_revalidation_stuck_in_progress() {
+	let timeout = std::time::Duration::from_secs(60);
+	let mut jitter = RevalidationJitter::with_seed(std::time::Duration::from_secs(0), 42);
+	let started = std::time::Instant::now();
+	let mut status = RevalidationStatus::<u64>::InProgress(started);
+
+	// still within the timeout
+	assert!(!status.next_required_at(started + timeout / 2, 0, None, Some(1), Some(timeout), &mut jitter, "txpool"));
+	match status {
+		RevalidationStatus::InProgress(at) if at == started => {},
+		status => panic!("Unexpected status: {:?}", status),
+	}
+
+	// without timeout the revalidation is awaited indefinitely
+	assert!(!status.next_required_at(started + timeout * 10, 0, None, Some(1), None, &mut jitter, "txpool"));
+	match status {
+		RevalidationStatus::InProgress(at) if at == started => {},
+		status => panic!("Unexpected status: {:?}", status),
+	}
+
+	// stuck revalidation gets reset
+	assert!(!status.next_required_at(started + timeout, 0, None, Some(1), Some(timeout), &mut jitter, "txpool"));
+	match status {
+		RevalidationStatus::NotScheduled => {},
+		status => panic!("Unexpected status: {:?}", status),
+	}
+
+	// and scheduling resumes
+	assert!(!status.next_required_at(started + timeout, 0, None, Some(1), Some(timeout), &mut jitter, "txpool"));
+	assert!(status.next_required_at(started + timeout, 1, None, Some(1), Some(timeout), &mut jitter, "txpool"));
+	match status {
+		RevalidationStatus::InProgress(at) if at == started + timeout => {},
+		status => panic!("Unexpected status: {:?}", status),
+	}
+}
+
+#[test]
+fn should_reschedule_revalidation_that_never_completes() {
+	let api = TestApi::with_alice_nonce(209);
+	let pool = BasicPool::with_revalidation_type(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions {
+			revalidation_timeout: Some(std::time::Duration::from_millis(100)),
+			..Default::default()
+		},
+		..Default::default()
+	}, api, RevalidationType::Light);
+	let xt = uxt(Alice, 209);
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("1. Imported");
+	pool.api().add_stalled(&xt);
+
+	// schedules revalidation at block 20
+	block_on(pool.maintain(&BlockId::number(0), &[]));
+	match pool.revalidation_state() {
+		RevalidationStateView::Scheduled { at_block: Some(20), .. } => {},
+		state => panic!("Unexpected state: {:?}", state),
+	}
+
+	// revalidation never completes, the maintenance gets abandoned
+	let mut maintain = pool.maintain(&BlockId::number(20), &[]);
+	assert!(futures::FutureExt::now_or_never(&mut maintain).is_none());
+	drop(maintain);
+	assert_eq!(pool.revalidation_state(), RevalidationStateView::InProgress);
+
+	// still in progress within the timeout
+	block_on(pool.maintain(&BlockId::number(21), &[]));
+	assert_eq!(pool.revalidation_state(), RevalidationStateView::InProgress);
+
+	// stuck revalidation gets reset and scheduled again
+	std::thread::sleep(std::time::Duration::from_millis(150));
+	block_on(pool.maintain(&BlockId::number(22), &[]));
+	assert_eq!(pool.revalidation_state(), RevalidationStateView::NotScheduled);
+	block_on(pool.maintain(&BlockId::number(23), &[]));
+	match pool.revalidation_state() {
+		RevalidationStateView::Scheduled { at_block: Some(43), .. } => {},
+		state => panic!("Unexpected state: {:?}", state),
+	}
+}
+
+#[test]
+fn should_compute_validity_without_importing() {
+	let pool = maintained_pool();
+
+	let validity = block_on(pool.compute_validity(&BlockId::number(0), TransactionSource::Local, uxt(Alice, 210)))
+		.expect("Validation api doesn't fail");
+
+	assert_eq!(validity, Ok(ValidTransaction {
+		priority: 1,
+		requires: vec![vec![209]],
+		provides: vec![vec![210]],
+		longevity: 64,
+		propagate: true,
+	}));
+	assert!(pool.status().is_empty());
+}
+
+#[test]
+fn should_prune_tags_and_promote_dependent_transactions() {
+	let pool = maintained_pool();
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).expect("2. Imported");
+	assert_eq!(pool.status().ready, 2);
+
+	pool.api().increment_nonce(Alice.into());
+	block_on(pool.prune_tags(&BlockId::number(1), vec![vec![209]])).expect("Pruned");
+
+	let pending: Vec<_> = pool.ready().map(|a| a.data.transfer().nonce).collect();
+	assert_eq!(pending, vec![210]);
+}
+
+#[test]
+fn should_report_pool_health() {
+	let pool = maintained_pool();
+	assert_eq!(pool.health(), PoolHealth {
+		maintained_recently: false,
+		revalidation_failures: 0,
+		near_capacity: false,
+		watchers: 0,
+	});
+
+	let _watcher = block_on(pool.submit_and_watch(&BlockId::number(0), uxt(Alice, 209))).expect("Imported");
+	block_on(pool.maintain(&BlockId::number(0), &[]));
+
+	assert_eq!(pool.health(), PoolHealth {
+		maintained_recently: true,
+		revalidation_failures: 0,
+		near_capacity: false,
+		watchers: 1,
+	});
+}
+
+#[test]
+fn should_record_maintain_timings() {
+	let pool = maintained_pool();
+	assert_eq!(pool.last_maintain_timings(), MaintainTimings::default());
+
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("Imported");
+	block_on(pool.maintain(&BlockId::number(0), &[]));
+
+	let timings = pool.last_maintain_timings();
+	assert!(timings.total > std::time::Duration::default());
+	assert!(timings.total >= timings.prune + timings.resubmit + timings.revalidate);
+}
+
+#[test]
+fn should_revalidate_whole_ready_set_after_scheduling_full_revalidation() {
+	let pool = maintained_pool();
+	for nonce in 209..229 {
+		block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, nonce))).expect("Imported");
+	}
+	assert_eq!(pool.status().ready, 20);
+	let validated_before = pool.api().validation_requests().len();
+
+	pool.schedule_full_revalidation();
+	pool.api().push_block(1, vec![]);
+	block_on(pool.maintain(&BlockId::number(1), &[]));
+	pool.api().push_block(2, vec![]);
+	block_on(pool.maintain(&BlockId::number(2), &[]));
+
+	let revalidated = pool.api().validation_requests()[validated_before..]
+		.iter()
+		.map(|xt| xt.transfer().nonce)
+		.collect::<HashSet<_>>();
+	assert_eq!(revalidated.len(), 20);
+}
+
+#[test]
+fn should_keep_snapshots_of_recently_maintained_blocks() {
+	let pool = BasicPool::new(sc_transaction_graph::Options {
+		maintenance: sc_transaction_graph::MaintenanceOptions { history_depth: 2, ..Default::default() },
+		..Default::default()
+	}, TestApi::with_alice_nonce(209));
+	let blocks = (0..3).map(|_| Hash::random()).collect::<Vec<_>>();
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 211))).expect("2. Imported");
+
+	for block in &blocks {
+		pool.api().push_fork_block(*block, vec![]);
+		block_on(pool.maintain(&BlockId::hash(*block), &[]));
+	}
+
+	assert_eq!(pool.snapshot_at(&blocks[0]), None);
+	assert_eq!(pool.snapshot_at(&blocks[2]), Some(PoolSnapshot {
+		ready: vec![pool.hash_of(&uxt(Alice, 209))],
+		future: vec![pool.hash_of(&uxt(Alice, 211))],
+	}));
+}
+
+#[test]
+fn should_export_and_import_snapshots_with_given_codec() {
+	// given
+	let pool = BasicPool::new(Default::default(), TestApi::with_alice_nonce(209));
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 211))).expect("2. Imported");
+
+	// when
+	let exported = pool.export_snapshot(&ScaleSnapshotCodec);
+
+	// then
+
+	assert_eq!(ScaleSnapshotCodec.decode(&exported), Some(ExportedSnapshot {
+		ready: vec![SnapshotTransaction { source: TransactionSource::External, encoded: uxt(Alice, 209).encode() }],
+		future: vec![SnapshotTransaction { source: TransactionSource::External, encoded: uxt(Alice, 211).encode() }],
+	}));
+	assert_eq!(ScaleSnapshotCodec.decode(&exported[..exported.len() - 1]), None);
+
+	// when
+	let imported = BasicPool::new(Default::default(), TestApi::with_alice_nonce(209));
+	let results = block_on(imported.import_snapshot(&BlockId::number(0), &exported, &ScaleSnapshotCodec))
+		.expect("Snapshot is well-formed");
+
+	// then
+	assert_eq!(results.len(), 2);
+	assert!(results.iter().all(Result::is_ok));
+	assert_eq!(imported.status().ready, 1);
+	assert_eq!(imported.status().future, 1);
+	let malformed = &exported[..exported.len() - 1];
+	match block_on(imported.import_snapshot(&BlockId::number(0), malformed, &ScaleSnapshotCodec)) {
+		Err(error::Error::MalformedSnapshot) => {},
+		other => panic!("Unexpected result: {:?}", other.map(|results| results.len())),
+	}
+}
+
+#[test]
+fn should_revalidate_with_replaced_api() {
+	let pool = maintained_pool();
+	let xt = uxt(Alice, 209);
+	block_on(pool.submit_one(&BlockId::number(0), xt.clone())).expect("Imported");
+	assert_eq!(pool.status().ready, 1);
+
+	let api = Arc::new(TestApi::with_alice_nonce(209));
+	api.add_invalid(&xt);
+	let outcome = block_on(pool.set_api_and_revalidate(api.clone(), &BlockId::number(0)))
+		.expect("Revalidated");
+
+	assert_eq!(outcome.removed, 1);
+	assert_eq!(pool.status().ready, 0);
+	assert_eq!(api.validation_requests().len(), 1);
+	assert!(Arc::ptr_eq(&pool.api(), &api));
+}
+
+#[test]
+fn should_report_near_capacity() {
+	let options = sc_transaction_graph::Options {
+		ready: sc_transaction_graph::base_pool::Limit { count: 2, total_bytes: 1_000_000 },
+		..Default::default()
+	};
+	let pool = BasicPool::new(options, TestApi::with_alice_nonce(209));
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("1. Imported");
+	assert!(!pool.health().near_capacity);
+
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 210))).expect("2. Imported");
+	assert!(pool.health().near_capacity);
+}
+
+#[test]
+fn should_use_provided_extensions() {
+	#[derive(Debug)]
+	struct Constant;
+	impl sc_transaction_graph::PriorityNormalizer for Constant {
+		fn normalize(&self, _tx: &ValidTransaction) -> u64 {
+			7
+		}
+	}
+
+	let extensions = sc_transaction_graph::PoolExtensions {
+		priority_normalizer: Arc::new(Constant),
+		..Default::default()
+	};
+	let pool = BasicPool::with_extensions(Default::default(), TestApi::with_alice_nonce(209), extensions);
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("Imported");
+
+	let priorities: Vec<_> = pool.ready().map(|tx| tx.priority).collect();
+	assert_eq!(priorities, vec![7]);
+}
+
+#[test]
+fn clones_should_share_the_pool() {
+	let pool = maintained_pool();
+	let clone = pool.clone();
+
+	block_on(clone.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("Imported");
+
+	let pending: Vec<_> = pool.ready().map(|a| a.data.transfer().nonce).collect();
+	assert_eq!(pending, vec![209]);
+}
+
+#[test]
+fn should_submit_synchronously() {
+	let pool = maintained_pool();
+
+	let hash = pool.submit_one_sync(&BlockId::number(0), uxt(Alice, 209)).expect("Imported");
+
+	assert_eq!(pool.ready().map(|tx| tx.hash).collect::<Vec<_>>(), vec![hash]);
+	assert!(pool.submit_one_sync(&BlockId::number(0), uxt(Alice, 209)).is_err());
+}
+
+#[test]
+fn should_bump_generation_on_changes() {
+	let pool = maintained_pool();
+	let initial = pool.generation();
+
+	block_on(pool.submit_one(&BlockId::number(0), uxt(Alice, 209))).expect("Imported");
+	let after_import = pool.generation();
+	assert!(after_import > initial);
+
+	let _ = pool.ready().count();
+	let _ = pool.status();
+	assert_eq!(pool.generation(), after_import);
+
+	block_on(pool.prune_tags(&BlockId::number(1), vec![vec![209]])).expect("Pruned");
+	assert!(pool.generation() > after_import);
+}
+
+#[test]
+fn should_expose_revalidation_state() {
+	let full = maintained_pool();
+	assert_eq!(full.revalidation_state(), RevalidationStateView::Always);
+
+	let light = BasicPool::with_revalidation_type(
+		Default::default(),
+		TestApi::with_alice_nonce(209),
+		RevalidationType::Light,
+	);
+	assert_eq!(light.revalidation_state(), RevalidationStateView::NotScheduled);
+
+	light.revalidation_strategy.lock().next(1, Some(std::time::Duration::from_secs(60)), Some(20), None, "txpool");
+	match light.revalidation_state() {
+		RevalidationStateView::Scheduled { at_time: Some(_), at_block: Some(21) } => {},
+		state => panic!("Unexpected state: {:?}", state),
+	}
+}
@@ -0,0 +1,194 @@
+// Copyright 2020 Parity Technologies (UK) Ltd.
+// This file is part of Substrate.
+
+// Substrate is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Substrate is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Substrate.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Recording and replaying of pool operations.
+//!
+//! Create the pool with `RecordingPool` to record the operations it's asked to perform,
+//! then `replay` them on a fresh pool with the same options to reproduce its state in a test.
+
+use crate::*;
+
+/// Operation performed on the pool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PoolOp<Block: BlockT> {
+	/// Transactions submitted at given block.
+	Submit {
+		/// Block the transactions were validated at.
+		at: BlockId<Block>,
+		/// Source of the transactions.
+		source: TransactionSource,
+		/// Submitted transactions.
+		xts: Vec<Block::Extrinsic>,
+	},
+	/// Maintenance of the pool for given block.
+	Maintain {
+		/// The new best block.
+		at: BlockId<Block>,
+		/// Blocks retracted by the new best block.
+		retracted: Vec<Block::Hash>,
+	},
+	/// Removal of transactions with given hashes as invalid.
+	RemoveInvalid(Vec<Block::Hash>),
+}
+
+/// Pool wrapper recording operations performed on the pool.
+pub struct RecordingPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash>,
+{
+	pool: BasicPool<PoolApi, Block>,
+	options: sc_transaction_graph::Options,
+	ops: Mutex<Vec<PoolOp<Block>>>,
+}
+
+impl<PoolApi, Block> RecordingPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: 'static + sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash, Error=error::Error>,
+{
+	/// Creates a new pool with given options and starts recording operations performed on it.
+	pub fn new(options: sc_transaction_graph::Options, api: PoolApi) -> Self {
+		RecordingPool {
+			pool: BasicPool::new(options.clone(), api),
+			options,
+			ops: Mutex::new(Vec::new()),
+		}
+	}
+
+	/// Returns the wrapped pool.
+	///
+	/// Operations performed directly on the wrapped pool are not recorded.
+	pub fn pool(&self) -> &BasicPool<PoolApi, Block> {
+		&self.pool
+	}
+
+	/// Returns options the pool was created with.
+	pub fn options(&self) -> &sc_transaction_graph::Options {
+		&self.options
+	}
+
+	/// Returns operations recorded so far.
+	pub fn ops(&self) -> Vec<PoolOp<Block>> {
+		self.ops.lock().clone()
+	}
+
+	/// Imports a bunch of unverified transactions coming from given source.
+	pub fn submit_at(
+		&self,
+		at: &BlockId<Block>,
+		source: TransactionSource,
+		xts: Vec<Block::Extrinsic>,
+	) -> PoolResult<Vec<Result<Block::Hash, error::Error>>> {
+		self.ops.lock().push(PoolOp::Submit {
+			at: *at,
+			source,
+			xts: xts.clone(),
+		});
+		self.pool.submit_at_with_source(at, source, xts)
+	}
+
+	/// Maintains the pool for given best block.
+	pub fn maintain(&self, at: &BlockId<Block>, retracted: &[Block::Hash]) -> Pin<Box<dyn Future<Output=()> + Send>> {
+		self.ops.lock().push(PoolOp::Maintain {
+			at: *at,
+			retracted: retracted.to_vec(),
+		});
+		self.pool.maintain(at, retracted)
+	}
+
+	/// Removes transactions with given hashes as invalid.
+	pub fn remove_invalid(&self, hashes: &[Block::Hash]) -> usize {
+		self.ops.lock().push(PoolOp::RemoveInvalid(hashes.to_vec()));
+		self.pool.remove_invalid(hashes).len()
+	}
+}
+
+/// Performs given operations, in order, on a fresh pool with given options.
+///
+/// The state is only reproduced if the options are the same as the ones of the recorded
+/// pool (see `RecordingPool::options`). Results of the operations are ignored, so that
+/// the replay doesn't stop on the operations that failed when recorded.
+pub async fn replay<PoolApi, Block>(
+	options: sc_transaction_graph::Options,
+	ops: &[PoolOp<Block>],
+	api: PoolApi,
+) -> BasicPool<PoolApi, Block>
+	where
+		Block: BlockT,
+		PoolApi: 'static + sc_transaction_graph::ChainApi<Block=Block, Hash=Block::Hash, Error=error::Error>,
+{
+	let pool = BasicPool::new(options, api);
+	for op in ops {
+		match op {
+			PoolOp::Submit { at, source, xts } => {
+				let _ = pool.submit_at_with_source(at, *source, xts.clone()).await;
+			},
+			PoolOp::Maintain { at, retracted } => pool.maintain(at, retracted).await,
+			PoolOp::RemoveInvalid(hashes) => {
+				pool.remove_invalid(hashes);
+			},
+		}
+	}
+	pool
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use futures::executor::block_on;
+	use substrate_test_runtime_client::{runtime::Block, AccountKeyring::*};
+	use crate::testing::api::{TestApi, uxt};
+
+	#[test]
+	fn should_replay_recorded_operations() {
+		let api = || {
+			let api = TestApi::with_alice_nonce(209);
+			api.push_block(1, vec![uxt(Alice, 209)]);
+			api
+		};
+
+		// given
+		let options = sc_transaction_graph::Options {
+			reject_future_transactions: true,
+			..Default::default()
+		};
+		let pool = RecordingPool::new(options, api());
+		let hashes = block_on(pool.submit_at(&BlockId::number(0), TransactionSource::External, vec![
+			uxt(Alice, 209),
+			uxt(Alice, 210),
+			uxt(Alice, 211),
+			uxt(Alice, 213),
+		])).expect("Imported");
+		assert_eq!(hashes.len(), 4);
+		assert!(hashes[3].is_err());
+		block_on(pool.maintain(&BlockId::number(1), &[]));
+		let invalid = pool.pool().hash_of(&uxt(Alice, 211));
+		assert_eq!(pool.remove_invalid(&[invalid]), 1);
+		let ops: Vec<PoolOp<Block>> = pool.ops();
+		assert_eq!(ops.len(), 3);
+
+		// when
+		let replayed = block_on(replay(pool.options().clone(), &ops, api()));
+
+		// then
+		let ready = |pool: &BasicPool<TestApi, Block>| pool.ready().map(|tx| tx.hash).collect::<Vec<_>>();
+		assert_eq!(ready(&replayed), ready(pool.pool()));
+		assert_eq!(ready(&replayed), vec![pool.pool().hash_of(&uxt(Alice, 210))]);
+		assert_eq!(replayed.status().future, 0);
+		assert_eq!(pool.pool().status().future, 0);
+	}
+}
@@ -19,6 +19,7 @@
 use sp_runtime::transaction_validity::{
 	TransactionPriority as Priority, InvalidTransaction, UnknownTransaction,
 };
+use crate::pool::TransactionWeight;
 
 /// Transaction pool result.
 pub type Result<T> = std::result::Result<T, Error>;
@@ -56,17 +57,130 @@ pub enum Error {
 	#[display(fmt="Cycle Detected")]
 	CycleDetected,
 	/// Transaction was dropped immediately after it got inserted.
-	#[display(fmt="Transaction couldn't enter the pool because of the limit.")]
-	ImmediatelyDropped,
+	#[display(
+		fmt="Transaction couldn't enter the pool because of the {} limit ({} of {} transactions, {} of {} bytes)",
+		exceeded, current, limit, current_bytes, limit_bytes
+	)]
+	ImmediatelyDropped {
+		/// The limit that was exceeded.
+		exceeded: ExceededLimit,
+		/// Number of transactions counted towards the limit when it was enforced.
+		current: usize,
+		/// Maximal number of transactions allowed by the limit.
+		limit: usize,
+		/// Approximate memory occupied by transactions counted towards the limit when it was enforced.
+		current_bytes: usize,
+		/// Maximal approximate memory allowed by the limit.
+		limit_bytes: usize,
+	},
+	/// Transaction was dropped immediately after it got inserted, because of the weight limit.
+	#[display(fmt="Transaction couldn't enter the pool because of the weight limit ({} of {})", current, limit)]
+	WeightLimitExceeded {
+		/// Total weight of ready transactions when the limit was enforced.
+		current: TransactionWeight,
+		/// Maximal total weight of ready transactions.
+		limit: TransactionWeight,
+	},
 	/// Invalid block id.
 	InvalidBlockId(String),
 	/// The pool is not accepting future transactions.
 	#[display(fmt="The pool is not accepting future transactions")]
 	RejectedFutureTransaction,
+	/// The transaction is too far in the future to be kept in the pool.
+	#[display(fmt="Transaction is too far in the future ({} > {})", distance, max)]
+	TooDistantFuture {
+		/// Estimated number of missing predecessors.
+		distance: u64,
+		/// Maximal accepted distance.
+		max: u64,
+	},
+	/// The transaction would form too long dependency chain in the future queue.
+	#[display(fmt="Future dependency chain is too deep ({} > {})", depth, max)]
+	ChainTooDeep {
+		/// Length of the chain including the transaction.
+		depth: usize,
+		/// Maximal accepted length of the chain.
+		max: usize,
+	},
+	/// The transaction would form a dependency cycle with transactions in the future queue.
+	#[display(fmt="Transaction requirements form a dependency cycle")]
+	DependencyCycle,
+	/// The transaction priority is below minimal priority accepted by the pool.
+	#[display(fmt="Priority too low ({} < {})", priority, min)]
+	PriorityTooLow {
+		/// Priority of the transaction.
+		priority: Priority,
+		/// Minimal accepted priority.
+		min: Priority,
+	},
+	/// The transaction is not propagable and resubmission of such transactions is disabled.
+	#[display(fmt="Non-propagable transaction is not resubmitted")]
+	NonPropagableResubmission,
+	/// The transaction was rejected by the pre-validation filter.
+	#[display(fmt="Transaction filtered out by the node policy")]
+	Filtered,
+	/// The runtime panicked while validating the transaction.
+	#[display(fmt="Transaction validation panicked")]
+	ValidationPanicked,
+	/// The transaction can't be encoded.
+	#[display(fmt="Transaction encoding failed")]
+	EncodingFailed,
+	/// The source has reached the limit of concurrently watched transactions.
+	#[display(fmt="Too many watchers for source ({} >= {})", current, limit)]
+	TooManyWatchersForSource {
+		/// Number of live watchers of the source.
+		current: usize,
+		/// Maximal number of watchers per source.
+		limit: usize,
+	},
+	/// The transaction is not part of the future queue.
+	#[display(fmt="Transaction is not in the future queue")]
+	NotInFutureQueue,
+	/// The operation is not supported by the pool.
+	#[display(fmt="Operation is not supported by the pool")]
+	Unsupported,
+	/// The pool is temporarily not accepting new transactions.
+	#[display(fmt="The pool is not accepting new transactions")]
+	NotAccepting,
+	/// The pool already holds the maximal number of transactions of this type.
+	#[display(fmt="Too many transactions of type {:?} ({} >= {})", prefix, current, limit)]
+	TypeLimitExceeded {
+		/// Encoding prefix identifying the type of the transaction.
+		prefix: Vec<u8>,
+		/// Number of transactions of the type in the pool.
+		current: usize,
+		/// Maximal number of transactions of the type.
+		limit: usize,
+	},
+	/// The transaction longevity is below minimal longevity accepted by the pool.
+	#[display(fmt="Longevity too short ({} < {})", longevity, min)]
+	LongevityTooShort {
+		/// Longevity of the transaction.
+		longevity: u64,
+		/// Minimal accepted longevity.
+		min: u64,
+	},
 }
 
 impl std::error::Error for Error {}
 
+/// Count and memory limit of the pool that caused a transaction to be dropped.
+#[derive(Debug, derive_more::Display, Clone, Copy, PartialEq, Eq)]
+pub enum ExceededLimit {
+	/// Limit of the ready queue.
+	#[display(fmt="ready queue")]
+	Ready,
+	/// Limit of the future queue.
+	#[display(fmt="future queue")]
+	Future,
+	/// Share of the ready queue limit available for local transactions.
+	#[display(fmt="local quota")]
+	LocalQuota,
+	/// Share of the ready queue limit available for external transactions.
+	#[display(fmt="external quota")]
+	ExternalQuota,
+}
+
 /// Transaction pool error conversion.
 pub trait IntoPoolError: std::error::Error + Send + Sized {
 	/// Try to extract original `Error`
@@ -75,8 +189,15 @@ pub trait IntoPoolError: std::error::Error + Send + Sized {
 	/// provide more descriptive error messages for end users
 	/// of RPC API.
 	fn into_pool_error(self) -> std::result::Result<Error, Self> { Err(self) }
+
+	/// Try to get a reference to the original `Error`
+	///
+	/// Same as `into_pool_error`, but without consuming the error.
+	fn as_pool_error(&self) -> Option<&Error> { None }
 }
 
 impl IntoPoolError for Error {
 	fn into_pool_error(self) -> std::result::Result<Error, Self> { Ok(self) }
+
+	fn as_pool_error(&self) -> Option<&Error> { Some(self) }
 }
@@ -21,17 +21,20 @@ use std::{
 	hash::Hash,
 	sync::Arc,
 	pin::Pin,
+	time::Duration,
 };
+use codec::Encode;
 use futures::{
-	Future, Stream,
+	Future, Stream, StreamExt, TryFutureExt,
 	channel::mpsc,
+	future,
 };
 use serde::{Deserialize, Serialize};
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block as BlockT, Member},
 	transaction_validity::{
-		TransactionLongevity, TransactionPriority, TransactionTag,
+		TransactionLongevity, TransactionPriority, TransactionTag, TransactionValidity,
 	},
 };
 
@@ -40,11 +43,11 @@ use sp_runtime::{
 pub struct PoolStatus {
 	/// Number of transactions in the ready queue.
 	pub ready: usize,
-	/// Sum of bytes of ready transaction encodings.
+	/// Approximate memory occupied by ready transactions (encodings and pool metadata).
 	pub ready_bytes: usize,
 	/// Number of transactions in the future queue.
 	pub future: usize,
-	/// Sum of bytes of ready transaction encodings.
+	/// Approximate memory occupied by future transactions (encodings and pool metadata).
 	pub future_bytes: usize,
 }
 
@@ -55,6 +58,73 @@ impl PoolStatus {
 	}
 }
 
+/// Aggregate statistics of the future queue.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FutureStatus {
+	/// Number of transactions in the future queue.
+	pub count: usize,
+	/// Approximate memory occupied by future transactions (encodings and pool metadata).
+	pub bytes: usize,
+	/// Number of distinct senders of future transactions.
+	pub distinct_senders: usize,
+	/// Time spent in the pool by the oldest future transaction, `None` if the queue is empty.
+	pub oldest_age: Option<Duration>,
+}
+
+/// The source of the transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TransactionSource {
+	/// Transaction is already included in a block.
+	///
+	/// E.g. it's being resubmitted from a retracted block.
+	InBlock,
+	/// Transaction is coming from a local source, e.g. RPC or offchain worker.
+	Local,
+	/// Transaction has been received externally, e.g. over the network.
+	External,
+}
+
+/// Number of transactions in the pool per source.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SourceCounts {
+	/// Transactions submitted locally.
+	pub local: usize,
+	/// Transactions received from the network or RPC.
+	pub external: usize,
+	/// Transactions resubmitted from retracted blocks.
+	pub in_block: usize,
+}
+
+impl SourceCounts {
+	fn get_mut(&mut self, source: TransactionSource) -> &mut usize {
+		match source {
+			TransactionSource::Local => &mut self.local,
+			TransactionSource::External => &mut self.external,
+			TransactionSource::InBlock => &mut self.in_block,
+		}
+	}
+
+	/// Accounts for a transaction from given source entering the pool.
+	pub fn add(&mut self, source: TransactionSource) {
+		*self.get_mut(source) += 1;
+	}
+
+	/// Accounts for a transaction from given source leaving the pool.
+	pub fn remove(&mut self, source: TransactionSource) {
+		let count = self.get_mut(source);
+		*count = count.saturating_sub(1);
+	}
+
+	/// Returns the sum of both counts.
+	pub fn combine(self, other: SourceCounts) -> SourceCounts {
+		SourceCounts {
+			local: self.local + other.local,
+			external: self.external + other.external,
+			in_block: self.in_block + other.in_block,
+		}
+	}
+}
+
 /// Possible transaction status events.
 ///
 /// This events are being emitted by `TransactionPool` watchers,
@@ -95,11 +165,20 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	Future,
 	/// Transaction is part of the ready queue.
 	Ready,
+	/// Transaction has been moved from the future queue to disk.
+	///
+	/// It's reported as `Future` or `Ready` again once its requirements get satisfied.
+	Spilled,
 	/// The transaction has been broadcast to the given peers.
 	Broadcast(Vec<String>),
 	/// Transaction has been included in block with given hash.
 	#[serde(rename = "finalized")] // See #4438
 	InBlock(BlockHash),
+	/// The block the transaction has been included in hasn't been finalized
+	/// within the configured number of blocks, the pool stops watching it.
+	///
+	/// Only sent by pools that follow finality.
+	FinalityTimeout(BlockHash),
 	/// Transaction has been replaced in the pool, by another transaction
 	/// that provides the same tags. (e.g. same (sender, nonce)).
 	Usurped(Hash),
@@ -107,6 +186,34 @@ pub enum TransactionStatus<Hash, BlockHash> {
 	Dropped,
 	/// Transaction is no longer valid in the current state.
 	Invalid,
+	/// Approximate position of the transaction in the ready queue.
+	///
+	/// Only sent to watchers that opted in (see `submit_and_watch_with_position`).
+	/// Position is 1-based and follows the current ready set ordering, it's
+	/// updated on every pool maintenance and is not consensus-relevant in any way.
+	Queued {
+		/// Position of the transaction in the ready queue.
+		position: usize,
+		/// Total number of ready transactions.
+		total: usize,
+	},
+}
+
+impl<Hash, BlockHash> TransactionStatus<Hash, BlockHash> {
+	/// Returns `true` if no more events follow this one.
+	///
+	/// Terminal statuses are `InBlock`, `FinalityTimeout`, `Usurped`, `Dropped` and `Invalid`.
+	/// Pools that follow finality might still report `FinalityTimeout` after `InBlock`.
+	pub fn is_terminal(&self) -> bool {
+		match self {
+			TransactionStatus::InBlock(_)
+				| TransactionStatus::FinalityTimeout(_)
+				| TransactionStatus::Usurped(_)
+				| TransactionStatus::Dropped
+				| TransactionStatus::Invalid => true,
+			_ => false,
+		}
+	}
 }
 
 /// The stream of transaction events.
@@ -115,6 +222,47 @@ pub type TransactionStatusStream<Hash, BlockHash> = dyn Stream<Item=TransactionS
 /// The import notification event stream.
 pub type ImportNotificationStream<H> = mpsc::UnboundedReceiver<H>;
 
+/// The stream of status events of all transactions in the pool.
+pub type AllStatusStream<Hash, BlockHash> = mpsc::UnboundedReceiver<(Hash, TransactionStatus<Hash, BlockHash>)>;
+
+/// Changes of the ready set since the previous notification.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadyDiff<Hash> {
+	/// Transactions that entered the ready set.
+	pub added: Vec<Hash>,
+	/// Transactions that left the ready set.
+	pub removed: Vec<Hash>,
+}
+
+/// The ready set changes stream.
+pub type ReadyDiffStream<H> = mpsc::UnboundedReceiver<ReadyDiff<H>>;
+
+/// The pool emptiness changes stream.
+///
+/// Yields `true` when the pool becomes empty and `false` when it becomes non-empty.
+pub type EmptinessStream = mpsc::UnboundedReceiver<bool>;
+
+/// Usage of the pool capacity.
+///
+/// Levels are ordered, `Full` means that any of the pool limits has been reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PressureLevel {
+	/// Usage below the medium threshold.
+	Low,
+	/// Usage between the medium and the high threshold.
+	Medium,
+	/// Usage above the high threshold, but below the limits.
+	High,
+	/// Any of the limits has been reached.
+	Full,
+}
+
+/// The pool pressure level changes stream.
+pub type PressureStream = mpsc::UnboundedReceiver<PressureLevel>;
+
+/// Weight (execution cost) of a transaction as estimated by the pool.
+pub type TransactionWeight = u64;
+
 /// Transaction hash type for a pool.
 pub type TxHash<P> = <P as TransactionPool>::Hash;
 /// Block hash type for a pool.
@@ -151,6 +299,12 @@ pub trait InPoolTransaction {
 	fn provides(&self) -> &[TransactionTag];
 	/// Return a flag indicating if the transaction should be propagated to other peers.
 	fn is_propagateable(&self) -> bool;
+	/// Get time the runtime validation of the transaction took.
+	///
+	/// Zero if the pool doesn't measure it.
+	fn validation_time(&self) -> Duration {
+		Duration::default()
+	}
 }
 
 /// Transaction pool interface.
@@ -165,7 +319,7 @@ pub trait TransactionPool: Send + Sync {
 		Hash = TxHash<Self>
 	>;
 	/// Error type.
-	type Error: From<crate::error::Error> + crate::error::IntoPoolError;
+	type Error: From<crate::error::Error> + crate::error::IntoPoolError + 'static;
 
 	// *** RPC
 
@@ -184,17 +338,197 @@ pub trait TransactionPool: Send + Sync {
 	) -> PoolFuture<TxHash<Self>, Self::Error>;
 
 	/// Returns a future that import a single transaction and starts to watch their progress in the pool.
+	///
+	/// On successful import the stream yields exactly one initial placement event
+	/// (`Ready` or `Future`) before any other event, even if the transaction gets
+	/// pruned or dropped right after the import.
 	fn submit_and_watch(
 		&self,
 		at: &BlockId<Self::Block>,
 		xt: TransactionFor<Self>,
 	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error>;
 
+	/// Same as `submit_and_watch`, but on behalf of the submitter identified by `source_id`.
+	///
+	/// The submitter (e.g. RPC connection) may be limited in the number of watchers
+	/// alive at the same time. By default the submitter is not taken into account.
+	fn submit_and_watch_from_source(
+		&self,
+		at: &BlockId<Self::Block>,
+		source_id: String,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error> {
+		let _ = source_id;
+		self.submit_and_watch(at, xt)
+	}
+
+	/// Same as `submit_and_watch`, but the stream also includes approximate
+	/// position of the transaction in the ready queue (`TransactionStatus::Queued`).
+	///
+	/// Pools that don't track positions return the same stream as `submit_and_watch`.
+	fn submit_and_watch_with_position(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error> {
+		self.submit_and_watch(at, xt)
+	}
+
+	/// Same as `submit_and_watch`, but the stream yields only the terminal status of the
+	/// transaction (see `TransactionStatus::is_terminal`).
+	fn submit_and_watch_terminal_only(
+		&self,
+		at: &BlockId<Self::Block>,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<Box<TransactionStatusStreamFor<Self>>, Self::Error> {
+		Box::pin(self.submit_and_watch(at, xt).map_ok(|stream| {
+			let terminal = stream.filter(|status| future::ready(status.is_terminal()));
+			Box::new(Box::pin(terminal)) as Box<TransactionStatusStreamFor<Self>>
+		}))
+	}
+
+	/// Returns a future that imports a bundle of transactions identified by `bundle_id`.
+	///
+	/// Members of the bundle are returned by `next_block_candidates` all together or not
+	/// at all. Nothing is imported if any of the transactions is not valid.
+	fn submit_bundle(
+		&self,
+		at: &BlockId<Self::Block>,
+		xts: Vec<TransactionFor<Self>>,
+		bundle_id: [u8; 32],
+	) -> PoolFuture<Vec<TxHash<Self>>, Self::Error> {
+		let _ = (at, xts, bundle_id);
+		Box::pin(future::ready(Err(crate::error::Error::Unsupported.into())))
+	}
+
+	/// Returns a future that validates a transaction without importing it to the pool.
+	///
+	/// The validity is returned exactly as reported by the runtime, but the source-dependent
+	/// policies of the pool (e.g. minimal priority that local transactions are exempt from)
+	/// are applied as if the transaction was submitted from `source`.
+	fn compute_validity(
+		&self,
+		at: &BlockId<Self::Block>,
+		source: TransactionSource,
+		xt: TransactionFor<Self>,
+	) -> PoolFuture<TransactionValidity, Self::Error> {
+		let _ = (at, source, xt);
+		Box::pin(future::ready(Err(crate::error::Error::Unsupported.into())))
+	}
+
 	// *** Block production / Networking
 	/// Get an iterator for ready transactions ordered by priority
 	fn ready(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>>;
 
+	/// Get an iterator for ready transactions ordered by priority per encoded byte.
+	///
+	/// Reflects the value of the transactions for block space better than `ready`,
+	/// transactions are still yielded after the ones they depend on. By default
+	/// the transactions are yielded in the `ready` order.
+	fn ready_by_fee_density(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		self.ready()
+	}
+
+	/// Get ready transactions shuffled deterministically by given seed (e.g. a block hash).
+	///
+	/// Allows fair selection of transactions regardless of their priority,
+	/// transactions are still returned after the ones they depend on. By default
+	/// the transactions are returned in the `ready` order.
+	fn ready_shuffled(&self, seed: [u8; 32]) -> Vec<Arc<Self::InPoolTransaction>> {
+		let _ = seed;
+		self.ready().collect()
+	}
+
+	/// Get a page of up to `limit` ready transactions following the one at `cursor`.
+	///
+	/// Returns the transactions in the same order as `ready`, together with the cursor
+	/// of the next page (`None` if it's the last one). The order is only stable while
+	/// the pool is not modified. If the transaction at the cursor left the ready queue
+	/// in the meantime, an empty page without a cursor is returned.
+	fn ready_page(
+		&self,
+		cursor: Option<TxHash<Self>>,
+		limit: usize,
+	) -> (Vec<Arc<Self::InPoolTransaction>>, Option<TxHash<Self>>) {
+		let mut ready = self.ready();
+		if let Some(cursor) = cursor {
+			if !ready.any(|tx| *tx.hash() == cursor) {
+				return (Vec::new(), None);
+			}
+		}
+
+		let page = ready.by_ref().take(limit).collect::<Vec<_>>();
+		let next = match (page.last(), ready.next()) {
+			(Some(last), Some(_)) => Some(last.hash().clone()),
+			_ => None,
+		};
+		(page, next)
+	}
+
+	// *** Networking
+	/// Get an iterator for ready transactions that should be propagated to other peers,
+	/// ordered by priority.
+	fn ready_propagable(&self) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.ready().filter(|tx| tx.is_propagateable()))
+	}
+
 	// *** Block production
+	/// Get an iterator for at most `max` best ready transactions, ordered by priority.
+	fn ready_limited(&self, max: usize) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		Box::new(self.ready().take(max))
+	}
+
+	/// Get an iterator for the best ready transactions which total weight doesn't exceed `max`.
+	///
+	/// Pools that don't estimate weight consider all transactions weightless.
+	fn ready_within_weight(
+		&self,
+		max: TransactionWeight,
+	) -> Box<dyn Iterator<Item=Arc<Self::InPoolTransaction>>> {
+		let _ = max;
+		self.ready()
+	}
+
+	/// Returns the best ready transactions a block would be built from, given its size
+	/// and weight budget.
+	///
+	/// Transactions are ordered by priority and dependencies, the first transaction exceeding
+	/// any of the budgets ends the list. Bundles (see `submit_bundle`) are included as a whole
+	/// or not at all. By default only the size budget is enforced.
+	fn next_block_candidates(
+		&self,
+		max_bytes: usize,
+		max_weight: TransactionWeight,
+	) -> Vec<Arc<Self::InPoolTransaction>> {
+		let _ = max_weight;
+		let mut total_bytes = 0;
+		self.ready()
+			.take_while(|tx| {
+				total_bytes += tx.data().encoded_size();
+				total_bytes <= max_bytes
+			})
+			.collect()
+	}
+
+	/// Takes up to `limit` best ready transactions out of the ready set for external block building.
+	///
+	/// Reserved transactions are not dropped, but they (and transactions depending on them)
+	/// are no longer returned by `ready` until released with `release_reserved`, which
+	/// prevents including them twice. Once included, they are pruned as usual.
+	/// Pools that don't support reservations reserve nothing.
+	fn reserve_ready(&self, limit: usize) -> Vec<Arc<Self::InPoolTransaction>> {
+		let _ = limit;
+		Vec::new()
+	}
+
+	/// Returns reserved transactions back to the ready set.
+	///
+	/// Returns number of transactions that were reserved.
+	fn release_reserved(&self, hashes: &[TxHash<Self>]) -> usize {
+		let _ = hashes;
+		0
+	}
+
 	/// Remove transactions identified by given hashes (and dependent transactions) from the pool.
 	fn remove_invalid(&self, hashes: &[TxHash<Self>]) -> Vec<Arc<Self::InPoolTransaction>>;
 
@@ -202,10 +536,73 @@ pub trait TransactionPool: Send + Sync {
 	/// Returns pool status.
 	fn status(&self) -> PoolStatus;
 
+	/// Returns aggregate statistics of the future queue.
+	///
+	/// By default only the count and size from `status` are known.
+	fn future_status(&self) -> FutureStatus {
+		let status = self.status();
+		FutureStatus {
+			count: status.future,
+			bytes: status.future_bytes,
+			distinct_senders: 0,
+			oldest_age: None,
+		}
+	}
+
+	/// Returns number of transactions in the pool per source.
+	///
+	/// Pools that don't track sources of transactions return zero counts.
+	fn count_by_source(&self) -> SourceCounts {
+		SourceCounts::default()
+	}
+
+	/// Returns up to `limit` pending transactions (ready and future) that were submitted the earliest.
+	///
+	/// Pools that don't track submission time return no transactions.
+	fn oldest_pending(&self, limit: usize) -> Vec<Arc<Self::InPoolTransaction>> {
+		let _ = limit;
+		Vec::new()
+	}
+
+	/// Returns transactions invalidated during revalidation together with the reason.
+	///
+	/// Empty unless the pool is configured to quarantine invalid transactions.
+	fn quarantined(&self) -> Vec<(Arc<Self::InPoolTransaction>, String)> {
+		Vec::new()
+	}
+
 	// *** logging / RPC / networking
 	/// Return an event stream of transactions imported to the pool.
 	fn import_notification_stream(&self) -> ImportNotificationStream<TxHash<Self>>;
 
+	/// Return a stream of ready set changes.
+	///
+	/// A diff is emitted after each maintenance and after submissions or removals
+	/// which changed the ready set. Pools that don't report changes return a stream that ends
+	/// right away, as do the other notification streams below.
+	fn ready_diff_stream(&self) -> ReadyDiffStream<TxHash<Self>> {
+		mpsc::unbounded().1
+	}
+
+	/// Return a stream of transitions between empty and non-empty pool.
+	fn emptiness_stream(&self) -> EmptinessStream {
+		mpsc::unbounded().1
+	}
+
+	/// Return a stream of transitions between pool pressure levels.
+	///
+	/// Consumers like the network layer may accept less gossip while the pressure is high.
+	fn pressure_stream(&self) -> PressureStream {
+		mpsc::unbounded().1
+	}
+
+	/// Return a stream of status events of all transactions in the pool.
+	///
+	/// Unlike individual watchers, it doesn't include queue position updates.
+	fn all_status_stream(&self) -> AllStatusStream<TxHash<Self>, BlockHash<Self>> {
+		mpsc::unbounded().1
+	}
+
 	// *** networking
 	/// Notify the pool about transactions broadcast.
 	fn on_broadcasted(&self, propagations: HashMap<TxHash<Self>, Vec<String>>);
@@ -215,6 +612,49 @@ pub trait TransactionPool: Send + Sync {
 
 	/// Return specific ready transaction by hash, if there is one.
 	fn ready_transaction(&self, hash: &TxHash<Self>) -> Option<Arc<Self::InPoolTransaction>>;
+
+	/// Returns SCALE-encoded transaction (ready or future) by hash, if it's in the pool.
+	///
+	/// By default only ready transactions are found.
+	fn encoded_of(&self, hash: &TxHash<Self>) -> Option<Vec<u8>> {
+		self.ready_transaction(hash).map(|tx| tx.data().encode())
+	}
+
+	/// Returns encoded size of transaction (ready or future) by hash, if it's in the pool.
+	///
+	/// Unlike `encoded_of`, the transaction is not encoded again (unless the default is used).
+	fn encoded_size_of(&self, hash: &TxHash<Self>) -> Option<usize> {
+		self.ready_transaction(hash).map(|tx| tx.data().encoded_size())
+	}
+
+	/// Returns true if transaction with given hash might have been seen recently.
+	///
+	/// Covers submitted, pruned and banned transactions. Might return false positives,
+	/// but never misses recently seen hashes. Pools that don't remember seen hashes
+	/// always return `true`.
+	fn maybe_seen(&self, hash: &TxHash<Self>) -> bool {
+		let _ = hash;
+		true
+	}
+
+	/// Returns total value committed by pending transactions providing a tag with given prefix.
+	///
+	/// Meant for wallets to avoid overspending an account. The value is only known if the
+	/// pool is configured with a value extractor, it's `0` otherwise.
+	fn committed_value_for(&self, provides_prefix: &[u8]) -> u128 {
+		let _ = provides_prefix;
+		0
+	}
+
+	/// Returns all transactions in the pool together with their required and provided tags.
+	///
+	/// Meant for debugging of dependencies between transactions. By default only ready
+	/// transactions are returned.
+	fn tag_graph(&self) -> Vec<(TxHash<Self>, Vec<TransactionTag>, Vec<TransactionTag>)> {
+		self.ready()
+			.map(|tx| (tx.hash().clone(), tx.requires().to_vec(), tx.provides().to_vec()))
+			.collect()
+	}
 }
 
 /// Trait for transaction pool maintenance.
@@ -222,6 +662,14 @@ pub trait MaintainedTransactionPool : TransactionPool {
 	/// Perform maintenance
 	fn maintain(&self, block: &BlockId<Self::Block>, retracted: &[BlockHash<Self>])
 		-> Pin<Box<dyn Future<Output=()> + Send>>;
+
+	/// Notify the pool about finalization of given block.
+	///
+	/// Pools that don't follow finality ignore it.
+	fn on_block_finalized(&self, block: BlockHash<Self>) -> Pin<Box<dyn Future<Output=()> + Send>> {
+		let _ = block;
+		Box::pin(future::ready(()))
+	}
 }
 
 /// An abstraction for transaction pool.